@@ -0,0 +1,298 @@
+use std::collections::BTreeMap;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use config::{Config, Map, Source, Value, ValueKind};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::layer::{flatten_into, LayerSource};
+use crate::provenance::value_origin;
+use crate::{Layer, SettingsError};
+
+/// Statement type of the attestation, following the in-toto attestation framework.
+pub const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+
+/// Predicate type describing the settings inputs of an effective configuration.
+pub const PREDICATE_TYPE: &str = "https://github.com/dmrolfs/settings-loader-rs/attestation/settings/v1";
+
+/// Payload type of the signed envelope, following DSSE.
+pub const PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+
+/// Signs attestation envelopes; e.g., with a key held by a KMS or a signing service.
+pub trait AttestationSigner {
+    fn key_id(&self) -> &str;
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, SettingsError>;
+}
+
+/// Signs with HMAC-SHA256 using a key shared with the pipeline verifying the attestation.
+#[derive(Clone)]
+pub struct HmacSha256Signer {
+    key_id: String,
+    key: Vec<u8>,
+}
+
+impl std::fmt::Debug for HmacSha256Signer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HmacSha256Signer").field("key_id", &self.key_id).finish()
+    }
+}
+
+impl HmacSha256Signer {
+    pub fn new(key_id: impl Into<String>, key: impl Into<Vec<u8>>) -> Self {
+        Self { key_id: key_id.into(), key: key.into() }
+    }
+
+    fn mac(&self, message: &[u8]) -> Result<Hmac<Sha256>, SettingsError> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).map_err(|err| SettingsError::AttestationSigning {
+            key_id: self.key_id.clone(),
+            message: err.to_string(),
+        })?;
+        mac.update(message);
+        Ok(mac)
+    }
+
+    /// Whether each of the envelope's signatures by this signer's key is valid.
+    pub fn verify(&self, envelope: &Envelope) -> bool {
+        let Ok(payload) = BASE64.decode(&envelope.payload) else {
+            return false;
+        };
+        let message = pre_authentication_encoding(&envelope.payload_type, &payload);
+        let mut signatures = envelope.signatures.iter().filter(|s| s.keyid == self.key_id).peekable();
+        signatures.peek().is_some()
+            && signatures.all(|s| {
+                BASE64
+                    .decode(&s.sig)
+                    .is_ok_and(|sig| self.mac(&message).is_ok_and(|mac| mac.verify_slice(&sig).is_ok()))
+            })
+    }
+}
+
+impl AttestationSigner for HmacSha256Signer {
+    fn key_id(&self) -> &str {
+        self.key_id.as_str()
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, SettingsError> {
+        Ok(self.mac(message)?.finalize().into_bytes().to_vec())
+    }
+}
+
+/// A configuration input of the effective configuration; i.e., one of its layers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Material {
+    pub layer: Layer,
+    pub uri: String,
+    pub digest: BTreeMap<String, String>,
+}
+
+/// An attestation describing exactly which configuration inputs produced an effective
+/// configuration, for supply-chain and audit pipelines that consume in-toto style attestations.
+///
+/// The statement's subject is the effective configuration, identified by its SHA-256 digest. Its
+/// predicate lists each layer as a material with the digest of the layer's settings, along with a
+/// provenance map naming the origin of each setting. Setting values are not included, although
+/// digests of small layers, such as a secrets file, may be guessable; attest to the secrets layer
+/// only if that is acceptable.
+///
+/// Digests are computed over the settings rendered as JSON with sorted keys, so they are stable
+/// across file formats and formatting changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attestation {
+    subject: String,
+    digest: String,
+    materials: Vec<Material>,
+    provenance: BTreeMap<String, String>,
+}
+
+impl Attestation {
+    /// Describes the configuration merged from the layers, naming the subject; e.g., after the
+    /// deployment.
+    pub fn new(subject: impl Into<String>, config: &Config, layers: &[LayerSource]) -> Result<Self, SettingsError> {
+        let mut materials = Vec::with_capacity(layers.len());
+        let mut layer_settings = Vec::with_capacity(layers.len());
+        for layer in layers {
+            materials.push(Material {
+                layer: layer.layer(),
+                uri: layer.origin().to_string(),
+                digest: sha256_digest(layer.collect()?)?,
+            });
+            layer_settings.push((layer.origin().to_string(), layer.collect_flattened()?));
+        }
+
+        let table = config.collect()?;
+        let mut flattened = BTreeMap::new();
+        flatten_into(None, table.clone(), &mut flattened);
+        let provenance = flattened
+            .iter()
+            .filter_map(|(key, value)| value_origin(&layer_settings, key, value).map(|origin| (key.clone(), origin)))
+            .collect();
+
+        let digest = sha256_digest(table)?.remove("sha256").unwrap_or_default();
+        Ok(Self {
+            subject: subject.into(),
+            digest,
+            materials,
+            provenance,
+        })
+    }
+
+    /// SHA-256 digest of the effective configuration, hex encoded.
+    pub const fn digest(&self) -> &str {
+        self.digest.as_str()
+    }
+
+    pub fn materials(&self) -> &[Material] {
+        &self.materials
+    }
+
+    /// The origin of each setting by its dotted key.
+    pub const fn provenance(&self) -> &BTreeMap<String, String> {
+        &self.provenance
+    }
+
+    /// The in-toto statement describing the effective configuration.
+    pub fn statement(&self) -> serde_json::Value {
+        serde_json::json!({
+            "_type": STATEMENT_TYPE,
+            "subject": [{ "name": self.subject, "digest": { "sha256": self.digest } }],
+            "predicateType": PREDICATE_TYPE,
+            "predicate": {
+                "materials": self.materials,
+                "provenance": self.provenance,
+            },
+        })
+    }
+
+    /// Signs the statement, wrapping it in a DSSE envelope.
+    pub fn sign(&self, signer: &dyn AttestationSigner) -> Result<Envelope, SettingsError> {
+        let payload = serde_json::to_vec(&self.statement()).map_err(std::io::Error::other)?;
+        let signature = signer.sign(&pre_authentication_encoding(PAYLOAD_TYPE, &payload))?;
+        Ok(Envelope {
+            payload_type: PAYLOAD_TYPE.to_string(),
+            payload: BASE64.encode(payload),
+            signatures: vec![Signature {
+                keyid: signer.key_id().to_string(),
+                sig: BASE64.encode(signature),
+            }],
+        })
+    }
+}
+
+/// A DSSE envelope holding the base64 encoded statement and its signatures.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Envelope {
+    pub payload_type: String,
+    pub payload: String,
+    pub signatures: Vec<Signature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Signature {
+    pub keyid: String,
+    pub sig: String,
+}
+
+/// The DSSE pre-authentication encoding of the payload, which is what is signed.
+fn pre_authentication_encoding(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut message = format!("DSSEv1 {} {payload_type} {} ", payload_type.len(), payload.len()).into_bytes();
+    message.extend_from_slice(payload);
+    message
+}
+
+fn sha256_digest(table: Map<String, Value>) -> Result<BTreeMap<String, String>, SettingsError> {
+    let json: serde_json::Value = Value::new(None, ValueKind::Table(table)).try_deserialize()?;
+    let canonical = serde_json::to_vec(&json).map_err(std::io::Error::other)?;
+    Ok(BTreeMap::from([(
+        "sha256".to_string(),
+        hex::encode(Sha256::digest(canonical)),
+    )]))
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn layers(host: &str) -> Vec<LayerSource> {
+        vec![
+            LayerSource::new(
+                Layer::Config,
+                "application.yaml",
+                File::from_str(&format!("database: {{ host: {host}, port: 5432 }}"), FileFormat::Yaml),
+            ),
+            LayerSource::new(
+                Layer::EnvironmentConfig,
+                "production.toml",
+                File::from_str("[database]\nport = 6543", FileFormat::Toml),
+            ),
+        ]
+    }
+
+    fn attest(layers: &[LayerSource]) -> Attestation {
+        let mut builder = Config::builder();
+        for layer in layers {
+            builder = builder.add_source(layer.clone());
+        }
+        assert_ok!(Attestation::new("orders-service", &assert_ok!(builder.build()), layers))
+    }
+
+    #[test]
+    fn test_attestation_statement() {
+        let attestation = attest(&layers("localhost"));
+        assert_eq!(
+            attestation.provenance(),
+            &BTreeMap::from([
+                ("database.host".to_string(), "application.yaml".to_string()),
+                ("database.port".to_string(), "production.toml".to_string()),
+            ])
+        );
+        assert_eq!(attestation.materials().len(), 2);
+        assert_eq!(attestation.materials()[1].layer, Layer::EnvironmentConfig);
+        assert_eq!(attestation.digest().len(), 64);
+
+        let same = attest(&[
+            LayerSource::new(
+                Layer::Config,
+                "application.json",
+                File::from_str(r#"{"database": {"port": 5432, "host": "localhost"}}"#, FileFormat::Json),
+            ),
+            layers("localhost").remove(1),
+        ]);
+        assert_eq!(same.digest(), attestation.digest());
+        assert_eq!(same.materials()[0].digest, attestation.materials()[0].digest);
+        assert_ne!(attest(&layers("db.prod")).digest(), attestation.digest());
+
+        let statement = attestation.statement();
+        assert_eq!(statement["_type"], STATEMENT_TYPE);
+        assert_eq!(statement["subject"][0]["name"], "orders-service");
+        assert_eq!(statement["subject"][0]["digest"]["sha256"], attestation.digest());
+        assert_eq!(statement["predicate"]["materials"][0]["uri"], "application.yaml");
+    }
+
+    #[test]
+    fn test_attestation_signing() {
+        let attestation = attest(&layers("localhost"));
+        let signer = HmacSha256Signer::new("ci-key", b"shared secret".to_vec());
+        let envelope = assert_ok!(attestation.sign(&signer));
+        assert_eq!(envelope.payload_type, PAYLOAD_TYPE);
+        assert_eq!(envelope.signatures[0].keyid, "ci-key");
+        assert!(signer.verify(&envelope));
+
+        let payload: serde_json::Value =
+            assert_ok!(serde_json::from_slice(&assert_ok!(BASE64.decode(&envelope.payload))));
+        assert_eq!(payload, attestation.statement());
+
+        let other = HmacSha256Signer::new("ci-key", b"other secret".to_vec());
+        assert!(!other.verify(&envelope));
+        let mut tampered = envelope;
+        tampered.payload = BASE64.encode(b"{}");
+        assert!(!signer.verify(&tampered));
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::BTreeSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use config::{Config, Source};
+use sha2::{Digest, Sha256};
+
+use crate::diff::{Change, SettingsDiff};
+use crate::layer::{flatten_into, key_pattern_matches, LayerSource};
+use crate::provenance::is_sensitive;
+use crate::{Layer, SettingsError};
+
+/// Placeholder recorded in place of redacted values.
+pub const REDACTED: &str = "<redacted>";
+
+/// Default size at which the audit file is rotated.
+const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Default number of rotated audit files kept.
+const DEFAULT_MAX_FILES: usize = 5;
+
+/// A local JSONL file recording the changes of each settings reload, so operators can later
+/// answer when a value changed and from what.
+///
+/// Each line records the reload's timestamp, a fingerprint of the reloaded configuration, and the
+/// changed keys with their old and new values and origins. Values supplied by the secrets layer,
+/// held by settings named as sensitive (e.g., `password`), or of keys matching the additional
+/// redaction patterns (dotted, as in `KeyRestriction`) are never written. The file is rotated to
+/// `<path>.1`, `<path>.2`, etc., once it exceeds the maximum size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditLog {
+    path: PathBuf,
+    redacted: Vec<String>,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl AuditLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            redacted: Vec::default(),
+            max_bytes: DEFAULT_MAX_BYTES,
+            max_files: DEFAULT_MAX_FILES,
+        }
+    }
+
+    /// Redacts the values of keys matching the pattern, in addition to secrets and sensitive
+    /// settings.
+    pub fn redact(mut self, pattern: impl Into<String>) -> Self {
+        self.redacted.push(pattern.into());
+        self
+    }
+
+    pub fn with_rotation(self, max_bytes: u64, max_files: usize) -> Self {
+        Self { max_bytes, max_files, ..self }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    fn is_redacted(&self, key: &str) -> bool {
+        is_sensitive(key) || self.redacted.iter().any(|pattern| key_pattern_matches(pattern, key))
+    }
+
+    /// Appends an entry recording the diff of a reload to the audit file, given the layers the
+    /// settings were reloaded from so values from the secrets layer are redacted.
+    pub fn record(
+        &self, at: SystemTime, diff: &SettingsDiff, reloaded: &Config, layers: &[LayerSource],
+    ) -> Result<(), SettingsError> {
+        let timestamp_ms = at
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis());
+        let mut secret_origins = BTreeSet::new();
+        let mut secret_keys = BTreeSet::new();
+        for layer in layers.iter().filter(|l| l.layer() == Layer::Secrets) {
+            secret_origins.insert(layer.origin());
+            secret_keys.extend(layer.collect_flattened()?.into_keys());
+        }
+        let display = |key: &str, value: &config::Value| {
+            let from_secrets =
+                secret_keys.contains(key) || value.origin().is_some_and(|origin| secret_origins.contains(origin));
+            let rep = if from_secrets || self.is_redacted(key) {
+                REDACTED.to_string()
+            } else {
+                value.to_string()
+            };
+            serde_json::json!({ "value": rep, "origin": value.origin() })
+        };
+        let changes: Vec<_> = diff
+            .changes()
+            .iter()
+            .map(|c| match c.change {
+                Change::Added { ref new } => {
+                    serde_json::json!({ "key": c.key, "change": "added", "new": display(&c.key, new) })
+                },
+                Change::Removed { ref old } => {
+                    serde_json::json!({ "key": c.key, "change": "removed", "old": display(&c.key, old) })
+                },
+                Change::Changed { ref old, ref new } => serde_json::json!({
+                    "key": c.key, "change": "changed", "old": display(&c.key, old), "new": display(&c.key, new),
+                }),
+            })
+            .collect();
+        let entry = serde_json::json!({
+            "timestamp_ms": timestamp_ms,
+            "fingerprint": fingerprint(reloaded)?,
+            "changes": changes,
+        });
+
+        let mut line = serde_json::to_string(&entry).map_err(std::io::Error::other)?;
+        line.push('\n');
+        self.rotate_if_full(line.len() as u64)?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    fn rotate_if_full(&self, incoming: u64) -> Result<(), SettingsError> {
+        let size = fs::metadata(&self.path).map_or(0, |m| m.len());
+        if size == 0 || size + incoming <= self.max_bytes {
+            return Ok(());
+        }
+
+        let rotated = |n: usize| PathBuf::from(format!("{}.{n}", self.path.display()));
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+            return Ok(());
+        }
+
+        let _ignored = fs::remove_file(rotated(self.max_files));
+        for n in (1..self.max_files).rev() {
+            if rotated(n).exists() {
+                fs::rename(rotated(n), rotated(n + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated(1))?;
+        tracing::info!(path=?self.path, "rotated settings audit log");
+        Ok(())
+    }
+}
+
+/// A fingerprint of the configuration's settings, which identifies the configuration a change
+/// produced without recording its values.
+///
+/// It is the hex SHA-256 digest of the sorted settings, so it is stable across builds and
+/// comparable over the life of the audit log.
+pub fn fingerprint(config: &Config) -> Result<String, SettingsError> {
+    let mut flattened = std::collections::BTreeMap::new();
+    flatten_into(None, config.collect()?, &mut flattened);
+
+    let mut hasher = Sha256::new();
+    for (key, value) in flattened {
+        hasher.update(key.as_bytes());
+        hasher.update([0]);
+        hasher.update(value.to_string().as_bytes());
+        hasher.update([0]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use claim::*;
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn yaml_layer(layer: Layer, origin: &str, yaml: &str) -> LayerSource {
+        LayerSource::new(layer, origin, File::from_str(yaml, FileFormat::Yaml))
+    }
+
+    fn build(layers: &[LayerSource]) -> Config {
+        let mut builder = Config::builder();
+        for layer in layers {
+            builder = builder.add_source(layer.clone());
+        }
+        assert_ok!(builder.build())
+    }
+
+    #[test]
+    fn test_audit_log_records_redacted_changes() {
+        let dir = std::env::temp_dir().join(format!("settings-audit-{}", std::process::id()));
+        assert_ok!(fs::create_dir_all(&dir));
+        let log = AuditLog::new(dir.join("audit.jsonl"))
+            .redact("database.user")
+            .with_rotation(700, 1);
+
+        let old = build(&[
+            yaml_layer(
+                Layer::Config,
+                "application.yaml",
+                "database: { host: localhost, user: app, password: old-pw }",
+            ),
+            yaml_layer(Layer::Secrets, "secrets.yaml", "api: { key: old-key }"),
+        ]);
+        let layers = vec![
+            yaml_layer(
+                Layer::Config,
+                "application.yaml",
+                "database: { host: db.local, user: svc, password: new-pw }",
+            ),
+            yaml_layer(Layer::Secrets, "secrets.yaml", "api: { key: new-key }"),
+        ];
+        let new = build(&layers);
+        let diff = assert_ok!(SettingsDiff::between_configs(&old, &new));
+        let at = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_000);
+        assert_ok!(log.record(at, &diff, &new, &layers));
+
+        let contents = assert_ok!(fs::read_to_string(log.path()));
+        for secret in ["old-pw", "new-pw", "old-key", "new-key", "app", "svc"] {
+            assert!(
+                !contents.contains(&format!("\"{secret}\"")),
+                "{secret} recorded: {contents}"
+            );
+        }
+        let entry: serde_json::Value = assert_ok!(serde_json::from_str(contents.trim()));
+        assert_eq!(entry["timestamp_ms"], 1_700_000_000_000_u64);
+        assert_eq!(entry["fingerprint"], assert_ok!(fingerprint(&new)));
+        let values: Vec<_> = assert_some!(entry["changes"].as_array())
+            .iter()
+            .map(|c| {
+                (
+                    c["key"].as_str().unwrap_or_default(),
+                    c["old"]["value"].as_str().unwrap_or_default(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("api.key", REDACTED),
+                ("database.host", "localhost"),
+                ("database.password", REDACTED),
+                ("database.user", REDACTED),
+            ]
+        );
+
+        assert_ok!(log.record(at, &diff, &new, &layers));
+        assert_eq!(assert_ok!(fs::read_to_string(dir.join("audit.jsonl.1"))), contents);
+        assert_eq!(assert_ok!(fs::read_to_string(log.path())), contents);
+
+        let _ignored = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_fingerprint_is_stable() {
+        let config = build(&[yaml_layer(
+            Layer::Config,
+            "application.yaml",
+            "port: 8080\nhost: localhost",
+        )]);
+        assert_eq!(
+            assert_ok!(fingerprint(&config)),
+            "c5852adde5bfc974257d900c16461ef207d95a4ac0369adbea9098af2a112cf8"
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use config::{ConfigError, Map, Value};
+
+/// Parsed settings of a file, along with the fingerprint of the contents they were parsed from.
+type FingerprintedTable = (u64, Map<String, Value>);
+
+/// Counts of how settings file lookups in a `LayerCache` were served.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LayerCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+/// Caches the parsed settings of files so repeated loads skip re-parsing unchanged files.
+///
+/// Entries are keyed by path and a hash of the file's contents, so a changed file is parsed again
+/// on its next load. Caching is opt-in via `LoadingOptions::layer_cache()`; share one cache across
+/// loads, e.g., in tests or when watching settings files.
+#[derive(Debug, Default)]
+pub struct LayerCache {
+    entries: Mutex<HashMap<PathBuf, FingerprintedTable>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl LayerCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stats(&self) -> LayerCacheStats {
+        LayerCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.lock().map_or(0, |entries| entries.len()),
+        }
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+
+    /// Returns the cached settings for the file if its fingerprint is unchanged, otherwise parses
+    /// and caches them.
+    pub(crate) fn get_or_parse(
+        &self, path: &Path, fingerprint: impl Hash, parse: impl FnOnce() -> Result<Map<String, Value>, ConfigError>,
+    ) -> Result<Map<String, Value>, ConfigError> {
+        let mut hasher = DefaultHasher::new();
+        fingerprint.hash(&mut hasher);
+        let fingerprint = hasher.finish();
+
+        let cached = self.entries.lock().ok().and_then(|entries| {
+            entries
+                .get(path)
+                .filter(|(f, _)| *f == fingerprint)
+                .map(|(_, table)| table.clone())
+        });
+        if let Some(table) = cached {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            tracing::debug!(?path, "using cached settings layer");
+            return Ok(table);
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let table = parse()?;
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(path.to_path_buf(), (fingerprint, table.clone()));
+        }
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_layer_cache_reparses_changed_files() {
+        let cache = LayerCache::new();
+        let path = Path::new("resources/application.yaml");
+        let parse = |value: &str| {
+            let value = value.to_string();
+            move || {
+                let mut table = Map::new();
+                table.insert("foo".to_string(), Value::from(value));
+                Ok(table)
+            }
+        };
+
+        assert_ok!(cache.get_or_parse(path, "foo: bar", parse("bar")));
+        let cached = assert_ok!(cache.get_or_parse(path, "foo: bar", parse("ignored")));
+        assert_eq!(assert_some!(cached.get("foo")).to_string(), "bar");
+        let changed = assert_ok!(cache.get_or_parse(path, "foo: zed", parse("zed")));
+        assert_eq!(assert_some!(changed.get("foo")).to_string(), "zed");
+
+        assert_eq!(cache.stats(), LayerCacheStats { hits: 1, misses: 2, entries: 1 });
+        cache.clear();
+        assert_eq!(cache.stats().entries, 0);
+    }
+}
@@ -0,0 +1,28 @@
+use std::fmt::Debug;
+use std::time::SystemTime;
+
+/// Source of the timestamps recorded while loading settings. Overriding
+/// `LoadingOptions::clock()` with a `FixedClock` makes timestamps deterministic in tests.
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// The system's wall clock; used unless `LoadingOptions::clock()` is overridden.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock that always reports the same time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(pub SystemTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
@@ -0,0 +1,202 @@
+use std::fmt;
+
+use config::{Map, Value, ValueKind};
+
+use crate::common::de::{parse_byte_size, parse_duration};
+use crate::layer::key_pattern_matches;
+use crate::provenance::is_sensitive;
+use crate::SettingsError;
+
+/// The type a setting's string value is converted to before deserialization.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ValueType {
+    Integer,
+    Float,
+    /// `true`/`false`, `yes`/`no`, `on`/`off` or `1`/`0`, ignoring case.
+    Boolean,
+    /// A human-readable duration, e.g., `30s`, converted to the form `std::time::Duration`
+    /// deserializes from.
+    Duration,
+    /// A human-readable byte size, e.g., `64MiB`, converted to the number of bytes.
+    ByteSize,
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Integer => "an integer",
+            Self::Float => "a number",
+            Self::Boolean => "a boolean",
+            Self::Duration => "a duration",
+            Self::ByteSize => "a byte size",
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// Declares the type of the settings matching a dotted key pattern.
+///
+/// String values of the matching settings, such as those from environment variables, are
+/// converted to the type before deserialization. A `*` segment matches any single key segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coercion {
+    pattern: String,
+    value_type: ValueType,
+}
+
+impl Coercion {
+    pub fn new(pattern: impl Into<String>, value_type: ValueType) -> Self {
+        Self { pattern: pattern.into(), value_type }
+    }
+
+    pub const fn pattern(&self) -> &str {
+        self.pattern.as_str()
+    }
+
+    pub const fn value_type(&self) -> ValueType {
+        self.value_type
+    }
+
+    fn matches(&self, key: &str) -> bool {
+        key_pattern_matches(&self.pattern, key) && self.pattern.split('.').count() == key.split('.').count()
+    }
+}
+
+/// Converts the string values of the settings matching the coercions to their declared types.
+/// Values that are not strings are left as they are, to be checked by deserialization.
+pub fn coerce_values(table: Map<String, Value>, coercions: &[Coercion]) -> Result<Map<String, Value>, SettingsError> {
+    let mut table = table;
+    coerce_table(None, &mut table, coercions)?;
+    Ok(table)
+}
+
+fn coerce_table(
+    prefix: Option<&str>, table: &mut Map<String, Value>, coercions: &[Coercion],
+) -> Result<(), SettingsError> {
+    for (key, value) in table.iter_mut() {
+        let path = prefix.map_or_else(|| key.clone(), |p| format!("{p}.{key}"));
+        if let ValueKind::Table(ref mut nested) = value.kind {
+            coerce_table(Some(&path), nested, coercions)?;
+        } else if let Some(coercion) = coercions.iter().find(|c| c.matches(&path)) {
+            coerce_value(&path, value, coercion.value_type)?;
+        }
+    }
+    Ok(())
+}
+
+fn coerce_value(path: &str, value: &mut Value, value_type: ValueType) -> Result<(), SettingsError> {
+    match value.kind {
+        ValueKind::Array(ref mut items) => {
+            for item in items.iter_mut() {
+                coerce_value(path, item, value_type)?;
+            }
+            Ok(())
+        },
+        ValueKind::String(ref rep) => {
+            let kind = coerce_str(rep.trim(), value_type).ok_or_else(|| SettingsError::Deserialization {
+                key: path.to_string(),
+                value: if is_sensitive(path) { None } else { Some(rep.clone()) },
+                origin: value.origin().map(String::from),
+                layer: None,
+                message: format!("expected {value_type}"),
+            })?;
+            value.kind = kind;
+            Ok(())
+        },
+        _ => Ok(()),
+    }
+}
+
+fn coerce_str(rep: &str, value_type: ValueType) -> Option<ValueKind> {
+    match value_type {
+        ValueType::Integer => rep.parse().ok().map(ValueKind::I64),
+        ValueType::Float => rep.parse().ok().map(ValueKind::Float),
+        ValueType::Boolean => match rep.to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Some(ValueKind::Boolean(true)),
+            "false" | "no" | "off" | "0" => Some(ValueKind::Boolean(false)),
+            _ => None,
+        },
+        ValueType::Duration => parse_duration(rep).ok().map(|duration| {
+            let mut table = Map::new();
+            table.insert("secs".to_string(), Value::from(duration.as_secs()));
+            table.insert("nanos".to_string(), Value::from(duration.subsec_nanos()));
+            ValueKind::Table(table)
+        }),
+        ValueType::ByteSize => parse_byte_size(rep).ok().map(ValueKind::U64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use claim::*;
+    use config::{Config, File, FileFormat, Source};
+    use pretty_assertions::assert_eq;
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Server {
+        port: u16,
+        tls: bool,
+        timeout: Duration,
+        max_body: u64,
+        ratio: f64,
+        backlog: Vec<u32>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Settings {
+        server: Server,
+    }
+
+    fn table(yaml: &str) -> Map<String, Value> {
+        let config = assert_ok!(Config::builder()
+            .add_source(File::from_str(yaml, FileFormat::Yaml))
+            .build());
+        assert_ok!(config.collect())
+    }
+
+    fn coercions() -> Vec<Coercion> {
+        vec![
+            Coercion::new("*.port", ValueType::Integer),
+            Coercion::new("server.tls", ValueType::Boolean),
+            Coercion::new("server.timeout", ValueType::Duration),
+            Coercion::new("server.max_body", ValueType::ByteSize),
+            Coercion::new("server.ratio", ValueType::Float),
+            Coercion::new("server.backlog", ValueType::Integer),
+        ]
+    }
+
+    #[test]
+    fn test_coerce_values() {
+        let actual = assert_ok!(coerce_values(
+            table(
+                "server: { port: '8080', tls: 'On', timeout: 1m30s, max_body: 1KiB, ratio: '0.5', backlog: ['1', 2] }"
+            ),
+            &coercions(),
+        ));
+        let actual: Settings = assert_ok!(Value::new(None, ValueKind::Table(actual)).try_deserialize());
+        assert_eq!(
+            actual.server,
+            Server {
+                port: 8080,
+                tls: true,
+                timeout: Duration::from_secs(90),
+                max_body: 1024,
+                ratio: 0.5,
+                backlog: vec![1, 2],
+            }
+        );
+
+        let err = assert_err!(coerce_values(table("server: { port: eighty }"), &coercions()));
+        assert_eq!(
+            err.to_string(),
+            "invalid setting server.port = eighty: expected an integer"
+        );
+        assert_ok!(coerce_values(table("server: { port: { nested: '1' } }"), &coercions()));
+        assert_err!(coerce_values(table("server: { tls: maybe }"), &coercions()));
+    }
+}
@@ -0,0 +1,352 @@
+//! Serde helpers reading human-friendly setting values, for use with `serde_with::serde_as`; e.g.,
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use serde::Deserialize;
+//! use serde_with::serde_as;
+//! use settings_loader::common::de::{ByteSize, HumanDuration, Percentage};
+//!
+//! #[serde_as]
+//! #[derive(Deserialize)]
+//! struct CacheSettings {
+//!     #[serde_as(as = "HumanDuration")]
+//!     ttl: Duration,
+//!     #[serde_as(as = "ByteSize")]
+//!     max_size: u64,
+//!     #[serde_as(as = "Percentage")]
+//!     eviction_threshold: f64,
+//! }
+//!
+//! let settings: CacheSettings = serde_json::from_str(
+//!     r#"{ "ttl": "5m", "max_size": "512MiB", "eviction_threshold": "90%" }"#,
+//! )
+//! .unwrap();
+//! assert_eq!(settings.ttl, Duration::from_secs(300));
+//! assert_eq!(settings.max_size, 512 * 1024 * 1024);
+//! assert_eq!(settings.eviction_threshold, 0.9);
+//! ```
+use std::fmt;
+use std::time::Duration;
+
+use serde::de::{self, Visitor};
+use serde::{Deserializer, Serializer};
+use serde_with::{DeserializeAs, SerializeAs};
+
+/// Reads durations written with units, such as `30s`, `5m`, `1h30m` or `250ms`. Bare numbers are
+/// read as seconds. Units are `ns`, `us`, `ms`, `s`, `m`, `h` and `d`.
+#[derive(Debug, Copy, Clone)]
+pub struct HumanDuration;
+
+/// Reads byte sizes written with units, such as `512MiB` or `1GB`, as a number of bytes.
+///
+/// Bare numbers are read as bytes. Decimal units (`KB`, `MB`, `GB`, `TB`) are powers of 1000 and
+/// binary units (`KiB`, `MiB`, `GiB`, `TiB`) powers of 1024.
+#[derive(Debug, Copy, Clone)]
+pub struct ByteSize;
+
+/// Reads percentages, such as `75%`, as a fraction (`0.75`). Bare numbers are read as fractions.
+#[derive(Debug, Copy, Clone)]
+pub struct Percentage;
+
+const DURATION_UNITS: &[(&str, u128)] = &[
+    ("d", 86_400_000_000_000),
+    ("h", 3_600_000_000_000),
+    ("m", 60_000_000_000),
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("ns", 1),
+];
+
+const BYTE_UNITS: &[(&str, u64)] = &[
+    ("b", 1),
+    ("kb", 1_000),
+    ("mb", 1_000_000),
+    ("gb", 1_000_000_000),
+    ("tb", 1_000_000_000_000),
+    ("kib", 1 << 10),
+    ("mib", 1 << 20),
+    ("gib", 1 << 30),
+    ("tib", 1 << 40),
+];
+
+/// Parses a duration such as `1h30m`; see `HumanDuration`.
+pub fn parse_duration(rep: &str) -> Result<Duration, String> {
+    let rep = rep.trim();
+    if let Ok(secs) = rep.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+
+    let mut rest = rep;
+    let mut nanos: u128 = 0;
+    while !rest.is_empty() {
+        let digits_end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let (amount, tail) = rest.split_at(digits_end);
+        let unit_end = tail.find(|c: char| c.is_ascii_digit()).unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_end);
+        let amount: u128 = amount
+            .parse()
+            .map_err(|_| format!("invalid duration {rep:?}: expected an amount before {unit:?}"))?;
+        let (_, scale) = DURATION_UNITS
+            .iter()
+            .find(|(u, _)| *u == unit.trim())
+            .ok_or_else(|| format!("invalid duration {rep:?}: unknown unit {unit:?}"))?;
+        nanos += amount * scale;
+        rest = tail;
+    }
+
+    if rep.is_empty() {
+        return Err("invalid duration: empty".to_string());
+    }
+    let secs = u64::try_from(nanos / 1_000_000_000).map_err(|_| format!("duration {rep:?} is too long"))?;
+    #[allow(clippy::cast_possible_truncation)]
+    Ok(Duration::new(secs, (nanos % 1_000_000_000) as u32))
+}
+
+/// Formats the duration in the largest units that represent it exactly; e.g., `1h30m`.
+pub fn format_duration(duration: Duration) -> String {
+    let mut nanos = duration.as_nanos();
+    if nanos == 0 {
+        return "0s".to_string();
+    }
+
+    let mut rep = String::new();
+    for (unit, scale) in DURATION_UNITS {
+        if *scale <= nanos {
+            rep.push_str(&format!("{}{unit}", nanos / scale));
+            nanos %= scale;
+        }
+    }
+    rep
+}
+
+/// Parses a byte size such as `512MiB`; see `ByteSize`.
+pub fn parse_byte_size(rep: &str) -> Result<u64, String> {
+    let rep = rep.trim();
+    let unit_start = rep.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rep.len());
+    let (amount, unit) = rep.split_at(unit_start);
+    let unit = unit.trim().to_lowercase();
+    let scale = if unit.is_empty() {
+        1
+    } else {
+        BYTE_UNITS
+            .iter()
+            .find(|(u, _)| *u == unit)
+            .map(|(_, scale)| *scale)
+            .ok_or_else(|| format!("invalid byte size {rep:?}: unknown unit {unit:?}"))?
+    };
+
+    if let Ok(amount) = amount.parse::<u64>() {
+        return amount
+            .checked_mul(scale)
+            .ok_or_else(|| format!("byte size {rep:?} is too large"));
+    }
+    let amount: f64 = amount
+        .parse()
+        .map_err(|_| format!("invalid byte size {rep:?}: expected an amount"))?;
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    let bytes = (amount * scale as f64).round() as u64;
+    Ok(bytes)
+}
+
+/// Formats the byte size in the largest binary unit that represents it exactly; e.g., `512MiB`.
+pub fn format_byte_size(bytes: u64) -> String {
+    ["TiB", "GiB", "MiB", "KiB"]
+        .iter()
+        .zip([40, 30, 20, 10])
+        .find(|(_, shift)| bytes != 0 && bytes.is_multiple_of(1 << shift))
+        .map_or_else(
+            || format!("{bytes}B"),
+            |(unit, shift)| format!("{}{unit}", bytes >> shift),
+        )
+}
+
+/// Parses a percentage such as `75%` as a fraction; see `Percentage`.
+pub fn parse_percentage(rep: &str) -> Result<f64, String> {
+    let rep = rep.trim();
+    let (amount, scale) = rep
+        .strip_suffix('%')
+        .map_or((rep, 1.0), |amount| (amount.trim_end(), 100.0));
+    let amount: f64 = amount
+        .parse()
+        .map_err(|_| format!("invalid percentage {rep:?}: expected, e.g., 75%"))?;
+    if !amount.is_finite() || amount < 0.0 {
+        return Err(format!("invalid percentage {rep:?}: must not be negative"));
+    }
+    Ok(amount / scale)
+}
+
+/// Deserializes a value written either as a string, parsed by the helper's parser, or a number.
+struct HumaneVisitor<T> {
+    expecting: &'static str,
+    parse: fn(&str) -> Result<T, String>,
+    from_u64: fn(u64) -> T,
+    from_f64: fn(f64) -> Result<T, String>,
+}
+
+impl<'de, T> Visitor<'de> for HumaneVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.expecting)
+    }
+
+    fn visit_str<E: de::Error>(self, rep: &str) -> Result<T, E> {
+        (self.parse)(rep).map_err(E::custom)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<T, E> {
+        Ok((self.from_u64)(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<T, E> {
+        u64::try_from(value)
+            .map(self.from_u64)
+            .map_err(|_| E::custom(format!("{} must not be negative", self.expecting)))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<T, E> {
+        (self.from_f64)(value).map_err(E::custom)
+    }
+}
+
+fn non_negative(value: f64) -> Result<f64, String> {
+    if value.is_finite() && 0.0 <= value {
+        Ok(value)
+    } else {
+        Err(format!("{value} must not be negative"))
+    }
+}
+
+impl<'de> DeserializeAs<'de, Duration> for HumanDuration {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        deserializer.deserialize_any(HumaneVisitor {
+            expecting: "a duration such as 30s or 5m",
+            parse: parse_duration,
+            from_u64: Duration::from_secs,
+            from_f64: |secs| non_negative(secs).map(Duration::from_secs_f64),
+        })
+    }
+}
+
+impl SerializeAs<Duration> for HumanDuration {
+    fn serialize_as<S: Serializer>(source: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_duration(*source))
+    }
+}
+
+impl<'de> DeserializeAs<'de, u64> for ByteSize {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        deserializer.deserialize_any(HumaneVisitor {
+            expecting: "a byte size such as 512MiB",
+            parse: parse_byte_size,
+            from_u64: |bytes| bytes,
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            from_f64: |bytes| non_negative(bytes).map(|b| b.round() as u64),
+        })
+    }
+}
+
+impl SerializeAs<u64> for ByteSize {
+    fn serialize_as<S: Serializer>(source: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_byte_size(*source))
+    }
+}
+
+impl<'de> DeserializeAs<'de, f64> for Percentage {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        deserializer.deserialize_any(HumaneVisitor {
+            expecting: "a percentage such as 75%",
+            parse: parse_percentage,
+            #[allow(clippy::cast_precision_loss)]
+            from_u64: |fraction| fraction as f64,
+            from_f64: non_negative,
+        })
+    }
+}
+
+impl SerializeAs<f64> for Percentage {
+    fn serialize_as<S: Serializer>(source: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{}%", source * 100.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+    use serde::{Deserialize, Serialize};
+    use serde_with::serde_as;
+
+    use super::*;
+
+    #[serde_as]
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Limits {
+        #[serde_as(as = "HumanDuration")]
+        timeout: Duration,
+        #[serde_as(as = "ByteSize")]
+        max_body: u64,
+        #[serde_as(as = "Percentage")]
+        threshold: f64,
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(assert_ok!(parse_duration("30s")), Duration::from_secs(30));
+        assert_eq!(assert_ok!(parse_duration("1h30m")), Duration::from_secs(5_400));
+        assert_eq!(assert_ok!(parse_duration("250ms")), Duration::from_millis(250));
+        assert_eq!(assert_ok!(parse_duration("2d")), Duration::from_secs(172_800));
+        assert_eq!(assert_ok!(parse_duration("45")), Duration::from_secs(45));
+        assert_err!(parse_duration(""));
+        assert_err!(parse_duration("5 minutes"));
+        assert_err!(parse_duration("ms"));
+        assert_eq!(format_duration(Duration::from_millis(5_400_250)), "1h30m250ms");
+        assert_eq!(format_duration(Duration::ZERO), "0s");
+    }
+
+    #[test]
+    fn test_parse_byte_size_and_percentage() {
+        assert_eq!(assert_ok!(parse_byte_size("512MiB")), 512 * 1024 * 1024);
+        assert_eq!(assert_ok!(parse_byte_size("1GB")), 1_000_000_000);
+        assert_eq!(assert_ok!(parse_byte_size("1.5 KiB")), 1_536);
+        assert_eq!(assert_ok!(parse_byte_size("2048")), 2_048);
+        assert_err!(parse_byte_size("12 parsecs"));
+        assert_eq!(format_byte_size(512 * 1024 * 1024), "512MiB");
+        assert_eq!(format_byte_size(1_000), "1000B");
+
+        assert_eq!(assert_ok!(parse_percentage("75%")), 0.75);
+        assert_eq!(assert_ok!(parse_percentage("0.5")), 0.5);
+        assert_err!(parse_percentage("-5%"));
+        assert_err!(parse_percentage("lots"));
+    }
+
+    #[test]
+    fn test_humane_serde_round_trip() {
+        let yaml = "timeout: 1m30s\nmax_body: 4MiB\nthreshold: 80%";
+        let actual: Limits = assert_ok!(serde_yaml::from_str(yaml));
+        let expected = Limits {
+            timeout: Duration::from_secs(90),
+            max_body: 4 * 1024 * 1024,
+            threshold: 0.8,
+        };
+        assert_eq!(actual, expected);
+
+        let numbers: Limits = assert_ok!(serde_yaml::from_str("timeout: 90\nmax_body: 4194304\nthreshold: 0.8"));
+        assert_eq!(numbers, expected);
+        assert_err!(serde_yaml::from_str::<Limits>(
+            "timeout: -1\nmax_body: 1\nthreshold: 0.8"
+        ));
+
+        let json = assert_ok!(serde_json::to_value(&expected));
+        assert_eq!(
+            json,
+            serde_json::json!({ "timeout": "1m30s", "max_body": "4MiB", "threshold": "80%" })
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use url::{Host, Url};
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HttpServerSettings {
     pub host: String,
     pub port: u16,
@@ -0,0 +1,165 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+use crate::{ConfigFs, SettingsError};
+
+pub const DEFAULT_PORT: u16 = 8000;
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+pub const DEFAULT_KEEP_ALIVE: Duration = Duration::from_secs(75);
+
+/// Settings for an application's HTTP server, defaulting to plain HTTP on all interfaces at port
+/// 8000.
+///
+/// These configure the server the application runs; `common::http::HttpServerSettings` instead
+/// addresses a server by host name, e.g., to build URLs for it.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpServerSettings {
+    pub bind_address: IpAddr,
+
+    pub port: u16,
+
+    pub tls: Option<TlsSettings>,
+
+    #[serde(alias = "request_timeout_secs")]
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    pub request_timeout: Duration,
+
+    #[serde(alias = "shutdown_timeout_secs")]
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    pub shutdown_timeout: Duration,
+
+    /// How long idle connections are kept alive; `None` disables keep-alive.
+    #[serde(alias = "keep_alive_secs")]
+    #[serde_as(as = "Option<serde_with::DurationSeconds<u64>>")]
+    pub keep_alive: Option<Duration>,
+}
+
+impl Default for HttpServerSettings {
+    fn default() -> Self {
+        Self {
+            bind_address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            port: DEFAULT_PORT,
+            tls: None,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            keep_alive: Some(DEFAULT_KEEP_ALIVE),
+        }
+    }
+}
+
+/// Paths of the PEM encoded certificate chain and private key an HTTP server serves TLS with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsSettings {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+impl HttpServerSettings {
+    pub const fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.bind_address, self.port)
+    }
+
+    pub const fn is_tls(&self) -> bool {
+        self.tls.is_some()
+    }
+
+    /// Checks that the timeouts are positive and that any TLS certificate and key files exist.
+    pub fn validate(&self, fs: &dyn ConfigFs) -> Result<(), SettingsError> {
+        let invalid = |setting: &str, message: &str| SettingsError::Bootstrap {
+            message: message.to_string(),
+            setting: setting.to_string(),
+        };
+
+        if self.request_timeout.is_zero() {
+            return Err(invalid(
+                "request_timeout",
+                "HTTP server request timeout must be positive",
+            ));
+        }
+        if self.shutdown_timeout.is_zero() {
+            return Err(invalid(
+                "shutdown_timeout",
+                "HTTP server shutdown timeout must be positive",
+            ));
+        }
+
+        if let Some(ref tls) = self.tls {
+            for (setting, path) in [("tls.cert_path", &tls.cert_path), ("tls.key_path", &tls.key_path)] {
+                if !fs.is_file(path) {
+                    return Err(invalid(setting, &format!("TLS file {} not found", path.display())));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+    use trim_margin::MarginTrimmable;
+
+    use super::*;
+    use crate::fs::MemoryFs;
+
+    #[test]
+    fn test_http_server_defaults() {
+        let actual: HttpServerSettings = assert_ok!(serde_yaml::from_str("{}"));
+        assert_eq!(actual, HttpServerSettings::default());
+        assert_eq!(actual.socket_addr().to_string(), "0.0.0.0:8000");
+        assert!(!actual.is_tls());
+        assert_ok!(actual.validate(&MemoryFs::new()));
+    }
+
+    #[test]
+    fn test_http_server_deser() {
+        let yaml = r##"
+            |bind_address: 127.0.0.1
+            |port: 8443
+            |tls:
+            |  cert_path: certs/server.pem
+            |  key_path: certs/server.key
+            |request_timeout_secs: 10
+            |keep_alive: null
+            |"##
+        .trim_margin()
+        .unwrap();
+
+        let actual: HttpServerSettings = assert_ok!(serde_yaml::from_str(yaml.as_str()));
+        assert_eq!(
+            actual,
+            HttpServerSettings {
+                bind_address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                port: 8443,
+                tls: Some(TlsSettings {
+                    cert_path: PathBuf::from("certs/server.pem"),
+                    key_path: PathBuf::from("certs/server.key"),
+                }),
+                request_timeout: Duration::from_secs(10),
+                shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+                keep_alive: None,
+            }
+        );
+
+        assert_err!(actual.validate(&MemoryFs::new()));
+        let fs = MemoryFs::new()
+            .with_file("certs/server.pem", "cert")
+            .with_file("certs/server.key", "key");
+        assert_ok!(actual.validate(&fs));
+
+        let no_timeout = HttpServerSettings {
+            request_timeout: Duration::ZERO,
+            ..HttpServerSettings::default()
+        };
+        assert_err!(no_timeout.validate(&fs));
+    }
+}
@@ -0,0 +1,196 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::redact::Redacted;
+use crate::SettingsError;
+
+/// Settings for connecting a Kafka (or compatible message broker) client.
+///
+/// The SASL password is held `Redacted`, so it is masked when the settings are printed.
+/// `client_properties()` lists the settings under their librdkafka property names, ready to load
+/// into, e.g., `rdkafka::ClientConfig`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KafkaSettings {
+    pub brokers: Vec<String>,
+
+    #[serde(default)]
+    pub client_id: Option<String>,
+
+    #[serde(default)]
+    pub security_protocol: SecurityProtocol,
+
+    #[serde(default)]
+    pub sasl: Option<SaslSettings>,
+
+    #[serde(default)]
+    pub consumer_group: Option<String>,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityProtocol {
+    #[default]
+    Plaintext,
+    Ssl,
+    SaslPlaintext,
+    SaslSsl,
+}
+
+impl SecurityProtocol {
+    pub const fn is_sasl(&self) -> bool {
+        matches!(self, Self::SaslPlaintext | Self::SaslSsl)
+    }
+}
+
+impl fmt::Display for SecurityProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Plaintext => "plaintext",
+            Self::Ssl => "ssl",
+            Self::SaslPlaintext => "sasl_plaintext",
+            Self::SaslSsl => "sasl_ssl",
+        };
+        write!(f, "{label}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaslSettings {
+    #[serde(default)]
+    pub mechanism: SaslMechanism,
+
+    pub username: String,
+
+    pub password: Redacted<String>,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SaslMechanism {
+    #[default]
+    #[serde(rename = "PLAIN")]
+    Plain,
+    #[serde(rename = "SCRAM-SHA-256")]
+    ScramSha256,
+    #[serde(rename = "SCRAM-SHA-512")]
+    ScramSha512,
+}
+
+impl fmt::Display for SaslMechanism {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Plain => "PLAIN",
+            Self::ScramSha256 => "SCRAM-SHA-256",
+            Self::ScramSha512 => "SCRAM-SHA-512",
+        };
+        write!(f, "{label}")
+    }
+}
+
+impl KafkaSettings {
+    /// The brokers as a `bootstrap.servers` list.
+    pub fn bootstrap_servers(&self) -> String {
+        self.brokers.join(",")
+    }
+
+    /// Checks that brokers are listed and that SASL credentials accompany a SASL security protocol.
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        let invalid = |setting: &str, message: &str| SettingsError::Bootstrap {
+            message: message.to_string(),
+            setting: setting.to_string(),
+        };
+
+        if self.brokers.iter().all(|b| b.trim().is_empty()) {
+            return Err(invalid("brokers", "at least one Kafka broker must be listed"));
+        }
+        match (self.security_protocol.is_sasl(), self.sasl.is_some()) {
+            (true, false) => Err(invalid(
+                "sasl",
+                &format!("SASL credentials are required for {}", self.security_protocol),
+            )),
+            (false, true) => Err(invalid(
+                "security_protocol",
+                "SASL credentials are set but the security protocol does not use SASL",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The settings as librdkafka client properties. The SASL password is exposed in these
+    /// properties, so do not log them.
+    pub fn client_properties(&self) -> Vec<(&'static str, String)> {
+        let mut properties = vec![
+            ("bootstrap.servers", self.bootstrap_servers()),
+            ("security.protocol", self.security_protocol.to_string()),
+        ];
+        if let Some(ref client_id) = self.client_id {
+            properties.push(("client.id", client_id.clone()));
+        }
+        if let Some(ref group) = self.consumer_group {
+            properties.push(("group.id", group.clone()));
+        }
+        if let Some(ref sasl) = self.sasl {
+            properties.push(("sasl.mechanism", sasl.mechanism.to_string()));
+            properties.push(("sasl.username", sasl.username.clone()));
+            properties.push(("sasl.password", sasl.password.expose().clone()));
+        }
+        properties
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+    use trim_margin::MarginTrimmable;
+
+    use super::*;
+
+    #[test]
+    fn test_kafka_deser() {
+        let yaml = r##"
+            |brokers: [kafka-1:9092, kafka-2:9092]
+            |client_id: orders
+            |security_protocol: sasl_ssl
+            |sasl:
+            |  mechanism: SCRAM-SHA-512
+            |  username: orders
+            |  password: hunter2
+            |consumer_group: orders-consumer
+            |"##
+        .trim_margin()
+        .unwrap();
+
+        let actual: KafkaSettings = assert_ok!(serde_yaml::from_str(yaml.as_str()));
+        assert_ok!(actual.validate());
+        assert!(!format!("{actual:?}").contains("hunter2"));
+        assert_eq!(
+            actual.client_properties(),
+            vec![
+                ("bootstrap.servers", "kafka-1:9092,kafka-2:9092".to_string()),
+                ("security.protocol", "sasl_ssl".to_string()),
+                ("client.id", "orders".to_string()),
+                ("group.id", "orders-consumer".to_string()),
+                ("sasl.mechanism", "SCRAM-SHA-512".to_string()),
+                ("sasl.username", "orders".to_string()),
+                ("sasl.password", "hunter2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_kafka_validate() {
+        let plain: KafkaSettings = assert_ok!(serde_yaml::from_str("brokers: [localhost:9092]"));
+        assert_eq!(plain.security_protocol, SecurityProtocol::Plaintext);
+        assert_ok!(plain.validate());
+
+        let no_brokers = KafkaSettings { brokers: Vec::default(), ..plain.clone() };
+        assert_err!(no_brokers.validate());
+
+        let missing_sasl = KafkaSettings {
+            security_protocol: SecurityProtocol::SaslPlaintext,
+            ..plain
+        };
+        assert_err!(missing_sasl.validate());
+    }
+}
@@ -0,0 +1,156 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use config::{ConfigError, Map, Source, Value};
+
+use crate::layer::{insert_nested, LayerSource};
+use crate::Layer;
+
+/// Conventional mount point of a `ConfigMap` volume.
+pub const CONFIG_MOUNT: &str = "/etc/config";
+
+/// Conventional mount point of the application's `Secret` volume.
+pub const SECRETS_MOUNT: &str = "/var/run/secrets/app";
+
+/// Environment variable Kubernetes sets in every container, used to detect running in-cluster.
+const SERVICE_HOST_ENV: &str = "KUBERNETES_SERVICE_HOST";
+
+/// The `ConfigMap` and `Secret` volumes mounted into a pod, loaded as settings layers via
+/// `LoadingOptions::kubernetes_scope()`.
+///
+/// Volumes are read in the "one file per key" projection style: each file holds a single setting
+/// named by its file name, with `.` separating nested keys; e.g., `database.host`. The projected
+/// volume's bookkeeping entries (such as `..data`) are skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KubernetesScope {
+    config_dir: PathBuf,
+    secrets_dir: PathBuf,
+}
+
+impl Default for KubernetesScope {
+    fn default() -> Self {
+        Self::new(CONFIG_MOUNT, SECRETS_MOUNT)
+    }
+}
+
+impl KubernetesScope {
+    pub fn new(config_dir: impl Into<PathBuf>, secrets_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            config_dir: config_dir.into(),
+            secrets_dir: secrets_dir.into(),
+        }
+    }
+
+    /// The scope at the conventional mount points when running in-cluster, otherwise `None`.
+    pub fn detect() -> Option<Self> {
+        std::env::var_os(SERVICE_HOST_ENV).map(|_| Self::default())
+    }
+
+    pub fn config_dir(&self) -> &Path {
+        self.config_dir.as_path()
+    }
+
+    pub fn secrets_dir(&self) -> &Path {
+        self.secrets_dir.as_path()
+    }
+
+    /// The `ConfigMap` volume layer, which takes precedence over the settings files.
+    pub fn config_layer(&self) -> LayerSource {
+        let origin = format!("kubernetes config map {}", self.config_dir.display());
+        LayerSource::new(Layer::EnvironmentConfig, origin, ProjectedVolume::new(&self.config_dir))
+    }
+
+    /// The `Secret` volume layer, which takes precedence over the secrets file.
+    pub fn secrets_layer(&self) -> LayerSource {
+        let origin = format!("kubernetes secret {}", self.secrets_dir.display());
+        LayerSource::new(Layer::Secrets, origin, ProjectedVolume::new(&self.secrets_dir))
+    }
+}
+
+/// A mounted volume holding one setting per file. A volume that is not mounted supplies no
+/// settings.
+#[derive(Debug, Clone)]
+pub struct ProjectedVolume {
+    dir: PathBuf,
+}
+
+impl ProjectedVolume {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn read_settings(&self) -> io::Result<Map<String, Value>> {
+        let mut settings = Map::new();
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                tracing::debug!(dir=?self.dir, "kubernetes volume not mounted");
+                return Ok(settings);
+            },
+            Err(err) => return Err(err),
+        };
+
+        let uri = self.dir.display().to_string();
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with("..") || !entry.path().is_file() {
+                continue;
+            }
+
+            let contents = std::fs::read_to_string(entry.path())?;
+            let value = contents.strip_suffix('\n').unwrap_or(&contents).to_string();
+            insert_nested(&mut settings, &uri, &name, value.into());
+        }
+        Ok(settings)
+    }
+}
+
+impl Source for ProjectedVolume {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        self.read_settings().map_err(|err| ConfigError::Foreign(Box::new(err)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use claim::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_projected_volume() {
+        let dir = std::env::temp_dir().join(format!("settings-kubernetes-{}", std::process::id()));
+        assert_ok!(fs::create_dir_all(dir.join("..2024_01_01_00_00_00.000000000")));
+        assert_ok!(fs::write(dir.join("database.host"), "db.cluster.local\n"));
+        assert_ok!(fs::write(dir.join("database.port"), "5432"));
+        assert_ok!(fs::write(dir.join("foo"), "bar"));
+        assert_ok!(fs::write(dir.join("..data"), "ignored"));
+
+        let scope = KubernetesScope::new(&dir, dir.join("missing"));
+        let config = assert_ok!(scope.config_layer().collect_flattened());
+        let actual: Vec<_> = config.iter().map(|(k, v)| (k.as_str(), v.to_string())).collect();
+        assert_eq!(
+            actual,
+            vec![
+                ("database.host", "db.cluster.local".to_string()),
+                ("database.port", "5432".to_string()),
+                ("foo", "bar".to_string()),
+            ]
+        );
+        assert_eq!(
+            assert_some!(config.get("foo")).origin(),
+            Some(dir.display().to_string().as_str())
+        );
+        assert!(assert_ok!(scope.secrets_layer().collect_flattened()).is_empty());
+
+        let _ignored = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,4 +1,9 @@
 #[cfg(feature = "database")]
 pub mod database;
+pub mod de;
 #[cfg(feature = "http")]
 pub mod http;
+pub mod http_server;
+pub mod kafka;
+#[cfg(feature = "kubernetes")]
+pub mod kubernetes;
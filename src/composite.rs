@@ -0,0 +1,384 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use config::builder::DefaultState;
+use config::ConfigBuilder;
+
+use crate::coerce::Coercion;
+use crate::deprecation::{DeprecatedKey, DeprecationPolicy};
+use crate::permissions::SecretsPermissions;
+use crate::policy::LoadPolicy;
+use crate::required::RequiredKey;
+use crate::{
+    audit, merge, migrations, runtime, Clock, ConfigDir, ConfigFs, EnvVarAlias, EnvVarFilter, Environment,
+    InstanceIdentity, KeyRestriction, LayerCache, LayerSource, LegacyLocation, LoadingOptions, PrecedenceProfile,
+    SecretsProvider, SettingsError, ValueDecryptor,
+};
+
+/// Composes two `LoadingOptions`, e.g., options shared across a workspace's binaries (`base`) and
+/// a binary's own additions (`addition`), so neither has to be merged by hand.
+///
+/// Contributions are merged deterministically:
+/// - lists, such as presets, search paths and key restrictions, are the base's followed by the
+///   addition's;
+/// - single values, such as the configuration and secrets paths and the environment, are the
+///   addition's when it sets them, otherwise the base's;
+/// - policies, such as `precedence_profile()`, `load_policy()`, `secrets_permissions()`,
+///   `env_var_filter()` and `deprecation_policy()`, are the addition's unless it leaves them at
+///   their default, otherwise the base's; so an addition cannot select the default over a base's
+///   other choice;
+/// - flags, such as `strict()`, are enabled when either enables them;
+/// - migrations are the base's and the addition's together, ordered by version;
+/// - the filesystem, clock and environment variable name are the base's.
+///
+/// CLI overrides of the base are applied before those of the addition.
+#[derive(Debug, Clone)]
+pub struct CompositeOptions<A, B> {
+    base: A,
+    addition: B,
+}
+
+impl<A, B> CompositeOptions<A, B> {
+    pub const fn new(base: A, addition: B) -> Self {
+        Self { base, addition }
+    }
+
+    pub const fn base(&self) -> &A {
+        &self.base
+    }
+
+    pub const fn addition(&self) -> &B {
+        &self.addition
+    }
+}
+
+/// The addition's value unless it is the default, otherwise the base's.
+fn unless_default<T: Default + PartialEq>(base: T, addition: T) -> T {
+    if addition == T::default() {
+        base
+    } else {
+        addition
+    }
+}
+
+fn concat<T>(mut base: Vec<T>, addition: Vec<T>) -> Vec<T> {
+    base.extend(addition);
+    base
+}
+
+impl<A, B> LoadingOptions for CompositeOptions<A, B>
+where
+    A: LoadingOptions,
+    B: LoadingOptions,
+{
+    type Error = SettingsError;
+
+    fn config_path(&self) -> Option<PathBuf> {
+        self.addition.config_path().or_else(|| self.base.config_path())
+    }
+
+    fn secrets_path(&self) -> Option<PathBuf> {
+        self.addition.secrets_path().or_else(|| self.base.secrets_path())
+    }
+
+    fn implicit_search_paths(&self) -> Vec<PathBuf> {
+        concat(self.base.implicit_search_paths(), self.addition.implicit_search_paths())
+    }
+
+    fn env_var_aliases(&self) -> Vec<EnvVarAlias> {
+        concat(self.base.env_var_aliases(), self.addition.env_var_aliases())
+    }
+
+    fn presets(&self) -> Vec<PathBuf> {
+        concat(self.base.presets(), self.addition.presets())
+    }
+
+    fn config_dirs(&self) -> Vec<ConfigDir> {
+        concat(self.base.config_dirs(), self.addition.config_dirs())
+    }
+
+    fn instance_identity(&self) -> Option<InstanceIdentity> {
+        self.addition.instance_identity().or_else(|| self.base.instance_identity())
+    }
+
+    fn legacy_locations(&self) -> Vec<LegacyLocation> {
+        concat(self.base.legacy_locations(), self.addition.legacy_locations())
+    }
+
+    fn per_environment_keys(&self) -> Vec<String> {
+        concat(self.base.per_environment_keys(), self.addition.per_environment_keys())
+    }
+
+    #[cfg(feature = "kubernetes")]
+    fn kubernetes_scope(&self) -> Option<crate::common::kubernetes::KubernetesScope> {
+        self.addition.kubernetes_scope().or_else(|| self.base.kubernetes_scope())
+    }
+
+    fn audit_log(&self) -> Option<audit::AuditLog> {
+        self.addition.audit_log().or_else(|| self.base.audit_log())
+    }
+
+    #[cfg(feature = "http")]
+    fn http_sources(&self) -> Vec<crate::http_source::HttpSource> {
+        concat(self.base.http_sources(), self.addition.http_sources())
+    }
+
+    fn precedence_profile(&self) -> PrecedenceProfile {
+        unless_default(self.base.precedence_profile(), self.addition.precedence_profile())
+    }
+
+    fn interpolate_values(&self) -> bool {
+        self.base.interpolate_values() || self.addition.interpolate_values()
+    }
+
+    fn value_coercions(&self) -> Vec<Coercion> {
+        concat(self.base.value_coercions(), self.addition.value_coercions())
+    }
+
+    fn value_decryptors(&self) -> Vec<Arc<dyn ValueDecryptor>> {
+        concat(self.base.value_decryptors(), self.addition.value_decryptors())
+    }
+
+    fn load_policy(&self) -> LoadPolicy {
+        unless_default(self.base.load_policy(), self.addition.load_policy())
+    }
+
+    fn secrets_permissions(&self) -> SecretsPermissions {
+        unless_default(self.base.secrets_permissions(), self.addition.secrets_permissions())
+    }
+
+    fn deprecated_keys(&self) -> Vec<DeprecatedKey> {
+        concat(self.base.deprecated_keys(), self.addition.deprecated_keys())
+    }
+
+    fn deprecation_policy(&self) -> DeprecationPolicy {
+        unless_default(self.base.deprecation_policy(), self.addition.deprecation_policy())
+    }
+
+    fn application_version(&self) -> Option<String> {
+        self.addition
+            .application_version()
+            .or_else(|| self.base.application_version())
+    }
+
+    fn required_keys(&self) -> Vec<RequiredKey> {
+        concat(self.base.required_keys(), self.addition.required_keys())
+    }
+
+    fn strict(&self) -> bool {
+        self.base.strict() || self.addition.strict()
+    }
+
+    fn in_memory_layers(&self) -> Vec<LayerSource> {
+        concat(self.base.in_memory_layers(), self.addition.in_memory_layers())
+    }
+
+    fn runtime_context(&self) -> Option<runtime::RuntimeContext> {
+        self.addition.runtime_context().or_else(|| self.base.runtime_context())
+    }
+
+    fn migrations(&self) -> migrations::Migrations {
+        self.base.migrations().merge(self.addition.migrations())
+    }
+
+    fn clock(&self) -> Arc<dyn Clock> {
+        self.base.clock()
+    }
+
+    fn layer_cache(&self) -> Option<Arc<LayerCache>> {
+        self.addition.layer_cache().or_else(|| self.base.layer_cache())
+    }
+
+    fn config_fs(&self) -> Arc<dyn ConfigFs> {
+        self.base.config_fs()
+    }
+
+    fn key_restrictions(&self) -> Vec<KeyRestriction> {
+        concat(self.base.key_restrictions(), self.addition.key_restrictions())
+    }
+
+    fn array_merge_rules(&self) -> Vec<merge::ArrayMergeRule> {
+        concat(self.base.array_merge_rules(), self.addition.array_merge_rules())
+    }
+
+    fn env_var_filter(&self) -> EnvVarFilter {
+        unless_default(self.base.env_var_filter(), self.addition.env_var_filter())
+    }
+
+    fn secrets_providers(&self) -> Vec<Arc<dyn SecretsProvider>> {
+        concat(self.base.secrets_providers(), self.addition.secrets_providers())
+    }
+
+    fn secrets_ttl(&self) -> Option<Duration> {
+        self.addition.secrets_ttl().or_else(|| self.base.secrets_ttl())
+    }
+
+    #[cfg(feature = "watch")]
+    fn restart_required_keys(&self) -> Vec<String> {
+        concat(self.base.restart_required_keys(), self.addition.restart_required_keys())
+    }
+
+    #[cfg(feature = "watch")]
+    fn restart_coordinator(&self) -> Option<Arc<dyn crate::watch::RestartCoordinator>> {
+        self.addition
+            .restart_coordinator()
+            .or_else(|| self.base.restart_coordinator())
+    }
+
+    #[cfg(feature = "watch")]
+    fn change_listeners(&self) -> Vec<Arc<dyn crate::watch::SettingsChangeListener>> {
+        concat(self.base.change_listeners(), self.addition.change_listeners())
+    }
+
+    fn load_overrides(&self, config: ConfigBuilder<DefaultState>) -> Result<ConfigBuilder<DefaultState>, Self::Error> {
+        let config = self
+            .base
+            .load_overrides(config)
+            .map_err(|err| SettingsError::CliOption(err.into()))?;
+        self.addition
+            .load_overrides(config)
+            .map_err(|err| SettingsError::CliOption(err.into()))
+    }
+
+    fn environment(&self) -> Option<Environment> {
+        self.addition.environment().or_else(|| self.base.environment())
+    }
+
+    fn environment_override(&self) -> Option<Environment> {
+        self.addition
+            .environment_override()
+            .or_else(|| self.base.environment_override())
+    }
+
+    fn env_app_environment() -> &'static str {
+        A::env_app_environment()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct WorkspaceOptions;
+
+    impl LoadingOptions for WorkspaceOptions {
+        type Error = SettingsError;
+
+        fn config_path(&self) -> Option<PathBuf> {
+            Some(PathBuf::from("resources/application.yaml"))
+        }
+
+        fn secrets_path(&self) -> Option<PathBuf> {
+            Some(PathBuf::from("resources/secrets.yaml"))
+        }
+
+        fn implicit_search_paths(&self) -> Vec<PathBuf> {
+            vec![PathBuf::from("resources")]
+        }
+
+        fn presets(&self) -> Vec<PathBuf> {
+            vec![PathBuf::from("presets/observability.yaml")]
+        }
+
+        fn migrations(&self) -> migrations::Migrations {
+            migrations::Migrations::new()
+                .register(migrations::ConfigMigration::new(3, "rename workers").rename_key("workers", "pool.workers"))
+                .register(migrations::ConfigMigration::new(1, "rename level").rename_key("level", "log_level"))
+        }
+
+        fn load_overrides(
+            &self, config: ConfigBuilder<DefaultState>,
+        ) -> Result<ConfigBuilder<DefaultState>, Self::Error> {
+            Ok(config.set_override("log_level", "info")?)
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct IngestOptions;
+
+    impl LoadingOptions for IngestOptions {
+        type Error = SettingsError;
+
+        fn config_path(&self) -> Option<PathBuf> {
+            None
+        }
+
+        fn secrets_path(&self) -> Option<PathBuf> {
+            Some(PathBuf::from("resources/ingest-secrets.yaml"))
+        }
+
+        fn implicit_search_paths(&self) -> Vec<PathBuf> {
+            vec![PathBuf::from("resources/ingest")]
+        }
+
+        fn presets(&self) -> Vec<PathBuf> {
+            vec![PathBuf::from("presets/kafka.yaml")]
+        }
+
+        fn migrations(&self) -> migrations::Migrations {
+            migrations::Migrations::new()
+                .register(migrations::ConfigMigration::new(2, "rename batch").rename_key("batch", "batch_size"))
+        }
+
+        fn strict(&self) -> bool {
+            true
+        }
+
+        fn load_policy(&self) -> LoadPolicy {
+            LoadPolicy::SkipInvalidLayers
+        }
+
+        fn environment(&self) -> Option<Environment> {
+            Some(Environment::from("staging"))
+        }
+
+        fn load_overrides(
+            &self, config: ConfigBuilder<DefaultState>,
+        ) -> Result<ConfigBuilder<DefaultState>, Self::Error> {
+            Ok(config
+                .set_override("log_level", "debug")?
+                .set_override("batch_size", 500)?)
+        }
+    }
+
+    #[test]
+    fn test_composite_options() {
+        let options = CompositeOptions::new(WorkspaceOptions, IngestOptions);
+        assert_eq!(options.config_path(), Some(PathBuf::from("resources/application.yaml")));
+        assert_eq!(
+            options.secrets_path(),
+            Some(PathBuf::from("resources/ingest-secrets.yaml"))
+        );
+        assert_eq!(
+            options.implicit_search_paths(),
+            vec![PathBuf::from("resources"), PathBuf::from("resources/ingest")]
+        );
+        assert_eq!(
+            options.presets(),
+            vec![
+                PathBuf::from("presets/observability.yaml"),
+                PathBuf::from("presets/kafka.yaml")
+            ]
+        );
+        assert!(options.strict());
+        assert_eq!(options.load_policy(), LoadPolicy::SkipInvalidLayers);
+        assert_eq!(options.precedence_profile(), PrecedenceProfile::default());
+        assert_eq!(options.environment(), Some(Environment::from("staging")));
+
+        let migrations = options.migrations();
+        assert_eq!(migrations.current_version(), 3);
+        let mut table = config::Map::new();
+        table.insert("level".to_string(), config::Value::from("info"));
+        assert_eq!(assert_ok!(migrations.migrate(&mut table)), vec![1, 2, 3]);
+        assert!(table.contains_key("log_level"));
+
+        let config = assert_ok!(assert_ok!(options.load_overrides(config::Config::builder())).build());
+        assert_eq!(assert_ok!(config.get::<String>("log_level")), "debug");
+        assert_eq!(assert_ok!(config.get::<u32>("batch_size")), 500);
+    }
+}
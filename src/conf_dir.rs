@@ -0,0 +1,158 @@
+use std::cmp::Ordering;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use path_absolutize::*;
+
+use crate::fs::{format_for_extension, ConfigFile};
+use crate::{ConfigFs, SettingsError};
+
+/// The order a directory's settings files are layered in, from lowest to highest precedence.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Ordered by file name; e.g., `10-db.yaml` before `9-cache.yaml`.
+    #[default]
+    Lexicographic,
+
+    /// Ordered by file name, comparing runs of digits by their numeric value; e.g., `9-cache.yaml`
+    /// before `10-db.yaml`.
+    Natural,
+}
+
+impl SortOrder {
+    pub fn compare(&self, lhs: &str, rhs: &str) -> Ordering {
+        match self {
+            Self::Lexicographic => lhs.cmp(rhs),
+            Self::Natural => natural_cmp(lhs, rhs),
+        }
+    }
+}
+
+/// A `conf.d` style directory, whose recognized settings files are each loaded as a configuration
+/// layer, so settings can be adjusted by dropping snippets into the directory.
+///
+/// Files are layered in the directory's sort order, above the application's configuration files
+/// and presets and below the environment configuration files. Hidden files and files without a
+/// recognized settings extension are skipped, and a missing directory contributes no layers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDir {
+    path: PathBuf,
+    order: SortOrder,
+}
+
+impl ConfigDir {
+    pub fn new(path: impl Into<PathBuf>, order: SortOrder) -> Self {
+        Self { path: path.into(), order }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    pub const fn order(&self) -> SortOrder {
+        self.order
+    }
+
+    /// Reads the directory's settings files in order.
+    pub fn files(&self, fs: &dyn ConfigFs) -> Result<Vec<ConfigFile>, SettingsError> {
+        let dir = self.path.absolutize()?;
+        let mut paths = match fs.list_files(&dir) {
+            Ok(paths) => paths,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                tracing::info!(dir=?dir, "settings directory not found; skipping");
+                return Ok(Vec::default());
+            },
+            Err(err) => return Err(err.into()),
+        };
+
+        paths.retain(|path| is_settings_file(path));
+        paths.sort_by(|lhs, rhs| self.order.compare(&file_name(lhs), &file_name(rhs)));
+        paths.iter().map(|path| ConfigFile::load_required(fs, path)).collect()
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn is_settings_file(path: &Path) -> bool {
+    !file_name(path).starts_with('.')
+        && path
+            .extension()
+            .is_some_and(|ext| format_for_extension(&ext.to_string_lossy()).is_some())
+}
+
+fn natural_cmp(lhs: &str, rhs: &str) -> Ordering {
+    let (mut lhs, mut rhs) = (lhs, rhs);
+    loop {
+        match (lhs.chars().next(), rhs.chars().next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) if l.is_ascii_digit() && r.is_ascii_digit() => {
+                let (l_digits, l_rest) = split_digits(lhs);
+                let (r_digits, r_rest) = split_digits(rhs);
+                let l_value = l_digits.trim_start_matches('0');
+                let r_value = r_digits.trim_start_matches('0');
+                let ordering = l_value
+                    .len()
+                    .cmp(&r_value.len())
+                    .then_with(|| l_value.cmp(r_value))
+                    .then_with(|| l_digits.len().cmp(&r_digits.len()));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                (lhs, rhs) = (l_rest, r_rest);
+            },
+            (Some(l), Some(r)) => {
+                if l != r {
+                    return l.cmp(&r);
+                }
+                (lhs, rhs) = (&lhs[l.len_utf8()..], &rhs[r.len_utf8()..]);
+            },
+        }
+    }
+}
+
+fn split_digits(s: &str) -> (&str, &str) {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s.split_at(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::fs::MemoryFs;
+
+    #[test]
+    fn test_sort_order() {
+        let mut names = vec!["10-db.yaml", "9-cache.yaml", "2-auth.yaml", "base.yaml"];
+        names.sort_by(|l, r| SortOrder::Lexicographic.compare(l, r));
+        assert_eq!(names, vec!["10-db.yaml", "2-auth.yaml", "9-cache.yaml", "base.yaml"]);
+
+        names.sort_by(|l, r| SortOrder::Natural.compare(l, r));
+        assert_eq!(names, vec!["2-auth.yaml", "9-cache.yaml", "10-db.yaml", "base.yaml"]);
+    }
+
+    #[test]
+    fn test_config_dir_files() {
+        let fs = MemoryFs::new()
+            .with_file("conf.d/20-db.yaml", "database: { host: db }")
+            .with_file("conf.d/10-app.toml", "[application]\nport = 8080")
+            .with_file("conf.d/README.md", "ignored")
+            .with_file("conf.d/.30-hidden.yaml", "ignored: true")
+            .with_file("conf.d/nested/40-deep.yaml", "ignored: true");
+
+        let files = assert_ok!(ConfigDir::new("conf.d", SortOrder::Lexicographic).files(&fs));
+        let names: Vec<_> = files.iter().map(|f| file_name(f.path())).collect();
+        assert_eq!(names, vec!["10-app.toml", "20-db.yaml"]);
+
+        let missing = assert_ok!(ConfigDir::new("missing.d", SortOrder::Natural).files(&fs));
+        assert!(missing.is_empty());
+    }
+}
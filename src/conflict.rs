@@ -0,0 +1,249 @@
+use std::collections::BTreeMap;
+
+use config::{Value, ValueKind};
+
+use crate::layer::{key_pattern_matches, LayerSource};
+use crate::{Layer, SettingsError};
+
+/// Number of layers that must set a key, with differing values, before it is reported as a
+/// conflict by `SettingsLoader::conflicts()`.
+pub const CONFLICT_MIN_LAYERS: usize = 3;
+
+/// A value a layer supplies for a setting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub layer: Layer,
+    pub origin: String,
+    pub value: Value,
+}
+
+/// A setting assigned differing values by several layers, which tooling (e.g., a `config doctor`
+/// command) can present so redundant lower-layer entries may be cleaned up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub key: String,
+
+    /// Values supplied for the key, ordered from lowest to highest precedence.
+    pub candidates: Vec<Candidate>,
+}
+
+impl Conflict {
+    /// The candidate whose value is used in the loaded settings.
+    pub fn winner(&self) -> &Candidate {
+        self.candidates.last().expect("conflict has candidates")
+    }
+
+    /// Candidates overridden by the winner.
+    pub fn shadowed(&self) -> &[Candidate] {
+        &self.candidates[..self.candidates.len() - 1]
+    }
+}
+
+/// Finds the keys set by at least `min_layers` of the layers with differing values. Layers are
+/// expected in precedence order, lowest first, as assembled by `SettingsLoader::load_layers()`.
+pub fn find_conflicts(layers: &[LayerSource], min_layers: usize) -> Result<Vec<Conflict>, SettingsError> {
+    let mut candidates_by_key: BTreeMap<String, Vec<Candidate>> = BTreeMap::new();
+    for layer in layers {
+        for (key, value) in layer.collect_flattened()? {
+            let candidate = Candidate {
+                layer: layer.layer(),
+                origin: layer.origin().to_string(),
+                value,
+            };
+            candidates_by_key.entry(key).or_default().push(candidate);
+        }
+    }
+
+    let conflicts = candidates_by_key
+        .into_iter()
+        .filter(|(_, candidates)| min_layers <= candidates.len() && 1 < candidates.len())
+        .filter(|(_, candidates)| candidates.iter().any(|c| !same_value(&c.value, &candidates[0].value)))
+        .map(|(key, candidates)| Conflict { key, candidates })
+        .collect();
+
+    Ok(conflicts)
+}
+
+/// Whether two layers' values for a setting are the same. Scalars are compared as rendered, since
+/// formats such as INI and environment variables supply every value as a string; e.g., `"5432"`
+/// and `5432` are the same.
+fn same_value(first: &Value, second: &Value) -> bool {
+    match (&first.kind, &second.kind) {
+        (ValueKind::Table(_) | ValueKind::Array(_), _) | (_, ValueKind::Table(_) | ValueKind::Array(_)) => {
+            first.kind == second.kind
+        },
+        _ => first.to_string() == second.to_string(),
+    }
+}
+
+/// Finds the first incompatible value type supplied for the setting, or a setting nested beneath
+/// it, reported as `SettingsError::LayerTypeConflict`.
+///
+/// E.g., a string in one file and an integer in another. Integers and floats are compatible, as is
+/// a string that parses as the other value's type, since some formats, such as INI, supply every
+/// value as a string. The environment variables layer is not considered for the same reason.
+pub fn find_type_conflict(layers: &[LayerSource], key: &str) -> Result<Option<SettingsError>, SettingsError> {
+    let mut first_by_key: BTreeMap<String, (String, ValueKind)> = BTreeMap::new();
+    for layer in layers.iter().filter(|l| l.layer() != Layer::EnvironmentVariables) {
+        for (setting, value) in layer.collect_flattened()? {
+            if !key_pattern_matches(key, &setting) || type_name(&value.kind).is_none() {
+                continue;
+            }
+            match first_by_key.get(&setting) {
+                Some((first_origin, first_kind)) if !compatible(first_kind, &value.kind) => {
+                    return Ok(Some(SettingsError::LayerTypeConflict {
+                        key: setting,
+                        first_origin: first_origin.clone(),
+                        first_type: type_name(first_kind).unwrap_or_default(),
+                        second_origin: layer.origin().to_string(),
+                        second_type: type_name(&value.kind).unwrap_or_default(),
+                    }));
+                },
+                Some(_) => {},
+                None => {
+                    first_by_key.insert(setting, (layer.origin().to_string(), value.kind));
+                },
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Whether values of the kinds can stand for the same setting.
+fn compatible(first: &ValueKind, second: &ValueKind) -> bool {
+    match (first, second) {
+        (ValueKind::String(s), other) | (other, ValueKind::String(s)) if !matches!(other, ValueKind::String(_)) => {
+            parses_as(s, other)
+        },
+        _ => type_name(first) == type_name(second),
+    }
+}
+
+fn parses_as(value: &str, kind: &ValueKind) -> bool {
+    let value = value.trim();
+    match kind {
+        ValueKind::Boolean(_) => value.parse::<bool>().is_ok(),
+        ValueKind::I64(_) | ValueKind::I128(_) | ValueKind::U64(_) | ValueKind::U128(_) | ValueKind::Float(_) => {
+            value.parse::<f64>().is_ok()
+        },
+        _ => false,
+    }
+}
+
+pub(crate) const fn type_name(kind: &ValueKind) -> Option<&'static str> {
+    match kind {
+        ValueKind::Nil => None,
+        ValueKind::Boolean(_) => Some("a boolean"),
+        ValueKind::I64(_) | ValueKind::I128(_) | ValueKind::U64(_) | ValueKind::U128(_) | ValueKind::Float(_) => {
+            Some("a number")
+        },
+        ValueKind::String(_) => Some("a string"),
+        ValueKind::Table(_) => Some("a table"),
+        ValueKind::Array(_) => Some("an array"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn yaml_layer(layer: Layer, origin: &str, yaml: &'static str) -> LayerSource {
+        LayerSource::new(layer, origin, File::from_str(yaml, FileFormat::Yaml))
+    }
+
+    #[test]
+    fn test_find_conflicts() {
+        let layers = vec![
+            yaml_layer(
+                Layer::Config,
+                "application.yaml",
+                "database: { port: 5432, password: a, host: db }",
+            ),
+            yaml_layer(
+                Layer::EnvironmentConfig,
+                "local.yaml",
+                "database: { port: 5432, password: b }",
+            ),
+            yaml_layer(
+                Layer::Secrets,
+                "secrets.yaml",
+                "database: { port: 5432, password: c, host: other }",
+            ),
+        ];
+
+        let actual = assert_ok!(find_conflicts(&layers, CONFLICT_MIN_LAYERS));
+        assert_eq!(actual.len(), 1);
+        let conflict = &actual[0];
+        assert_eq!(conflict.key, "database.password");
+        assert_eq!(conflict.candidates.len(), 3);
+        assert_eq!(conflict.winner().layer, Layer::Secrets);
+        assert_eq!(conflict.winner().origin, "secrets.yaml");
+        assert_eq!(assert_ok!(conflict.winner().value.clone().into_string()), "c");
+        assert_eq!(conflict.shadowed().len(), 2);
+
+        let actual = assert_ok!(find_conflicts(&layers, 2));
+        let keys: Vec<_> = actual.iter().map(|c| c.key.as_str()).collect();
+        assert_eq!(keys, vec!["database.host", "database.password"]);
+
+        let layers = vec![
+            yaml_layer(Layer::Config, "application.yaml", "database: { port: 5432, ssl: true }"),
+            LayerSource::new(
+                Layer::EnvironmentConfig,
+                "local.ini",
+                File::from_str("[database]\nport=5432\nssl=false", FileFormat::Ini),
+            ),
+        ];
+        let actual = assert_ok!(find_conflicts(&layers, 2));
+        let keys: Vec<_> = actual.iter().map(|c| c.key.as_str()).collect();
+        assert_eq!(keys, vec!["database.ssl"]);
+    }
+
+    #[test]
+    fn test_find_type_conflict() {
+        let layers = vec![
+            yaml_layer(
+                Layer::Config,
+                "application.yaml",
+                "timeout: 30s\nretries: 3\nenabled: true",
+            ),
+            yaml_layer(Layer::EnvironmentConfig, "local.yaml", "retries: 4.5"),
+            LayerSource::new(
+                Layer::EnvironmentConfig,
+                "local.ini",
+                File::from_str("retries=5\nenabled=false", FileFormat::Ini),
+            ),
+            yaml_layer(Layer::EnvironmentVariables, "environment variables", "timeout: 30"),
+        ];
+        assert_none!(assert_ok!(find_type_conflict(&layers, "timeout")));
+        assert_none!(assert_ok!(find_type_conflict(&layers, "retries")));
+        assert_none!(assert_ok!(find_type_conflict(&layers, "enabled")));
+
+        let layers = vec![
+            yaml_layer(
+                Layer::Config,
+                "application.yaml",
+                "timeout: 30s\nserver: { port: 8080 }",
+            ),
+            yaml_layer(
+                Layer::EnvironmentConfig,
+                "local.yaml",
+                "timeout: 30\nserver: { port: http }",
+            ),
+        ];
+        assert_none!(assert_ok!(find_type_conflict(&layers, "retries")));
+        let actual = assert_some!(assert_ok!(find_type_conflict(&layers, "timeout")));
+        assert_eq!(
+            actual.to_string(),
+            "setting timeout is a string in application.yaml but a number in local.yaml"
+        );
+        let actual = assert_some!(assert_ok!(find_type_conflict(&layers, "server")));
+        assert_eq!(
+            actual.to_string(),
+            "setting server.port is a number in application.yaml but a string in local.yaml"
+        );
+    }
+}
@@ -0,0 +1,343 @@
+//! Conformance checks for downstream `LoadingOptions` and `SettingsLoader` implementations.
+//!
+//! Applications can run these in their own tests to verify their implementations uphold the
+//! loader's invariants; e.g., after overriding `load_layers()` or the environment variable naming.
+//!
+//! The `settings_conformance_tests!` macro generates a test per check:
+//!
+//! ```ignore
+//! settings_loader::settings_conformance_tests!(settings_conformance, MySettings, MyOptions::default());
+//! ```
+//!
+//! Checks load the configuration the same way the application does, so the options should refer to
+//! test resources. The environment mapping check briefly sets an environment variable, which the
+//! checks serialize among themselves.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use config::Value;
+use once_cell::sync::Lazy;
+
+use crate::layer::LayerSource;
+use crate::{Layer, LoadingOptions, SettingsLoader};
+
+const PROBE_KEY: &str = "conformanceprobe.value";
+const PROBE_VALUE: &str = "conformance-probe";
+
+static CONFORMANCE_LOCK: Lazy<Mutex<()>> = Lazy::new(Default::default);
+
+/// An invariant the settings loader implementation fails to uphold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Name of the conformance check that found the violation.
+    pub check: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.check, self.message)
+    }
+}
+
+impl Violation {
+    fn new(check: &'static str, message: impl Into<String>) -> Self {
+        Self { check, message: message.into() }
+    }
+}
+
+/// Runs every conformance check, returning all violations found.
+pub fn check_all<S: SettingsLoader>(options: &S::Options) -> Vec<Violation> {
+    let mut violations = check_layer_order::<S>(options);
+    violations.extend(check_precedence::<S>(options));
+    violations.extend(check_env_mapping::<S>(options));
+    violations
+}
+
+/// Verifies the configuration layers are assembled from lowest to highest precedence, with the
+/// environment variables layer last, and that each layer can be read.
+pub fn check_layer_order<S: SettingsLoader>(options: &S::Options) -> Vec<Violation> {
+    const CHECK: &str = "layer-order";
+    let _guard = CONFORMANCE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let layers = match S::load_layers(options) {
+        Ok(layers) => layers,
+        Err(err) => return vec![Violation::new(CHECK, format!("failed to load layers: {err}"))],
+    };
+
+    let profile = options.precedence_profile();
+    let mut violations = Vec::new();
+    for pair in layers.windows(2) {
+        if profile.rank(pair[1].layer()) < profile.rank(pair[0].layer()) {
+            violations.push(Violation::new(
+                CHECK,
+                format!(
+                    "{} layer from {} is ordered after {} layer from {}",
+                    pair[1].layer(),
+                    pair[1].origin(),
+                    pair[0].layer(),
+                    pair[0].origin()
+                ),
+            ));
+        }
+    }
+
+    let env_vars_highest = profile.order().last() == Some(&Layer::EnvironmentVariables);
+    if env_vars_highest && layers.last().map(LayerSource::layer) != Some(Layer::EnvironmentVariables) {
+        violations.push(Violation::new(CHECK, "environment variables layer is not last"));
+    }
+
+    for layer in &layers {
+        if let Err(err) = layer.collect_flattened() {
+            violations.push(Violation::new(
+                CHECK,
+                format!("failed to read {} layer from {}: {err}", layer.layer(), layer.origin()),
+            ));
+        }
+    }
+
+    violations
+}
+
+/// Verifies each setting in the loaded configuration takes the value from its highest precedence
+/// layer, unless overridden by `LoadingOptions::load_overrides()`.
+pub fn check_precedence<S: SettingsLoader>(options: &S::Options) -> Vec<Violation> {
+    const CHECK: &str = "precedence";
+    let _guard = CONFORMANCE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let mut expected: BTreeMap<String, (String, Value)> = BTreeMap::new();
+    let layers = match S::load_layers(options) {
+        Ok(layers) => layers,
+        Err(err) => return vec![Violation::new(CHECK, format!("failed to load layers: {err}"))],
+    };
+    for layer in &layers {
+        match layer.collect_flattened() {
+            Ok(settings) => {
+                let origin = format!("{} layer from {}", layer.layer(), layer.origin());
+                expected.extend(settings.into_iter().map(|(key, value)| (key, (origin.clone(), value))));
+            },
+            Err(err) => return vec![Violation::new(CHECK, format!("failed to read layer: {err}"))],
+        }
+    }
+
+    let overrides = options
+        .load_overrides(config::Config::builder())
+        .map_err(|err| err.to_string())
+        .and_then(|builder| builder.build().map_err(|err| err.to_string()));
+    match overrides {
+        Ok(overrides) => {
+            let overrides = LayerSource::new(Layer::EnvironmentVariables, "CLI options", overrides);
+            if let Ok(settings) = overrides.collect_flattened() {
+                expected.extend(
+                    settings
+                        .into_iter()
+                        .map(|(key, value)| (key, ("CLI option overrides".to_string(), value))),
+                );
+            }
+        },
+        Err(err) => return vec![Violation::new(CHECK, format!("failed to apply overrides: {err}"))],
+    }
+
+    let config = match S::load_config(options) {
+        Ok(config) => config,
+        Err(err) => return vec![Violation::new(CHECK, format!("failed to load configuration: {err}"))],
+    };
+
+    expected
+        .into_iter()
+        .filter_map(|(key, (origin, value))| match config.get::<Value>(&key) {
+            Ok(actual) if actual.to_string() == value.to_string() => None,
+            Ok(actual) => Some(Violation::new(
+                CHECK,
+                format!("{key} loaded as `{actual}` instead of `{value}` from the {origin}"),
+            )),
+            Err(err) => Some(Violation::new(
+                CHECK,
+                format!("{key} supplied by the {origin} is missing from the configuration: {err}"),
+            )),
+        })
+        .collect()
+}
+
+/// Verifies an environment variable named from `SettingsLoader::environment_prefix()` and
+/// `SettingsLoader::environment_path_separator()` is loaded into the corresponding setting.
+pub fn check_env_mapping<S: SettingsLoader>(options: &S::Options) -> Vec<Violation> {
+    const CHECK: &str = "env-mapping";
+    let _guard = CONFORMANCE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let prefix = S::environment_prefix();
+    let separator = S::environment_path_separator();
+    if prefix.is_empty() || separator.is_empty() {
+        return vec![Violation::new(
+            CHECK,
+            "environment variable prefix and path separator must not be empty",
+        )];
+    }
+
+    let var = format!("{prefix}{separator}{}", PROBE_KEY.replace('.', separator)).to_uppercase();
+    let previous = std::env::var(&var).ok();
+    std::env::set_var(&var, PROBE_VALUE);
+    let loaded = S::load_config(options);
+    match previous {
+        Some(previous) => std::env::set_var(&var, previous),
+        None => std::env::remove_var(&var),
+    }
+
+    match loaded.map(|config| config.get_string(PROBE_KEY)) {
+        Ok(Ok(actual)) if actual == PROBE_VALUE => Vec::new(),
+        Ok(Ok(actual)) => vec![Violation::new(
+            CHECK,
+            format!("environment variable {var} loaded into {PROBE_KEY} as `{actual}`"),
+        )],
+        Ok(Err(err)) => vec![Violation::new(
+            CHECK,
+            format!("environment variable {var} not loaded into {PROBE_KEY}: {err}"),
+        )],
+        Err(err) => vec![Violation::new(CHECK, format!("failed to load configuration: {err}"))],
+    }
+}
+
+/// Panics listing the violations, if any.
+pub fn assert_conforms(violations: Vec<Violation>) {
+    if !violations.is_empty() {
+        let listing: Vec<_> = violations.iter().map(ToString::to_string).collect();
+        panic!("settings loader conformance violations:\n  {}", listing.join("\n  "));
+    }
+}
+
+/// Generates a module of tests running each conformance check against the settings type, using
+/// the options expression (evaluated in the module's parent scope).
+#[macro_export]
+macro_rules! settings_conformance_tests {
+    ($module:ident, $settings:ty, $options:expr) => {
+        #[cfg(test)]
+        mod $module {
+            #[allow(unused_imports)]
+            use super::*;
+
+            #[test]
+            fn layer_order() {
+                $crate::conformance::assert_conforms($crate::conformance::check_layer_order::<$settings>(&$options));
+            }
+
+            #[test]
+            fn precedence() {
+                $crate::conformance::assert_conforms($crate::conformance::check_precedence::<$settings>(&$options));
+            }
+
+            #[test]
+            fn env_mapping() {
+                $crate::conformance::assert_conforms($crate::conformance::check_env_mapping::<$settings>(&$options));
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use claim::*;
+    use pretty_assertions::assert_eq;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::environment::{self, Environment};
+    use crate::fs::MemoryFs;
+    use crate::{ConfigFs, SettingsError};
+
+    #[derive(Debug, Clone)]
+    struct TestOptions(Arc<MemoryFs>);
+
+    impl Default for TestOptions {
+        fn default() -> Self {
+            let fs = MemoryFs::new()
+                .with_file("conformance/application.yaml", "port: 8000\nhost: 0.0.0.0")
+                .with_file("conformance/local.yaml", "host: localhost")
+                .with_file("conformance/secrets.yaml", "password: secret");
+            Self(Arc::new(fs))
+        }
+    }
+
+    impl LoadingOptions for TestOptions {
+        type Error = SettingsError;
+
+        fn config_path(&self) -> Option<PathBuf> {
+            None
+        }
+
+        fn secrets_path(&self) -> Option<PathBuf> {
+            Some(PathBuf::from("conformance/secrets.yaml"))
+        }
+
+        fn implicit_search_paths(&self) -> Vec<PathBuf> {
+            vec!["conformance".into()]
+        }
+
+        fn config_fs(&self) -> Arc<dyn ConfigFs> {
+            self.0.clone()
+        }
+
+        fn environment_override(&self) -> Option<Environment> {
+            Some(environment::LOCAL.clone())
+        }
+
+        fn load_overrides(
+            &self, config: config::ConfigBuilder<config::builder::DefaultState>,
+        ) -> Result<config::ConfigBuilder<config::builder::DefaultState>, Self::Error> {
+            Ok(config.set_override("port", 9000)?)
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestSettings {}
+
+    impl SettingsLoader for TestSettings {
+        type Options = TestOptions;
+
+        fn environment_prefix() -> &'static str {
+            "conformance_test"
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct ReversedSettings {}
+
+    impl SettingsLoader for ReversedSettings {
+        type Options = TestOptions;
+
+        fn environment_prefix() -> &'static str {
+            "conformance_test"
+        }
+
+        fn load_layers(options: &Self::Options) -> Result<Vec<LayerSource>, SettingsError> {
+            let mut layers = TestSettings::load_layers(options)?;
+            layers.reverse();
+            Ok(layers)
+        }
+
+        fn load_config(options: &Self::Options) -> Result<config::Config, SettingsError> {
+            let mut builder = config::Config::builder();
+            for layer in Self::load_layers(options)? {
+                builder = builder.add_source(layer);
+            }
+            Ok(builder.build()?)
+        }
+    }
+
+    crate::settings_conformance_tests!(generated, TestSettings, TestOptions::default());
+
+    #[test]
+    fn test_conformance_violations() {
+        assert_eq!(check_all::<TestSettings>(&TestOptions::default()), Vec::new());
+
+        let violations = check_all::<ReversedSettings>(&TestOptions::default());
+        let checks: Vec<_> = violations.iter().map(|v| v.check).collect();
+        assert!(checks.contains(&"layer-order"));
+        assert!(checks.contains(&"precedence"));
+        assert_none!(checks.iter().find(|c| **c == "env-mapping"));
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use config::{Map, Value, ValueKind};
+
+use crate::SettingsError;
+
+/// Prefix marking an encrypted setting value, written as `enc:<scheme>:<ciphertext>`; e.g.,
+/// `password: "enc:AES256:..."`.
+pub const ENCRYPTED_PREFIX: &str = "enc:";
+
+/// Decrypts setting values encrypted at rest under a scheme, so secrets can be kept in the same
+/// files as ordinary settings.
+///
+/// Decryptors are returned by `LoadingOptions::value_decryptors()`. Encrypted values in any layer
+/// are decrypted after the layers are merged and before the settings are deserialized.
+pub trait ValueDecryptor: Debug + Send + Sync {
+    /// Scheme naming the encryption in encrypted values; e.g., `AES256`.
+    fn scheme(&self) -> &str;
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, String>;
+}
+
+/// Decrypts the encrypted values held in the settings table. Values encrypted under a scheme
+/// without a decryptor are rejected, rather than being loaded as ciphertext.
+pub fn decrypt_values(
+    table: Map<String, Value>, decryptors: &[Arc<dyn ValueDecryptor>],
+) -> Result<Map<String, Value>, SettingsError> {
+    let mut table = table;
+    decrypt_table(None, &mut table, decryptors)?;
+    Ok(table)
+}
+
+/// Whether the settings table holds any encrypted values.
+pub fn contains_encrypted(table: &Map<String, Value>) -> bool {
+    table.values().any(is_encrypted)
+}
+
+fn is_encrypted(value: &Value) -> bool {
+    match value.kind {
+        ValueKind::Table(ref nested) => contains_encrypted(nested),
+        ValueKind::Array(ref items) => items.iter().any(is_encrypted),
+        ValueKind::String(ref rep) => rep.starts_with(ENCRYPTED_PREFIX),
+        _ => false,
+    }
+}
+
+/// Collects the dotted keys of the encrypted values held in the settings table; an array's key
+/// for the encrypted items of an array.
+pub(crate) fn encrypted_keys(prefix: Option<&str>, table: &Map<String, Value>, keys: &mut Vec<String>) {
+    for (key, value) in table {
+        let path = prefix.map_or_else(|| key.clone(), |p| format!("{p}.{key}"));
+        match value.kind {
+            ValueKind::Table(ref nested) => encrypted_keys(Some(path.as_str()), nested, keys),
+            _ if is_encrypted(value) => keys.push(path),
+            _ => {},
+        }
+    }
+}
+
+fn decrypt_table(
+    prefix: Option<&str>, table: &mut Map<String, Value>, decryptors: &[Arc<dyn ValueDecryptor>],
+) -> Result<(), SettingsError> {
+    for (key, value) in table.iter_mut() {
+        let path = prefix.map_or_else(|| key.clone(), |p| format!("{p}.{key}"));
+        decrypt_value(&path, value, decryptors)?;
+    }
+    Ok(())
+}
+
+fn decrypt_value(path: &str, value: &mut Value, decryptors: &[Arc<dyn ValueDecryptor>]) -> Result<(), SettingsError> {
+    match value.kind {
+        ValueKind::Table(ref mut nested) => decrypt_table(Some(path), nested, decryptors),
+        ValueKind::Array(ref mut items) => {
+            for item in items.iter_mut() {
+                decrypt_value(path, item, decryptors)?;
+            }
+            Ok(())
+        },
+        ValueKind::String(ref mut rep) if rep.starts_with(ENCRYPTED_PREFIX) => {
+            let error = |message: String| SettingsError::Decryption { key: path.to_string(), message };
+            let (scheme, ciphertext) = rep[ENCRYPTED_PREFIX.len()..]
+                .split_once(':')
+                .ok_or_else(|| error(format!("expected {ENCRYPTED_PREFIX}<scheme>:<ciphertext>")))?;
+            let decryptor = decryptors
+                .iter()
+                .find(|d| d.scheme() == scheme)
+                .ok_or_else(|| error(format!("no decryptor registered for scheme {scheme}")))?;
+            *rep = decryptor.decrypt(ciphertext).map_err(error)?;
+            Ok(())
+        },
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{Config, File, FileFormat, Source};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct Reversed;
+
+    impl ValueDecryptor for Reversed {
+        fn scheme(&self) -> &str {
+            "REV"
+        }
+
+        fn decrypt(&self, ciphertext: &str) -> Result<String, String> {
+            if ciphertext.is_empty() {
+                return Err("empty ciphertext".to_string());
+            }
+            Ok(ciphertext.chars().rev().collect())
+        }
+    }
+
+    fn table(yaml: &str) -> Map<String, Value> {
+        let config = assert_ok!(Config::builder()
+            .add_source(File::from_str(yaml, FileFormat::Yaml))
+            .build());
+        assert_ok!(config.collect())
+    }
+
+    #[test]
+    fn test_decrypt_values() {
+        let decryptors: Vec<Arc<dyn ValueDecryptor>> = vec![Arc::new(Reversed)];
+        assert!(!contains_encrypted(&table("database: { password: plain }")));
+        assert!(contains_encrypted(&table("database: { hosts: [a, 'enc:REV:b'] }")));
+        let mut keys = Vec::default();
+        encrypted_keys(
+            None,
+            &table("database: { password: 'enc:REV:2retnuh', hosts: [a, 'enc:REV:b'], port: 5432 }"),
+            &mut keys,
+        );
+        keys.sort();
+        assert_eq!(keys, vec!["database.hosts", "database.password"]);
+        let actual = assert_ok!(decrypt_values(
+            table("database: { password: 'enc:REV:2retnuh', hosts: [a, 'enc:REV:b'] }\nname: plain"),
+            &decryptors,
+        ));
+        let actual: serde_json::Value = assert_ok!(Value::new(None, ValueKind::Table(actual)).try_deserialize());
+        assert_eq!(
+            actual,
+            serde_json::json!({ "database": { "password": "hunter2", "hosts": ["a", "b"] }, "name": "plain" })
+        );
+
+        let err = assert_err!(decrypt_values(table("password: 'enc:AES256:abc'"), &decryptors));
+        assert_eq!(
+            err.to_string(),
+            "failed to decrypt setting password: no decryptor registered for scheme AES256"
+        );
+        assert_err!(decrypt_values(table("password: 'enc:REV:'"), &decryptors));
+        assert_err!(decrypt_values(table("password: 'enc:malformed'"), &decryptors));
+    }
+}
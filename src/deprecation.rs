@@ -0,0 +1,269 @@
+use std::fmt;
+
+use crate::layer::{key_pattern_matches, LayerSource};
+use crate::SettingsError;
+
+/// Why and since when a setting is deprecated, and what replaces it.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DeprecationInfo {
+    /// The version deprecating the setting; e.g., `0.14`.
+    pub since: Option<String>,
+    /// The dotted key of the setting replacing it.
+    pub replacement: Option<String>,
+    pub note: Option<String>,
+    /// The application version removing the setting, from which loading fails if a layer supplies
+    /// it under `DeprecationPolicy::RejectRemoved`.
+    pub removal_version: Option<String>,
+}
+
+impl DeprecationInfo {
+    /// Whether the setting is removed as of the application version, comparing the numeric
+    /// segments of the versions; e.g., `0.16.1` reaches a removal version of `0.16`.
+    pub fn is_removed_in(&self, version: &str) -> bool {
+        self.removal_version.as_deref().is_some_and(|removal| {
+            let (mut current, mut removal) = (version_segments(version), version_segments(removal));
+            let len = current.len().max(removal.len());
+            current.resize(len, 0);
+            removal.resize(len, 0);
+            removal <= current
+        })
+    }
+}
+
+fn version_segments(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split('.')
+        .map_while(|segment| {
+            let digits: String = segment.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse().ok()
+        })
+        .collect()
+}
+
+/// A deprecated setting, declared by `LoadingOptions::deprecated_keys()`, which is warned about
+/// whenever a layer supplies it so its users migrate before it is removed.
+///
+/// The key is a dotted key pattern, where a `*` segment matches any single key segment, matching
+/// the setting and any settings nested beneath it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeprecatedKey {
+    pub key: String,
+    pub deprecation: DeprecationInfo,
+}
+
+impl DeprecatedKey {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            deprecation: DeprecationInfo::default(),
+        }
+    }
+
+    pub fn since(mut self, version: impl Into<String>) -> Self {
+        self.deprecation.since = Some(version.into());
+        self
+    }
+
+    pub fn replaced_by(mut self, key: impl Into<String>) -> Self {
+        self.deprecation.replacement = Some(key.into());
+        self
+    }
+
+    pub fn removed_in(mut self, version: impl Into<String>) -> Self {
+        self.deprecation.removal_version = Some(version.into());
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.deprecation.note = Some(note.into());
+        self
+    }
+}
+
+/// A deprecated setting supplied by a layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeprecationWarning {
+    pub key: String,
+    /// The origin of the layer supplying the setting; e.g., its file path.
+    pub origin: String,
+    pub deprecation: DeprecationInfo,
+}
+
+impl fmt::Display for DeprecationInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "deprecated")?;
+        if let Some(ref since) = self.since {
+            write!(f, " since {since}")?;
+        }
+        if let Some(ref removal) = self.removal_version {
+            write!(f, ", to be removed in {removal}")?;
+        }
+        if let Some(ref replacement) = self.replacement {
+            write!(f, "; use {replacement} instead")?;
+        }
+        if let Some(ref note) = self.note {
+            write!(f, ": {note}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DeprecationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "setting {} from {} is {}", self.key, self.origin, self.deprecation)
+    }
+}
+
+/// How a load treats deprecated settings that layers still supply.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum DeprecationPolicy {
+    /// Deprecated settings are loaded with a warning.
+    #[default]
+    Warn,
+    /// Deprecated settings whose removal version the application's version, supplied by
+    /// `LoadingOptions::application_version()`, has reached fail the load with
+    /// `SettingsError::RemovedSettings`. Other deprecated settings are loaded with a warning.
+    RejectRemoved,
+}
+
+impl DeprecationPolicy {
+    /// Checks the deprecated settings supplied by the layers under the policy.
+    pub fn check(
+        self, layers: &[LayerSource], deprecated: &[DeprecatedKey], version: Option<&str>,
+    ) -> Result<(), SettingsError> {
+        let Some(version) = version.filter(|_| self == Self::RejectRemoved) else {
+            return Ok(());
+        };
+
+        let removed: Vec<_> = find_deprecated(layers, deprecated)
+            .into_iter()
+            .filter(|warning| warning.deprecation.is_removed_in(version))
+            .collect();
+        if removed.is_empty() {
+            return Ok(());
+        }
+        tracing::error!(?removed, %version, "layers supply settings removed by the application version");
+        Err(SettingsError::RemovedSettings { version: version.to_string(), settings: removed })
+    }
+}
+
+pub(crate) fn display_removed(removed: &[DeprecationWarning]) -> String {
+    removed
+        .iter()
+        .map(|warning| format!("{} from {}", warning.key, warning.origin))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Finds the deprecated settings supplied by the layers, once for each layer supplying them.
+/// Layers that fail to parse are passed over, since loading them reports the failure.
+pub fn find_deprecated(layers: &[LayerSource], deprecated: &[DeprecatedKey]) -> Vec<DeprecationWarning> {
+    if deprecated.is_empty() {
+        return Vec::default();
+    }
+
+    let mut warnings = Vec::default();
+    for layer in layers {
+        let Ok(settings) = layer.collect_flattened() else {
+            continue;
+        };
+        for deprecated_key in deprecated {
+            if settings.keys().any(|key| key_pattern_matches(&deprecated_key.key, key)) {
+                warnings.push(DeprecationWarning {
+                    key: deprecated_key.key.clone(),
+                    origin: layer.origin().to_string(),
+                    deprecation: deprecated_key.deprecation.clone(),
+                });
+            }
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::Layer;
+
+    #[test]
+    fn test_find_deprecated() {
+        let layers = vec![
+            LayerSource::new(
+                Layer::Config,
+                "application.yaml",
+                File::from_str("http: { port: 8080 }\nlegacy_auth: { token: abc }", FileFormat::Yaml),
+            ),
+            LayerSource::new(
+                Layer::EnvironmentConfig,
+                "production.yaml",
+                File::from_str("http: { port: 80 }", FileFormat::Yaml),
+            ),
+            LayerSource::new(Layer::Secrets, "secrets.yaml", File::from_str("[", FileFormat::Toml)),
+        ];
+        let deprecated = vec![
+            DeprecatedKey::new("http.port")
+                .since("0.14")
+                .replaced_by("server.port")
+                .with_note("http settings moved under server"),
+            DeprecatedKey::new("legacy_auth"),
+            DeprecatedKey::new("unused"),
+        ];
+
+        let actual: Vec<_> = find_deprecated(&layers, &deprecated)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            actual,
+            vec![
+                "setting http.port from application.yaml is deprecated since 0.14; use server.port instead: http \
+                 settings moved under server",
+                "setting legacy_auth from application.yaml is deprecated",
+                "setting http.port from production.yaml is deprecated since 0.14; use server.port instead: http \
+                 settings moved under server",
+            ]
+        );
+        assert!(find_deprecated(&layers, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_deprecation_removal_version() {
+        let deprecated = DeprecatedKey::new("http.port").since("0.14").removed_in("0.16");
+        assert_eq!(
+            deprecated.deprecation.to_string(),
+            "deprecated since 0.14, to be removed in 0.16"
+        );
+        assert!(!deprecated.deprecation.is_removed_in("0.15.9"));
+        assert!(deprecated.deprecation.is_removed_in("0.16"));
+        assert!(deprecated.deprecation.is_removed_in("v0.16.0"));
+        assert!(deprecated.deprecation.is_removed_in("1.0.0-rc.1"));
+        assert!(!DeprecatedKey::new("http.port").deprecation.is_removed_in("99.0"));
+    }
+
+    #[test]
+    fn test_deprecation_policy() {
+        let layers = vec![LayerSource::new(
+            Layer::Config,
+            "application.yaml",
+            File::from_str("http: { port: 8080 }", FileFormat::Yaml),
+        )];
+        let deprecated = vec![DeprecatedKey::new("http.port").removed_in("0.16")];
+
+        assert!(DeprecationPolicy::Warn.check(&layers, &deprecated, Some("0.16")).is_ok());
+        assert!(DeprecationPolicy::RejectRemoved.check(&layers, &deprecated, None).is_ok());
+        assert!(DeprecationPolicy::RejectRemoved
+            .check(&layers, &deprecated, Some("0.15"))
+            .is_ok());
+        let err = DeprecationPolicy::RejectRemoved
+            .check(&layers, &deprecated, Some("0.16"))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "settings removed as of version 0.16 are still supplied: http.port from application.yaml"
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use std::fmt;
+use std::path::Path;
+
+use crate::fs::{format_for_extension, SettingsFormat};
+use crate::layer::LayerSource;
+use crate::policy::SkippedLayer;
+use crate::{ConfigFs, Layer, SettingsError};
+
+/// Whether a settings layer contributed settings to the load.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LayerStatus {
+    /// Found and supplying settings.
+    Loaded,
+    /// Found but supplying no settings; e.g., an empty file or no matching environment variables.
+    Empty,
+    /// Searched for but not found; e.g., an environment configuration file absent from a resource
+    /// directory.
+    Missing,
+    /// Found but skipped by `LoadPolicy::SkipInvalidLayers` because it failed to parse.
+    Skipped,
+}
+
+impl fmt::Display for LayerStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Loaded => "loaded",
+            Self::Empty => "empty",
+            Self::Missing => "missing",
+            Self::Skipped => "skipped",
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// Describes a settings layer resolved for a load, or a settings file searched for but not found;
+/// created by `SettingsLoader::describe_layers()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerDescriptor {
+    pub layer: Layer,
+    /// The layer's source; e.g., a file path, URL or the environment variables read.
+    pub origin: String,
+    /// The format of a settings file, recognized from its extension.
+    pub format: Option<SettingsFormat>,
+    pub status: LayerStatus,
+}
+
+impl LayerDescriptor {
+    /// Describes the layer, reading it to determine whether it supplies settings.
+    pub fn of(layer: &LayerSource, fs: &dyn ConfigFs) -> Result<Self, SettingsError> {
+        let origin = layer.origin().to_string();
+        let path = Path::new(&origin);
+        let format = if fs.is_file(path) {
+            path.extension()
+                .and_then(|ext| format_for_extension(&ext.to_string_lossy()))
+        } else {
+            None
+        };
+        let status = if layer.collect_flattened()?.is_empty() {
+            LayerStatus::Empty
+        } else {
+            LayerStatus::Loaded
+        };
+        Ok(Self { layer: layer.layer(), origin, format, status })
+    }
+
+    pub fn missing(layer: Layer, origin: impl Into<String>) -> Self {
+        Self {
+            layer,
+            origin: origin.into(),
+            format: None,
+            status: LayerStatus::Missing,
+        }
+    }
+
+    pub fn skipped(skipped: SkippedLayer) -> Self {
+        Self {
+            layer: skipped.layer,
+            origin: skipped.origin,
+            format: None,
+            status: LayerStatus::Skipped,
+        }
+    }
+}
+
+impl fmt::Display for LayerDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} [{}]", self.layer, self.origin, self.status)
+    }
+}
+
+/// Inserts a descriptor of a missing or skipped file after the descriptors of its layer, or of the
+/// layers preceding it.
+pub fn insert_missing(descriptors: &mut Vec<LayerDescriptor>, missing: LayerDescriptor) {
+    let position = descriptors
+        .iter()
+        .rposition(|d| d.layer <= missing.layer)
+        .map_or(0, |p| p + 1);
+    descriptors.insert(position, missing);
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::FileFormat;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::fs::{ConfigFile, MemoryFs};
+
+    #[test]
+    fn test_layer_descriptor() {
+        let fs = MemoryFs::new()
+            .with_file("/etc/app/application.yaml", "foo: bar")
+            .with_file("/etc/app/secrets.yaml", "");
+        let file = |path: &str| assert_ok!(ConfigFile::load_required(&fs, Path::new(path)));
+
+        let config = LayerSource::new(
+            Layer::Config,
+            "/etc/app/application.yaml",
+            file("/etc/app/application.yaml"),
+        );
+        let secrets = LayerSource::new(Layer::Secrets, "/etc/app/secrets.yaml", file("/etc/app/secrets.yaml"));
+        let mut descriptors = vec![
+            assert_ok!(LayerDescriptor::of(&config, &fs)),
+            assert_ok!(LayerDescriptor::of(&secrets, &fs)),
+        ];
+        insert_missing(
+            &mut descriptors,
+            LayerDescriptor::missing(Layer::EnvironmentConfig, "/etc/app/production"),
+        );
+
+        let actual: Vec<_> = descriptors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            actual,
+            vec![
+                "config /etc/app/application.yaml [loaded]",
+                "environment-config /etc/app/production [missing]",
+                "secrets /etc/app/secrets.yaml [empty]",
+            ]
+        );
+        assert_eq!(descriptors[0].format, Some(SettingsFormat::File(FileFormat::Yaml)));
+        assert_none!(descriptors[1].format);
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+
+use config::{Config, Map, Source, Value, ValueKind};
+
+use crate::layer::flatten_into;
+use crate::SettingsError;
+
+/// How a setting differs between two configurations.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added { new: Value },
+    Removed { old: Value },
+    Changed { old: Value, new: Value },
+}
+
+/// A setting, identified by its dotted key, that differs between two configurations. The values
+/// carry their provenance in `Value::origin()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyChange {
+    pub key: String,
+    pub change: Change,
+}
+
+/// The settings added, removed and changed between two merged configurations; e.g., to log what
+/// changed after a reload.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SettingsDiff {
+    changes: Vec<KeyChange>,
+}
+
+impl SettingsDiff {
+    /// Compares two merged configurations, such as those built by `SettingsLoader::load_config()`.
+    pub fn between_configs(old: &Config, new: &Config) -> Result<Self, SettingsError> {
+        Ok(Self::between(old.collect()?, new.collect()?))
+    }
+
+    /// Compares two settings tables key by key, ordered by key.
+    pub fn between(old: Map<String, Value>, new: Map<String, Value>) -> Self {
+        let mut old_flattened = BTreeMap::new();
+        flatten_into(None, old, &mut old_flattened);
+        let mut new_flattened = BTreeMap::new();
+        flatten_into(None, new, &mut new_flattened);
+
+        let mut changes = BTreeMap::new();
+        for (key, old) in old_flattened {
+            let change = match new_flattened.remove(&key) {
+                None => Change::Removed { old },
+                Some(new) if !same_value(&old, &new) => Change::Changed { old, new },
+                Some(_) => continue,
+            };
+            changes.insert(key, change);
+        }
+        for (key, new) in new_flattened {
+            changes.insert(key, Change::Added { new });
+        }
+
+        Self {
+            changes: changes
+                .into_iter()
+                .map(|(key, change)| KeyChange { key, change })
+                .collect(),
+        }
+    }
+
+    pub const fn changes(&self) -> &[KeyChange] {
+        self.changes.as_slice()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Compares values ignoring their origins, so a setting moved to another file is unchanged.
+fn same_value(old: &Value, new: &Value) -> bool {
+    match (&old.kind, &new.kind) {
+        (ValueKind::Array(old), ValueKind::Array(new)) => {
+            old.len() == new.len() && old.iter().zip(new).all(|(o, n)| same_value(o, n))
+        },
+        (ValueKind::Table(old), ValueKind::Table(new)) => {
+            old.len() == new.len() && old.iter().all(|(k, o)| new.get(k).is_some_and(|n| same_value(o, n)))
+        },
+        (old, new) => old == new,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn yaml_config(yaml: &str) -> Config {
+        assert_ok!(Config::builder()
+            .add_source(File::from_str(yaml, FileFormat::Yaml))
+            .build())
+    }
+
+    #[test]
+    fn test_settings_diff() {
+        let old = yaml_config("database: { host: localhost, port: 5432 }\nhosts: [a, b]\nlegacy: true");
+        let new = yaml_config("database: { host: db.example.com, port: 5432 }\nhosts: [a, b]\nfoo: bar");
+
+        let actual = assert_ok!(SettingsDiff::between_configs(&old, &new));
+        let summary: Vec<_> = actual
+            .changes()
+            .iter()
+            .map(|c| match c.change {
+                Change::Added { ref new } => format!("+ {} = {new}", c.key),
+                Change::Removed { ref old } => format!("- {} = {old}", c.key),
+                Change::Changed { ref old, ref new } => format!("~ {} = {old} -> {new}", c.key),
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                "~ database.host = localhost -> db.example.com".to_string(),
+                "+ foo = bar".to_string(),
+                "- legacy = true".to_string(),
+            ]
+        );
+
+        assert!(assert_ok!(SettingsDiff::between_configs(&old, &old)).is_empty());
+    }
+}
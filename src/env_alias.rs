@@ -0,0 +1,122 @@
+use config::{ConfigError, Map, Source, Value};
+
+use crate::layer::insert_nested;
+
+/// Maps environment variables named with a short alias directly onto a settings section, without
+/// repeating the full path or the application prefix.
+///
+/// For example, with the alias `DB -> database`, `DB__HOST` maps to `database.host`. Aliased
+/// variables are overridden by variables naming the full path; e.g., `APP__DATABASE__HOST`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvVarAlias {
+    alias: String,
+    section: String,
+}
+
+impl EnvVarAlias {
+    pub fn new(alias: impl Into<String>, section: impl Into<String>) -> Self {
+        Self { alias: alias.into(), section: section.into() }
+    }
+
+    pub const fn alias(&self) -> &str {
+        self.alias.as_str()
+    }
+
+    pub const fn section(&self) -> &str {
+        self.section.as_str()
+    }
+
+    /// The settings key the variable maps to, if it is named with this alias.
+    pub fn key_for(&self, var: &str, separator: &str) -> Option<String> {
+        let prefix = format!("{}{separator}", self.alias).to_lowercase();
+        let var = var.to_lowercase();
+        let path = var.strip_prefix(&prefix).filter(|path| !path.is_empty())?;
+        Some(format!("{}.{}", self.section, path.replace(separator, ".")))
+    }
+}
+
+/// The environment variables named with aliases, as settings.
+#[derive(Debug, Clone)]
+pub(crate) struct AliasedEnvironmentSource {
+    aliases: Vec<EnvVarAlias>,
+    separator: String,
+    vars: Map<String, String>,
+}
+
+impl AliasedEnvironmentSource {
+    pub(crate) fn new(
+        aliases: Vec<EnvVarAlias>, separator: impl Into<String>, vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Self {
+        Self {
+            aliases,
+            separator: separator.into(),
+            vars: vars.into_iter().collect(),
+        }
+    }
+}
+
+impl Source for AliasedEnvironmentSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut table = Map::new();
+        for (var, value) in &self.vars {
+            if let Some(key) = self.aliases.iter().find_map(|a| a.key_for(var, &self.separator)) {
+                tracing::debug!(%var, %key, "mapping aliased environment variable");
+                insert_nested(&mut table, var, &key, value.clone().into());
+            }
+        }
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::layer::LayerSource;
+    use crate::Layer;
+
+    #[test]
+    fn test_aliased_environment_variables() {
+        let db = EnvVarAlias::new("DB", "database");
+        assert_eq!(db.key_for("DB__HOST", "__"), Some("database.host".to_string()));
+        assert_eq!(
+            db.key_for("db__pool__max_size", "__"),
+            Some("database.pool.max_size".to_string())
+        );
+        assert_none!(db.key_for("DB__", "__"));
+        assert_none!(db.key_for("DBX__HOST", "__"));
+
+        let vars = vec![
+            ("DB__HOST".to_string(), "db.local".to_string()),
+            ("CACHE__TTL".to_string(), "60".to_string()),
+            ("PATH".to_string(), "/usr/bin".to_string()),
+        ];
+        let source = AliasedEnvironmentSource::new(vec![db, EnvVarAlias::new("CACHE", "services.cache")], "__", vars);
+        let layer = LayerSource::new(Layer::EnvironmentVariables, "aliases", source);
+        let actual: Vec<_> = assert_ok!(layer.collect_flattened())
+            .into_iter()
+            .map(|(key, value)| (key, value.to_string(), value.origin().map(ToString::to_string)))
+            .collect();
+        assert_eq!(
+            actual,
+            vec![
+                (
+                    "database.host".to_string(),
+                    "db.local".to_string(),
+                    Some("DB__HOST".to_string())
+                ),
+                (
+                    "services.cache.ttl".to_string(),
+                    "60".to_string(),
+                    Some("CACHE__TTL".to_string())
+                ),
+            ]
+        );
+    }
+}
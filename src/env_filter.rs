@@ -0,0 +1,122 @@
+use config::Map;
+
+/// Restricts which environment variables the environment variables layer may consume, for contexts
+/// where arbitrary environment injection (e.g., via CI) must not influence the configuration.
+///
+/// Patterns match whole variable names, where `*` matches any run of characters; e.g.,
+/// `APP__DATABASE__*`. A variable is consumed if it matches the allow-list (when one is given) and
+/// matches none of the deny patterns.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EnvVarFilter {
+    allowed: Option<Vec<String>>,
+    denied: Vec<String>,
+}
+
+impl EnvVarFilter {
+    /// Permits every environment variable; the default.
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// Permits only the environment variables matching the patterns.
+    pub fn allow_only<P: Into<String>>(patterns: impl IntoIterator<Item = P>) -> Self {
+        Self {
+            allowed: Some(patterns.into_iter().map(Into::into).collect()),
+            denied: Vec::default(),
+        }
+    }
+
+    /// Adds a pattern to the allow-list, restricting the filter to an allow-list if it is not
+    /// already.
+    pub fn allow(mut self, pattern: impl Into<String>) -> Self {
+        self.allowed.get_or_insert_with(Vec::default).push(pattern.into());
+        self
+    }
+
+    pub fn deny(mut self, pattern: impl Into<String>) -> Self {
+        self.denied.push(pattern.into());
+        self
+    }
+
+    pub const fn is_unrestricted(&self) -> bool {
+        self.allowed.is_none() && self.denied.is_empty()
+    }
+
+    pub fn permits(&self, name: &str) -> bool {
+        let allowed = self
+            .allowed
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|p| wildcard_matches(p, name)));
+        allowed && !self.denied.iter().any(|p| wildcard_matches(p, name))
+    }
+
+    /// Filters the variables, logging the names (never the values) of those rejected.
+    pub fn filter(&self, vars: impl IntoIterator<Item = (String, String)>) -> Map<String, String> {
+        vars.into_iter()
+            .filter(|(name, _)| {
+                let permitted = self.permits(name);
+                if !permitted {
+                    tracing::debug!(var=%name, "ignoring environment variable rejected by filter");
+                }
+                permitted
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn wildcard_matches(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<_> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_wildcard_matches() {
+        assert!(wildcard_matches("APP__DATABASE__*", "APP__DATABASE__PASSWORD"));
+        assert!(wildcard_matches("APP__*__HOST", "APP__DATABASE__HOST"));
+        assert!(wildcard_matches("*", "ANYTHING"));
+        assert!(wildcard_matches("APP__FOO", "APP__FOO"));
+        assert!(!wildcard_matches("APP__FOO", "APP__FOOD"));
+        assert!(!wildcard_matches("APP__*__HOST", "APP__DATABASE__PORT"));
+        assert!(!wildcard_matches("APP__A*A", "APP__A"));
+    }
+
+    #[test]
+    fn test_env_var_filter() {
+        let filter = EnvVarFilter::allow_only(["APP__APPLICATION__*", "APP__DATABASE__*"]).deny("*PASSWORD");
+        assert!(!filter.is_unrestricted());
+        assert!(filter.permits("APP__DATABASE__HOST"));
+        assert!(!filter.permits("APP__DATABASE__PASSWORD"));
+        assert!(!filter.permits("APP__FOO"));
+
+        let vars = vec![
+            ("APP__APPLICATION__PORT".to_string(), "80".to_string()),
+            ("APP__FOO".to_string(), "injected".to_string()),
+        ];
+        let actual: Vec<_> = filter.filter(vars).into_keys().collect();
+        assert_eq!(actual, vec!["APP__APPLICATION__PORT".to_string()]);
+
+        assert!(EnvVarFilter::allow_all().permits("APP__FOO"));
+    }
+}
@@ -22,6 +22,89 @@ pub enum SettingsError {
     #[error("error during system bootstrap: {message}: {setting}")]
     Bootstrap { message: String, setting: String },
 
+    /// Error fetching secrets from a secrets provider.
+    #[error("failed to fetch secrets from {provider}: {message}")]
+    SecretsProvider { provider: String, message: String },
+
+    /// Files included by a settings file cannot be included.
+    #[error("failed to include settings in {file}: {message}")]
+    Include { file: String, message: String },
+
+    /// An encrypted setting value cannot be decrypted.
+    #[error("failed to decrypt setting {key}: {message}")]
+    Decryption { key: String, message: String },
+
+    /// Error signing an attestation of the settings inputs.
+    #[cfg(feature = "attestation")]
+    #[error("failed to sign settings attestation with key {key_id}: {message}")]
+    AttestationSigning { key_id: String, message: String },
+
+    /// Error watching settings files for changes.
+    #[cfg(feature = "watch")]
+    #[error("failed to watch settings files: {0}")]
+    Watch(#[from] notify::Error),
+
+    /// Two layers supply values of incompatible types for the same setting.
+    #[error("setting {key} is {first_type} in {first_origin} but {second_type} in {second_origin}")]
+    LayerTypeConflict {
+        key: String,
+        first_origin: String,
+        first_type: &'static str,
+        second_origin: String,
+        second_type: &'static str,
+    },
+
+    /// A setting failed to deserialize, described with its value (redacted if sensitive) and the
+    /// layer supplying it.
+    #[error(
+        "invalid setting {key}{}: {message}",
+        crate::provenance::display_provenance(.value.as_deref(), .origin.as_deref())
+    )]
+    Deserialization {
+        key: String,
+        value: Option<String>,
+        origin: Option<String>,
+        layer: Option<crate::Layer>,
+        message: String,
+    },
+
+    /// A settings preset requires a newer settings schema version than the application's.
+    #[error("preset {name} requires settings version {required}, but the current version is {current}")]
+    IncompatiblePreset { name: String, required: u32, current: u32 },
+
+    /// A per-request override document was rejected by `SettingsOverlay` validation.
+    #[error("override of {key} rejected: {reason}")]
+    OverlayRejected { key: String, reason: String },
+
+    /// Settings required in the active environment are not supplied by any layer.
+    #[error(
+        "required settings missing{}: {}",
+        crate::required::display_environment(.environment.as_deref()),
+        .keys.join(", ")
+    )]
+    MissingRequiredKeys {
+        environment: Option<String>,
+        keys: Vec<String>,
+    },
+
+    /// Layers supply deprecated settings the running application's version has removed.
+    #[error(
+        "settings removed as of version {version} are still supplied: {}",
+        crate::deprecation::display_removed(.settings)
+    )]
+    RemovedSettings {
+        version: String,
+        settings: Vec<crate::deprecation::DeprecationWarning>,
+    },
+
+    /// The secrets file is readable beyond its owner, under `SecretsPermissions::Deny`.
+    #[error("secrets file {} is readable beyond its owner (mode {mode:o}); restrict it to mode 600", .path.display())]
+    InsecureSecretsFile { path: std::path::PathBuf, mode: u32 },
+
+    /// The settings include keys the settings type does not define, under a strict load.
+    #[error("settings include unknown keys: {}", crate::strict::display_unknown_keys(.0))]
+    UnknownKeys(Vec<crate::strict::UnknownKey>),
+
     #[error("infallible operation failed: {0}")]
     Infallible(#[from] std::convert::Infallible),
 
@@ -0,0 +1,248 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::Path;
+
+use config::{Config, Map, Source, Value, ValueKind};
+
+use crate::audit::REDACTED;
+use crate::layer::LayerSource;
+use crate::provenance::{is_sensitive, value_origin};
+use crate::{Layer, SettingsError};
+
+/// Format of an exported settings snapshot.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[default]
+    Yaml,
+    Json,
+}
+
+/// Writes the fully merged, effective configuration to a single file; e.g., for support bundles
+/// or debugging a deployment.
+///
+/// Keys are written in alphabetical order, so snapshots of the same configuration are identical.
+/// Values supplied by the secrets layer, or held by settings named as sensitive (e.g.,
+/// `password`), are redacted unless secrets are included. YAML snapshots may also note the origin
+/// of each value in a trailing comment; JSON has no comments, so provenance is not written to it.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SettingsExport {
+    format: ExportFormat,
+    provenance: bool,
+    include_secrets: bool,
+}
+
+impl SettingsExport {
+    pub const fn new(format: ExportFormat) -> Self {
+        Self { format, provenance: false, include_secrets: false }
+    }
+
+    pub const fn with_provenance(mut self) -> Self {
+        self.provenance = true;
+        self
+    }
+
+    /// Writes secret values as they are rather than redacting them.
+    pub const fn include_secrets(mut self) -> Self {
+        self.include_secrets = true;
+        self
+    }
+
+    pub const fn format(&self) -> ExportFormat {
+        self.format
+    }
+
+    /// Renders the configuration merged from the layers.
+    pub fn render(&self, config: &Config, layers: &[LayerSource]) -> Result<String, SettingsError> {
+        let mut secret_keys = BTreeSet::new();
+        let mut layer_values = Vec::with_capacity(layers.len());
+        for layer in layers {
+            let settings = layer.collect_flattened()?;
+            if layer.layer() == Layer::Secrets && !self.include_secrets {
+                secret_keys.extend(settings.keys().cloned());
+            }
+            layer_values.push((layer.origin().to_string(), settings));
+        }
+
+        let snapshot = Snapshot { export: self, secret_keys, layer_values };
+        let table = config.collect()?;
+        match self.format {
+            ExportFormat::Json => {
+                let json = snapshot.to_json(None, table)?;
+                let mut rendered = serde_json::to_string_pretty(&json).map_err(std::io::Error::other)?;
+                rendered.push('\n');
+                Ok(rendered)
+            },
+            ExportFormat::Yaml => {
+                let mut rendered = String::new();
+                snapshot.write_yaml(&mut rendered, None, table, 0)?;
+                Ok(rendered)
+            },
+        }
+    }
+
+    /// Renders the configuration merged from the layers to the file at the path.
+    pub fn write(&self, path: &Path, config: &Config, layers: &[LayerSource]) -> Result<(), SettingsError> {
+        std::fs::write(path, self.render(config, layers)?)?;
+        tracing::info!(?path, format=?self.format, "exported settings snapshot");
+        Ok(())
+    }
+}
+
+struct Snapshot<'e> {
+    export: &'e SettingsExport,
+    secret_keys: BTreeSet<String>,
+    layer_values: Vec<(String, BTreeMap<String, Value>)>,
+}
+
+impl Snapshot<'_> {
+    fn is_redacted(&self, key: &str) -> bool {
+        !self.export.include_secrets && (is_sensitive(key) || self.secret_keys.contains(key))
+    }
+
+    fn leaf(&self, key: &str, value: Value) -> Result<serde_json::Value, SettingsError> {
+        if self.is_redacted(key) {
+            Ok(serde_json::Value::String(REDACTED.to_string()))
+        } else {
+            Ok(value.try_deserialize()?)
+        }
+    }
+
+    fn to_json(&self, prefix: Option<&str>, table: Map<String, Value>) -> Result<serde_json::Value, SettingsError> {
+        let mut json = serde_json::Map::new();
+        for (key, value) in sorted(table) {
+            let path = prefix.map_or_else(|| key.clone(), |p| format!("{p}.{key}"));
+            let value = match value.kind {
+                ValueKind::Table(nested) => self.to_json(Some(&path), nested)?,
+                _ => self.leaf(&path, value)?,
+            };
+            json.insert(key, value);
+        }
+        Ok(serde_json::Value::Object(json))
+    }
+
+    fn write_yaml(
+        &self, out: &mut String, prefix: Option<&str>, table: Map<String, Value>, indent: usize,
+    ) -> Result<(), SettingsError> {
+        for (key, value) in sorted(table) {
+            let path = prefix.map_or_else(|| key.clone(), |p| format!("{p}.{key}"));
+            let yaml_key = yaml_key(&key);
+            match value.kind {
+                ValueKind::Table(nested) if nested.is_empty() => {
+                    let _ = writeln!(out, "{:indent$}{yaml_key}: {{}}", "");
+                },
+                ValueKind::Table(nested) => {
+                    let _ = writeln!(out, "{:indent$}{yaml_key}:", "");
+                    self.write_yaml(out, Some(&path), nested, indent + 2)?;
+                },
+                _ => {
+                    let origin = self
+                        .export
+                        .provenance
+                        .then(|| value_origin(&self.layer_values, &path, &value))
+                        .flatten();
+                    let rendered = serde_json::to_string(&self.leaf(&path, value)?).map_err(std::io::Error::other)?;
+                    let _ = write!(out, "{:indent$}{yaml_key}: {rendered}", "");
+                    if let Some(origin) = origin {
+                        let _ = write!(out, "  # from {origin}");
+                    }
+                    out.push('\n');
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+fn sorted(table: Map<String, Value>) -> BTreeMap<String, Value> {
+    table.into_iter().collect()
+}
+
+/// Plain keys are written as is and others quoted, so they remain valid YAML keys.
+fn yaml_key(key: &str) -> String {
+    let is_plain = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if is_plain {
+        key.to_string()
+    } else {
+        serde_json::Value::String(key.to_string()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+    use trim_margin::MarginTrimmable;
+
+    use super::*;
+
+    fn layers() -> Vec<LayerSource> {
+        vec![
+            LayerSource::new(
+                Layer::Config,
+                "application.yaml",
+                File::from_str(
+                    "database: { host: localhost, port: 5432, tags: [a, b] }\nname: app",
+                    FileFormat::Yaml,
+                ),
+            ),
+            LayerSource::new(
+                Layer::Secrets,
+                "secrets.yaml",
+                File::from_str("database: { username: admin, password: hunter2 }", FileFormat::Yaml),
+            ),
+        ]
+    }
+
+    fn config(layers: &[LayerSource]) -> Config {
+        let mut builder = Config::builder();
+        for layer in layers {
+            builder = builder.add_source(layer.clone());
+        }
+        assert_ok!(builder.build())
+    }
+
+    #[test]
+    fn test_export_yaml_with_provenance() {
+        let layers = layers();
+        let export = SettingsExport::new(ExportFormat::Yaml).with_provenance();
+        let actual = assert_ok!(export.render(&config(&layers), &layers));
+        let expected = r##"
+            |database:
+            |  host: "localhost"  # from application.yaml
+            |  password: "<redacted>"  # from secrets.yaml
+            |  port: 5432  # from application.yaml
+            |  tags: ["a","b"]  # from application.yaml
+            |  username: "<redacted>"  # from secrets.yaml
+            |name: "app"  # from application.yaml
+            |"##
+        .trim_margin()
+        .unwrap();
+        assert_eq!(actual, expected);
+
+        let reparsed: serde_json::Value = assert_ok!(serde_yaml::from_str(&actual));
+        assert_eq!(reparsed["database"]["tags"], serde_json::json!(["a", "b"]));
+    }
+
+    #[test]
+    fn test_export_json_including_secrets() {
+        let layers = layers();
+        let export = SettingsExport::new(ExportFormat::Json).include_secrets();
+        let actual = assert_ok!(export.render(&config(&layers), &layers));
+        let actual: serde_json::Value = assert_ok!(serde_json::from_str(&actual));
+        assert_eq!(
+            actual,
+            serde_json::json!({
+                "database": {
+                    "host": "localhost",
+                    "password": "hunter2",
+                    "port": 5432,
+                    "tags": ["a", "b"],
+                    "username": "admin",
+                },
+                "name": "app",
+            })
+        );
+        assert_eq!(yaml_key("my.key"), "\"my.key\"");
+    }
+}
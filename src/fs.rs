@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt::Debug;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use config::{ConfigError, FileFormat, FileStoredFormat, Format, Map, Source, Value};
+use path_absolutize::*;
+
+use crate::{LayerCache, SettingsError};
+
+/// File formats the loader recognizes, in the order extensions are probed when a settings file is
+/// referenced without one.
+const FILE_FORMATS: &[FileFormat] = &[
+    FileFormat::Toml,
+    FileFormat::Json,
+    FileFormat::Yaml,
+    FileFormat::Ini,
+    FileFormat::Ron,
+    FileFormat::Json5,
+];
+
+/// Extension of dotenv files, which are probed after the `FILE_FORMATS`.
+const DOTENV_EXTENSION: &str = "env";
+
+/// Default naming of the variables in dotenv files, matching `SettingsLoader`'s default
+/// environment variable prefix and path separator.
+const DEFAULT_ENV_NAMING: (&str, &str) = ("app", "__");
+
+/// Format of a settings file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsFormat {
+    /// A format supported by the `config` crate; e.g., YAML, TOML or INI.
+    File(FileFormat),
+
+    /// A dotenv file of `KEY=value` lines, whose variables are named the same as the environment
+    /// variables the loader reads; e.g., `APP__DATABASE__HOST=localhost`.
+    DotEnv,
+}
+
+impl From<FileFormat> for SettingsFormat {
+    fn from(format: FileFormat) -> Self {
+        Self::File(format)
+    }
+}
+
+/// Abstracts the file access performed while loading settings.
+///
+/// Loads can then run against something other than the local disk; e.g., an in-memory filesystem
+/// for tests, WASM targets, or dry-run simulations of a configuration change.
+pub trait ConfigFs: Debug + Send + Sync {
+    fn is_file(&self, path: &Path) -> bool;
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+    /// The files directly within the directory, in no particular order. Directory listing is
+    /// unsupported unless implemented.
+    fn list_files(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("listing {dir:?} is not supported by this filesystem"),
+        ))
+    }
+}
+
+/// The local filesystem; used unless `LoadingOptions::config_fs()` is overridden.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RealFs;
+
+impl ConfigFs for RealFs {
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn list_files(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut files = Vec::default();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// In-memory filesystem holding settings files by absolute path. Relative paths are resolved
+/// against the current directory, matching how the loader resolves search paths.
+///
+/// An optional fallback filesystem is consulted for paths not held in memory, which supports
+/// simulating changes to selected files on top of the real configuration.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryFs {
+    files: HashMap<PathBuf, String>,
+    fallback: Option<Arc<dyn ConfigFs>>,
+}
+
+impl MemoryFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_fallback(fallback: Arc<dyn ConfigFs>) -> Self {
+        Self {
+            files: HashMap::default(),
+            fallback: Some(fallback),
+        }
+    }
+
+    pub fn with_file(mut self, path: impl AsRef<Path>, contents: impl Into<String>) -> Self {
+        self.insert(path, contents);
+        self
+    }
+
+    pub fn insert(&mut self, path: impl AsRef<Path>, contents: impl Into<String>) -> Option<String> {
+        self.files.insert(Self::normalize(path.as_ref()), contents.into())
+    }
+
+    pub fn remove(&mut self, path: impl AsRef<Path>) -> Option<String> {
+        self.files.remove(&Self::normalize(path.as_ref()))
+    }
+
+    fn normalize(path: &Path) -> PathBuf {
+        path.absolutize()
+            .map(|p| p.into_owned())
+            .unwrap_or_else(|_| path.to_path_buf())
+    }
+}
+
+impl ConfigFs for MemoryFs {
+    fn is_file(&self, path: &Path) -> bool {
+        self.files.contains_key(&Self::normalize(path))
+            || self.fallback.as_ref().is_some_and(|fallback| fallback.is_file(path))
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        match (self.files.get(&Self::normalize(path)), self.fallback.as_ref()) {
+            (Some(contents), _) => Ok(contents.clone()),
+            (None, Some(fallback)) => fallback.read_to_string(path),
+            (None, None) => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no in-memory file at {path:?}"),
+            )),
+        }
+    }
+
+    fn list_files(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let dir = Self::normalize(dir);
+        let mut files: Vec<_> = self
+            .files
+            .keys()
+            .filter(|path| path.parent() == Some(dir.as_path()))
+            .cloned()
+            .collect();
+        if let Some(ref fallback) = self.fallback {
+            match fallback.list_files(&dir) {
+                Ok(fallback_files) => {
+                    let fallback_files: Vec<_> = fallback_files.into_iter().filter(|f| !files.contains(f)).collect();
+                    files.extend(fallback_files);
+                },
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {},
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// A settings file read through a `ConfigFs`, used as a configuration source.
+#[derive(Debug, Clone)]
+pub struct ConfigFile {
+    path: PathBuf,
+    format: SettingsFormat,
+    contents: String,
+    env_naming: (String, String),
+    cache: Option<Arc<LayerCache>>,
+}
+
+impl ConfigFile {
+    /// Resolves the settings file for a path, which may omit the extension; in that case each
+    /// recognized format extension is tried in turn.
+    pub fn locate(fs: &dyn ConfigFs, path: &Path) -> Option<(PathBuf, SettingsFormat)> {
+        if let Some(format) = path
+            .extension()
+            .and_then(|ext| format_for_extension(&ext.to_string_lossy()))
+        {
+            if fs.is_file(path) {
+                return Some((path.to_path_buf(), format));
+            }
+        }
+
+        let file_extensions = FILE_FORMATS.iter().flat_map(|format| {
+            format
+                .file_extensions()
+                .iter()
+                .map(|ext| (*ext, SettingsFormat::File(*format)))
+        });
+        for (ext, format) in file_extensions.chain([(DOTENV_EXTENSION, SettingsFormat::DotEnv)]) {
+            let mut candidate = OsString::from(path.as_os_str());
+            candidate.push(".");
+            candidate.push(ext);
+            let candidate = PathBuf::from(candidate);
+            if fs.is_file(&candidate) {
+                return Some((candidate, format));
+            }
+        }
+
+        None
+    }
+
+    /// Reads the settings file for the path, returning `None` if it cannot be found.
+    pub fn load(fs: &dyn ConfigFs, path: &Path) -> Result<Option<Self>, SettingsError> {
+        match Self::locate(fs, path) {
+            Some((path, format)) => {
+                let contents = fs.read_to_string(&path)?;
+                let env_naming = (DEFAULT_ENV_NAMING.0.to_string(), DEFAULT_ENV_NAMING.1.to_string());
+                Ok(Some(Self { path, format, contents, env_naming, cache: None }))
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the settings file for the path, which must exist.
+    pub fn load_required(fs: &dyn ConfigFs, path: &Path) -> Result<Self, SettingsError> {
+        Self::load(fs, path)?.ok_or_else(|| {
+            SettingsError::IO(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("configuration file {path:?} not found"),
+            ))
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    pub const fn format(&self) -> SettingsFormat {
+        self.format
+    }
+
+    pub const fn contents(&self) -> &str {
+        self.contents.as_str()
+    }
+
+    /// Whether the file exists but holds nothing other than whitespace. Empty files contribute an
+    /// empty layer, whatever their format, rather than failing to parse.
+    pub fn is_empty(&self) -> bool {
+        self.contents.trim().is_empty()
+    }
+
+    /// Sets the environment variable prefix and path separator used to name the variables in a
+    /// dotenv file.
+    pub fn with_env_naming(self, prefix: impl Into<String>, separator: impl Into<String>) -> Self {
+        Self {
+            env_naming: (prefix.into(), separator.into()),
+            ..self
+        }
+    }
+
+    /// Caches the parsed settings of the file, keyed by its path and contents.
+    pub fn with_cache(self, cache: Option<Arc<LayerCache>>) -> Self {
+        Self { cache, ..self }
+    }
+
+    fn parse(&self) -> Result<Map<String, Value>, ConfigError> {
+        let uri = self.path.to_string_lossy().into_owned();
+        match self.format {
+            SettingsFormat::File(format) => format
+                .parse(Some(&uri), &self.contents)
+                .map_err(|cause| ConfigError::FileParse { uri: Some(uri), cause }),
+            SettingsFormat::DotEnv => {
+                let vars = parse_dotenv(&self.contents)
+                    .map_err(|cause| ConfigError::FileParse { uri: Some(uri), cause: cause.into() })?;
+                let (prefix, separator) = &self.env_naming;
+                config::Environment::with_prefix(prefix)
+                    .separator(separator)
+                    .source(Some(vars))
+                    .collect()
+            },
+        }
+    }
+}
+
+impl Source for ConfigFile {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        if self.is_empty() {
+            return Ok(Map::new());
+        }
+
+        self.cache.as_ref().map_or_else(
+            || self.parse(),
+            |cache| {
+                let fingerprint = (&self.contents, format!("{:?}", self.format), &self.env_naming);
+                cache.get_or_parse(&self.path, fingerprint, || self.parse())
+            },
+        )
+    }
+}
+
+pub(crate) fn format_for_extension(ext: &str) -> Option<SettingsFormat> {
+    if ext == DOTENV_EXTENSION {
+        return Some(SettingsFormat::DotEnv);
+    }
+
+    FILE_FORMATS
+        .iter()
+        .find(|format| format.file_extensions().contains(&ext))
+        .map(|format| SettingsFormat::File(*format))
+}
+
+/// Parses the `KEY=value` lines of a dotenv file. Blank lines and `#` comments are skipped, an
+/// `export` prefix is allowed, and values may be single- or double-quoted.
+fn parse_dotenv(contents: &str) -> Result<Map<String, String>, String> {
+    let mut vars = Map::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected KEY=value", number + 1))?;
+        let value = value.trim();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value
+                .strip_prefix(quote)
+                .and_then(|v| v.strip_suffix(quote))
+                .ok_or_else(|| format!("line {}: unterminated quoted value", number + 1))?
+                .to_string(),
+            _ => value.split(" #").next().unwrap_or_default().trim_end().to_string(),
+        };
+        vars.insert(key.trim().to_string(), value);
+    }
+    Ok(vars)
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::Source;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_locate_with_and_without_extension() {
+        let fs = MemoryFs::new()
+            .with_file("conf/application.yaml", "foo: bar")
+            .with_file("conf/local.toml", "foo = \"zed\"");
+
+        let (path, format) = assert_some!(ConfigFile::locate(&fs, Path::new("conf/application")));
+        assert_eq!(path, PathBuf::from("conf/application.yaml"));
+        assert_eq!(format, SettingsFormat::File(FileFormat::Yaml));
+
+        let (path, format) = assert_some!(ConfigFile::locate(&fs, Path::new("conf/local.toml")));
+        assert_eq!(path, PathBuf::from("conf/local.toml"));
+        assert_eq!(format, SettingsFormat::File(FileFormat::Toml));
+
+        assert_none!(ConfigFile::locate(&fs, Path::new("conf/production")));
+        assert_err!(ConfigFile::load_required(&fs, Path::new("conf/production")));
+    }
+
+    #[test]
+    fn test_load_ini_and_dotenv() {
+        let fs = MemoryFs::new()
+            .with_file("conf/application.ini", "[database]\nhost = localhost\nport = 5432")
+            .with_file(
+                "conf/local.env",
+                "# local overrides\nexport APP__DATABASE__HOST=db.local\nAPP__DATABASE__PASSWORD=\"pass # \
+                 word\"\nAPP__FOO=bar # trailing\nOTHER=ignored",
+            );
+
+        let ini = assert_some!(assert_ok!(ConfigFile::load(&fs, Path::new("conf/application"))));
+        assert_eq!(ini.format(), SettingsFormat::File(FileFormat::Ini));
+        let database = assert_ok!(assert_some!(assert_ok!(ini.collect()).remove("database")).into_table());
+        assert_eq!(assert_some!(database.get("host")).to_string(), "localhost");
+
+        let dotenv = assert_some!(assert_ok!(ConfigFile::load(&fs, Path::new("conf/local"))));
+        assert_eq!(dotenv.format(), SettingsFormat::DotEnv);
+        let mut actual = assert_ok!(dotenv.collect());
+        assert_eq!(assert_some!(actual.remove("foo")).to_string(), "bar");
+        assert_eq!(assert_some!(actual.remove("database.host")).to_string(), "db.local");
+        assert_eq!(
+            assert_some!(actual.remove("database.password")).to_string(),
+            "pass # word"
+        );
+        assert!(actual.is_empty());
+
+        assert_err!(parse_dotenv("NOT A VAR"));
+    }
+
+    #[test]
+    fn test_empty_file_is_empty_layer() {
+        let fs = MemoryFs::new()
+            .with_file("conf/application.json", "")
+            .with_file("conf/local.yaml", "  \n")
+            .with_file("conf/production.ron", "")
+            .with_file("conf/staging.json", "{");
+
+        for path in ["conf/application", "conf/local", "conf/production"] {
+            let file = assert_some!(assert_ok!(ConfigFile::load(&fs, Path::new(path))));
+            assert!(file.is_empty());
+            assert!(assert_ok!(file.collect()).is_empty());
+        }
+
+        let malformed = assert_some!(assert_ok!(ConfigFile::load(&fs, Path::new("conf/staging"))));
+        assert!(!malformed.is_empty());
+        assert_err!(malformed.collect());
+        assert_none!(assert_ok!(ConfigFile::load(&fs, Path::new("conf/missing"))));
+    }
+
+    #[test]
+    fn test_memory_fs_fallback() {
+        let real: Arc<dyn ConfigFs> = Arc::new(RealFs);
+        let fs = MemoryFs::with_fallback(real).with_file("resources/local.yaml", "foo: simulated");
+
+        let local = assert_some!(assert_ok!(ConfigFile::load(&fs, Path::new("resources/local"))));
+        assert_eq!(local.contents(), "foo: simulated");
+
+        let production = assert_some!(assert_ok!(ConfigFile::load(&fs, Path::new("resources/production"))));
+        assert_eq!(production.format(), SettingsFormat::File(FileFormat::Yaml));
+        assert!(production.contents().contains("without_options"));
+    }
+}
@@ -0,0 +1,114 @@
+use std::fmt;
+use std::sync::Arc;
+
+use arc_swap::ArcSwapOption;
+
+use crate::SettingsError;
+
+/// A process-wide settings cell, read lock-free and without poisoning.
+///
+/// Declare it as a `static`, initialize it once at startup, and read it anywhere:
+///
+/// ```
+/// use settings_loader::global::GlobalSettings;
+///
+/// #[derive(Debug)]
+/// struct AppSettings {
+///     port: u16,
+/// }
+///
+/// static SETTINGS: GlobalSettings<AppSettings> = GlobalSettings::new();
+///
+/// SETTINGS.init(AppSettings { port: 8080 }).unwrap();
+/// assert_eq!(SETTINGS.get().port, 8080);
+/// ```
+///
+/// Readers hold an `Arc` snapshot, so settings swapped in by `replace()` (e.g., on reload) are seen
+/// by subsequent reads without disturbing readers of the previous settings.
+pub struct GlobalSettings<T> {
+    settings: ArcSwapOption<T>,
+}
+
+impl<T> GlobalSettings<T> {
+    pub const fn new() -> Self {
+        Self { settings: ArcSwapOption::const_empty() }
+    }
+
+    /// Initializes the settings; fails if they were already initialized.
+    pub fn init(&self, settings: T) -> Result<(), SettingsError> {
+        let previous = self.settings.compare_and_swap(&None::<Arc<T>>, Some(Arc::new(settings)));
+        if previous.is_some() {
+            return Err(SettingsError::Bootstrap {
+                message: "global settings already initialized".to_string(),
+                setting: std::any::type_name::<T>().to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// The current settings.
+    ///
+    /// # Panics
+    /// Panics if the settings have not been initialized.
+    pub fn get(&self) -> Arc<T> {
+        self.try_get()
+            .unwrap_or_else(|| panic!("global {} settings not initialized", std::any::type_name::<T>()))
+    }
+
+    pub fn try_get(&self) -> Option<Arc<T>> {
+        self.settings.load_full()
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.settings.load().is_some()
+    }
+
+    /// Replaces the settings, e.g., with reloaded settings, returning the previous settings.
+    pub fn replace(&self, settings: T) -> Option<Arc<T>> {
+        self.settings.swap(Some(Arc::new(settings)))
+    }
+
+    /// Clears the settings so they can be initialized again; intended for tests.
+    pub fn reset(&self) -> Option<Arc<T>> {
+        self.settings.swap(None)
+    }
+}
+
+impl<T> Default for GlobalSettings<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for GlobalSettings<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("GlobalSettings").field(&self.settings.load()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    static SETTINGS: GlobalSettings<String> = GlobalSettings::new();
+
+    #[test]
+    fn test_global_settings() {
+        assert_none!(SETTINGS.try_get());
+        assert_ok!(SETTINGS.init("first".to_string()));
+        assert_err!(SETTINGS.init("again".to_string()));
+        let snapshot = SETTINGS.get();
+
+        assert_eq!(*assert_some!(SETTINGS.replace("second".to_string())), "first");
+        assert_eq!(*SETTINGS.get(), "second");
+        assert_eq!(*snapshot, "first");
+
+        assert_some!(SETTINGS.reset());
+        assert!(!SETTINGS.is_initialized());
+        assert_ok!(SETTINGS.init("third".to_string()));
+        assert_eq!(*SETTINGS.get(), "third");
+    }
+}
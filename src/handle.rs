@@ -0,0 +1,220 @@
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+use arc_swap::ArcSwap;
+use serde::de::DeserializeOwned;
+
+use crate::{SettingsError, SettingsLoader};
+
+/// A shared handle to live settings, the canonical way to share them across threads.
+///
+/// Reads via `get()` are lock-free snapshots. Settings replaced by `reload()`, or by watched
+/// settings files changing when `watch()` is enabled, are seen by subsequent reads and delivered
+/// to subscribers, while readers of the previous settings are undisturbed. Clones share the same
+/// settings.
+///
+/// ```no_run
+/// # use serde::Deserialize;
+/// use settings_loader::{Settings, SettingsLoader};
+///
+/// #[derive(Debug, Deserialize)]
+/// struct AppSettings {
+///     port: u16,
+/// }
+///
+/// impl SettingsLoader for AppSettings {
+///     type Options = ();
+/// }
+///
+/// let settings = Settings::<AppSettings>::load(()).unwrap();
+/// let updates = settings.subscribe();
+/// settings.reload().unwrap();
+/// assert_eq!(updates.recv().unwrap().port, settings.get().port);
+/// ```
+pub struct Settings<S: SettingsLoader> {
+    shared: Arc<Shared<S>>,
+}
+
+struct Shared<S: SettingsLoader> {
+    current: ArcSwap<S>,
+    options: S::Options,
+    subscribers: Mutex<Vec<Sender<Arc<S>>>>,
+}
+
+impl<S: SettingsLoader> Clone for Settings<S> {
+    fn clone(&self) -> Self {
+        Self { shared: self.shared.clone() }
+    }
+}
+
+impl<S: SettingsLoader> fmt::Debug for Settings<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Settings")
+            .field("current", &self.get())
+            .field("options", &self.shared.options)
+            .finish()
+    }
+}
+
+impl<S: SettingsLoader> Settings<S> {
+    /// Wraps settings already loaded for the options, which are used to reload them.
+    pub fn new(settings: S, options: S::Options) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                current: ArcSwap::from_pointee(settings),
+                options,
+                subscribers: Mutex::new(Vec::default()),
+            }),
+        }
+    }
+
+    /// The current settings.
+    pub fn get(&self) -> Arc<S> {
+        self.shared.current.load_full()
+    }
+
+    pub fn options(&self) -> &S::Options {
+        &self.shared.options
+    }
+
+    /// Receives the settings each time they are replaced, until the receiver is dropped.
+    pub fn subscribe(&self) -> Receiver<Arc<S>> {
+        let (tx, rx) = mpsc::channel();
+        self.lock_subscribers().push(tx);
+        rx
+    }
+
+    /// Replaces the settings and notifies the subscribers, returning the new settings.
+    pub fn replace(&self, settings: S) -> Arc<S> {
+        let settings = Arc::new(settings);
+        self.shared.current.store(settings.clone());
+        self.lock_subscribers()
+            .retain(|subscriber| subscriber.send(settings.clone()).is_ok());
+        settings
+    }
+
+    fn lock_subscribers(&self) -> std::sync::MutexGuard<'_, Vec<Sender<Arc<S>>>> {
+        self.shared
+            .subscribers
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl<S> Settings<S>
+where
+    S: SettingsLoader + DeserializeOwned,
+{
+    /// Loads the settings for the options.
+    pub fn load(options: S::Options) -> Result<Self, SettingsError> {
+        Ok(Self::new(S::load(&options)?, options))
+    }
+
+    /// Loads the settings again, replacing the current settings if they load. The current settings
+    /// are kept if the load fails.
+    pub fn reload(&self) -> Result<Arc<S>, SettingsError> {
+        let settings = S::load(&self.shared.options)?;
+        tracing::info!("settings reloaded");
+        Ok(self.replace(settings))
+    }
+
+    /// Watches the settings files, replacing the settings each time they change; see
+    /// `SettingsLoader::watch()`. Changes that fail to load are logged and the current settings
+    /// kept. Watching stops once every handle to the settings is dropped.
+    #[cfg(feature = "watch")]
+    pub fn watch(&self) -> Result<(), SettingsError>
+    where
+        S: Send + Sync + 'static,
+        S::Options: Clone + Send + Sync + 'static,
+    {
+        let watcher = S::watch(self.shared.options.clone())?;
+        let shared = Arc::downgrade(&self.shared);
+        std::thread::Builder::new()
+            .name("settings-handle".to_string())
+            .spawn(move || {
+                while let Ok(update) = watcher.updates().recv() {
+                    let Some(shared) = shared.upgrade() else {
+                        return;
+                    };
+                    match update {
+                        Ok(settings) => {
+                            Self { shared }.replace(settings);
+                        },
+                        Err(err) => tracing::warn!(error=%err, "watched settings failed to reload; keeping current"),
+                    }
+                }
+            })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use claim::*;
+    use pretty_assertions::assert_eq;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::LoadingOptions;
+
+    #[derive(Debug, Clone)]
+    struct FileOptions(PathBuf);
+
+    impl LoadingOptions for FileOptions {
+        type Error = SettingsError;
+
+        fn config_path(&self) -> Option<PathBuf> {
+            Some(self.0.clone())
+        }
+
+        fn secrets_path(&self) -> Option<PathBuf> {
+            None
+        }
+
+        fn implicit_search_paths(&self) -> Vec<PathBuf> {
+            Vec::default()
+        }
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct HandleSettings {
+        workers: u32,
+    }
+
+    impl SettingsLoader for HandleSettings {
+        type Options = FileOptions;
+    }
+
+    #[test]
+    fn test_settings_handle_reload_and_subscribe() {
+        let dir = std::env::temp_dir().join(format!("settings-handle-{}", std::process::id()));
+        assert_ok!(fs::create_dir_all(&dir));
+        let path = dir.join("application.yaml");
+        assert_ok!(fs::write(&path, "workers: 4"));
+
+        let settings = assert_ok!(Settings::<HandleSettings>::load(FileOptions(path.clone())));
+        let shared = settings.clone();
+        let updates = settings.subscribe();
+        let before = settings.get();
+        assert_eq!(before.workers, 4);
+
+        assert_ok!(fs::write(&path, "workers: 8"));
+        assert_eq!(assert_ok!(settings.reload()).workers, 8);
+        assert_eq!(shared.get().workers, 8);
+        assert_eq!(before.workers, 4);
+        assert_eq!(assert_ok!(updates.recv()).workers, 8);
+
+        assert_ok!(fs::write(&path, "workers: many"));
+        assert_err!(settings.reload());
+        assert_eq!(shared.get().workers, 8);
+
+        drop(updates);
+        settings.replace(HandleSettings { workers: 2 });
+        assert!(settings.lock_subscribers().is_empty());
+        let _ignored = fs::remove_dir_all(&dir);
+    }
+}
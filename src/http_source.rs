@@ -0,0 +1,293 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use config::{ConfigError, FileFormat, Format, Map, Source, Value};
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+
+/// Default time allowed for each request to the configuration service.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default number of times a failed request is retried.
+const DEFAULT_RETRIES: u32 = 2;
+
+/// Delay before the first retry, which doubles for each subsequent retry.
+const RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Settings fetched from a URL, e.g., a central configuration service, merged like a settings
+/// file via `LoadingOptions::http_sources()`.
+///
+/// Responses are revalidated with `If-None-Match` and `If-Modified-Since`, so an unchanged document
+/// is not downloaded again by later loads sharing the source (or its clones). Transport errors and
+/// server errors are retried with backoff. The source uses a blocking client, so it must not be
+/// loaded from within an async runtime's worker thread.
+#[derive(Debug, Clone)]
+pub struct HttpSource {
+    url: String,
+    format: FileFormat,
+    timeout: Duration,
+    retries: u32,
+    cached: Arc<Mutex<Option<CachedResponse>>>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedResponse {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+}
+
+impl HttpSource {
+    pub fn new(url: impl Into<String>, format: FileFormat) -> Self {
+        Self {
+            url: url.into(),
+            format,
+            timeout: DEFAULT_TIMEOUT,
+            retries: DEFAULT_RETRIES,
+            cached: Arc::default(),
+        }
+    }
+
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    pub fn with_retries(self, retries: u32) -> Self {
+        Self { retries, ..self }
+    }
+
+    pub const fn url(&self) -> &str {
+        self.url.as_str()
+    }
+
+    /// Fetches the document, or reuses the cached document if the service reports it unchanged.
+    fn fetch(&self) -> Result<String, ConfigError> {
+        let client = Client::builder().timeout(self.timeout).build().map_err(foreign)?;
+        let cached = self.cached.lock().map_or(None, |cached| cached.clone());
+
+        let mut headers = HeaderMap::new();
+        if let Some(ref cached) = cached {
+            if let Some(etag) = cached.etag.as_ref().and_then(|v| v.parse().ok()) {
+                headers.insert(IF_NONE_MATCH, etag);
+            }
+            if let Some(modified) = cached.last_modified.as_ref().and_then(|v| v.parse().ok()) {
+                headers.insert(IF_MODIFIED_SINCE, modified);
+            }
+        }
+
+        let mut attempt = 0;
+        let response = loop {
+            let result = client.get(&self.url).headers(headers.clone()).send().and_then(|r| {
+                if r.status().is_server_error() {
+                    r.error_for_status()
+                } else {
+                    Ok(r)
+                }
+            });
+            match result {
+                Ok(response) => break response,
+                Err(err) if attempt < self.retries => {
+                    let backoff = RETRY_BACKOFF * 2_u32.pow(attempt);
+                    tracing::warn!(url=%self.url, error=%err, ?backoff, "retrying settings request");
+                    std::thread::sleep(backoff);
+                    attempt += 1;
+                },
+                Err(err) => return Err(foreign(err)),
+            }
+        };
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(cached) = cached {
+                tracing::debug!(url=%self.url, "remote settings not modified");
+                return Ok(cached.body);
+            }
+        }
+
+        let response = response.error_for_status().map_err(foreign)?;
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(ToString::to_string)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        let body = response.text().map_err(foreign)?;
+        if let Ok(mut cached) = self.cached.lock() {
+            *cached = Some(CachedResponse { etag, last_modified, body: body.clone() });
+        }
+        Ok(body)
+    }
+}
+
+fn foreign(err: reqwest::Error) -> ConfigError {
+    ConfigError::Foreign(Box::new(err))
+}
+
+impl Source for HttpSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let body = self.fetch()?;
+        self.format
+            .parse(Some(&self.url), &body)
+            .map_err(|cause| ConfigError::FileParse { uri: Some(self.url.clone()), cause })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+
+    use claim::*;
+    use pretty_assertions::assert_eq;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::fs::MemoryFs;
+    use crate::{ConfigFs, Layer, LoadingOptions, SettingsError, SettingsLoader};
+
+    /// Serves the responses in turn, returning the requests' `If-None-Match` headers.
+    fn serve(responses: Vec<&'static str>) -> (String, std::thread::JoinHandle<Vec<Option<String>>>) {
+        let listener = assert_ok!(TcpListener::bind("127.0.0.1:0"));
+        let url = format!("http://{}/settings.json", assert_ok!(listener.local_addr()));
+        let handle = std::thread::spawn(move || {
+            let mut validators = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut validator = None;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("if-none-match") {
+                            validator = Some(value.trim().to_string());
+                        }
+                    }
+                }
+                validators.push(validator);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            validators
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn test_http_source_revalidates_and_retries() {
+        let (url, server) = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\netag: \"v1\"\r\ncontent-length: 14\r\nconnection: close\r\n\r\n{\"foo\": \"bar\"}",
+            "HTTP/1.1 304 Not Modified\r\netag: \"v1\"\r\nconnection: close\r\n\r\n",
+        ]);
+        let source = HttpSource::new(url, FileFormat::Json).with_retries(1);
+
+        let actual = assert_ok!(source.collect());
+        assert_eq!(assert_some!(actual.get("foo")).to_string(), "bar");
+        let actual = assert_ok!(source.collect());
+        assert_eq!(assert_some!(actual.get("foo")).to_string(), "bar");
+
+        let validators = assert_ok!(server.join());
+        assert_eq!(validators, vec![None, None, Some("\"v1\"".to_string())]);
+    }
+
+    #[derive(Debug)]
+    struct RemoteOptions {
+        config_path: Option<PathBuf>,
+        url: String,
+    }
+
+    impl LoadingOptions for RemoteOptions {
+        type Error = SettingsError;
+
+        fn config_path(&self) -> Option<PathBuf> {
+            self.config_path.clone()
+        }
+
+        fn secrets_path(&self) -> Option<PathBuf> {
+            None
+        }
+
+        fn implicit_search_paths(&self) -> Vec<PathBuf> {
+            vec!["./remote".into()]
+        }
+
+        fn config_fs(&self) -> Arc<dyn ConfigFs> {
+            Arc::new(
+                MemoryFs::new()
+                    .with_file("remote/application.yaml", "foo: file\nport: 8000")
+                    .with_file("remote/explicit.yaml", "foo: explicit\nport: 8080"),
+            )
+        }
+
+        fn environment_override(&self) -> Option<crate::Environment> {
+            None
+        }
+
+        fn http_sources(&self) -> Vec<HttpSource> {
+            vec![HttpSource::new(self.url.clone(), FileFormat::Json).with_retries(0)]
+        }
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct RemoteSettings {
+        foo: String,
+        port: u16,
+    }
+
+    impl SettingsLoader for RemoteSettings {
+        type Options = RemoteOptions;
+
+        fn environment_prefix() -> &'static str {
+            "http_source_test"
+        }
+    }
+
+    #[test]
+    fn test_http_source_fetched_once_per_load() {
+        for config_path in [None, Some(PathBuf::from("remote/explicit.yaml"))] {
+            let (url, server) = serve(vec![
+                "HTTP/1.1 200 OK\r\ncontent-length: 17\r\nconnection: close\r\n\r\n{\"foo\": \"remote\"}",
+            ]);
+            let options = RemoteOptions { config_path: config_path.clone(), url: url.clone() };
+
+            let (actual, report) = assert_ok!(RemoteSettings::load_with_report(&options));
+            assert_eq!(actual.foo, "remote");
+            assert_eq!(actual.port, if config_path.is_some() { 8080 } else { 8000 });
+            let layers: Vec<_> = report
+                .layers
+                .iter()
+                .map(|l| (l.layer, l.origin.ends_with(".yaml")))
+                .collect();
+            assert_eq!(&layers[..2], &[(Layer::Config, true), (Layer::Config, false)]);
+            assert_eq!(report.layers[1].origin, url);
+            assert_eq!(assert_ok!(server.join()).len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_http_source_explains_failure_from_single_fetch() {
+        let (url, server) = serve(vec![
+            "HTTP/1.1 200 OK\r\ncontent-length: 15\r\nconnection: close\r\n\r\n{\"port\": \"abc\"}",
+        ]);
+        let options = RemoteOptions { config_path: None, url: url.clone() };
+
+        let err = assert_err!(RemoteSettings::load(&options));
+        assert_eq!(assert_ok!(server.join()).len(), 1);
+        let SettingsError::LayerTypeConflict { ref key, ref second_origin, .. } = err else {
+            panic!("expected layer type conflict but got: {err:?}");
+        };
+        assert_eq!(key, "port");
+        assert_eq!(second_origin, &url);
+    }
+}
@@ -0,0 +1,209 @@
+use std::path::{Path, PathBuf};
+
+use config::{ConfigError, Map, Source, Value, ValueKind};
+use path_absolutize::*;
+
+use crate::env_filter::wildcard_matches;
+use crate::fs::{format_for_extension, ConfigFile};
+use crate::layer::LayerSource;
+use crate::{ConfigFs, Layer, SettingsError};
+
+/// Settings key listing the files a settings file includes; e.g.,
+/// `include = ["logging.toml", "db/*.yaml"]`.
+pub const INCLUDE_KEY: &str = "include";
+
+/// How deeply included files may themselves include files.
+pub const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// Splices the files included by each settings file layer in just below it, so the including
+/// file overrides the files it includes and later includes override earlier ones.
+///
+/// Included paths are relative to the including file, and a `*` in a path's file name matches any
+/// run of characters, with the matching files included in lexicographic order. Included files are
+/// loaded as layers of the including file's layer via `make_layer`.
+pub(crate) fn expand_includes(
+    fs: &dyn ConfigFs, layers: Vec<LayerSource>, make_layer: &dyn Fn(Layer, ConfigFile) -> LayerSource,
+) -> Result<Vec<LayerSource>, SettingsError> {
+    let mut expanded = Vec::with_capacity(layers.len());
+    for layer in layers {
+        let path = PathBuf::from(layer.origin());
+        if fs.is_file(&path) {
+            let mut chain = vec![normalize(&path)];
+            expand_layer(fs, layer, &mut chain, make_layer, &mut expanded)?;
+        } else {
+            expanded.push(layer);
+        }
+    }
+    Ok(expanded)
+}
+
+fn expand_layer(
+    fs: &dyn ConfigFs, layer: LayerSource, chain: &mut Vec<PathBuf>,
+    make_layer: &dyn Fn(Layer, ConfigFile) -> LayerSource, expanded: &mut Vec<LayerSource>,
+) -> Result<(), SettingsError> {
+    // Settings that fail to parse are left to fail, or be skipped, when the layers are loaded.
+    let Ok(Some(includes)) = layer.collect().map(|mut table| table.remove(INCLUDE_KEY)) else {
+        expanded.push(layer);
+        return Ok(());
+    };
+
+    let including = PathBuf::from(layer.origin());
+    let error = |message: String| SettingsError::Include { file: layer.origin().to_string(), message };
+    let patterns = include_patterns(includes).map_err(|err| error(err.to_string()))?;
+    if chain.len() > MAX_INCLUDE_DEPTH {
+        return Err(error(format!("includes nested more than {MAX_INCLUDE_DEPTH} deep")));
+    }
+
+    let dir = including.parent().unwrap_or_else(|| Path::new(""));
+    for pattern in patterns {
+        for path in resolve_pattern(fs, &dir.join(&pattern))? {
+            let normalized = normalize(&path);
+            if chain.contains(&normalized) {
+                return Err(error(format!("including {} forms a cycle", path.display())));
+            }
+
+            let file = ConfigFile::load_required(fs, &path).map_err(|err| error(err.to_string()))?;
+            let mut included = make_layer(layer.layer(), file);
+            if layer.is_optional() {
+                included = included.optional();
+            }
+            chain.push(normalized);
+            expand_layer(fs, included, chain, make_layer, expanded)?;
+            chain.pop();
+        }
+    }
+
+    tracing::info!(file=%including.display(), "included settings files");
+    expanded.push(layer.clone().with_source(WithoutIncludes(layer)));
+    Ok(())
+}
+
+fn include_patterns(includes: Value) -> Result<Vec<String>, ConfigError> {
+    match includes.kind {
+        ValueKind::Array(items) => items.into_iter().map(Value::into_string).collect(),
+        _ => Ok(vec![includes.into_string()?]),
+    }
+}
+
+/// The files matching the path, where a `*` in the file name matches any run of characters.
+fn resolve_pattern(fs: &dyn ConfigFs, path: &Path) -> Result<Vec<PathBuf>, SettingsError> {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if !name.contains('*') {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut files: Vec<_> = fs
+        .list_files(dir)?
+        .into_iter()
+        .filter(|file| {
+            let file_name = file
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let recognized = file
+                .extension()
+                .and_then(|ext| format_for_extension(&ext.to_string_lossy()))
+                .is_some();
+            recognized && wildcard_matches(&name, &file_name)
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    path.absolutize().map_or_else(|_| path.to_path_buf(), |p| p.into_owned())
+}
+
+/// Drops the include directive from the including file's settings.
+#[derive(Debug, Clone)]
+struct WithoutIncludes(LayerSource);
+
+impl Source for WithoutIncludes {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut table = self.0.collect()?;
+        table.remove(INCLUDE_KEY);
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::fs::MemoryFs;
+
+    fn make_layer(layer: Layer, file: ConfigFile) -> LayerSource {
+        LayerSource::new(layer, file.path().display().to_string(), file)
+    }
+
+    fn expand(fs: &MemoryFs) -> Result<Vec<LayerSource>, SettingsError> {
+        let file = assert_ok!(ConfigFile::load_required(fs, Path::new("/etc/app/application.toml")));
+        expand_includes(fs, vec![make_layer(Layer::Config, file)], &make_layer)
+    }
+
+    #[test]
+    fn test_expand_includes() {
+        let fs = MemoryFs::new()
+            .with_file(
+                "/etc/app/application.toml",
+                "include = [\"logging.toml\", \"db/*.yaml\"]\nname = \"app\"",
+            )
+            .with_file("/etc/app/logging.toml", "include = \"levels.toml\"\nlevel = \"info\"")
+            .with_file("/etc/app/levels.toml", "level = \"warn\"")
+            .with_file("/etc/app/db/2-replica.yaml", "replica: db2")
+            .with_file("/etc/app/db/1-primary.yaml", "primary: db1")
+            .with_file("/etc/app/db/notes.txt", "ignored");
+
+        let layers = assert_ok!(expand(&fs));
+        let origins: Vec<_> = layers.iter().map(LayerSource::origin).collect();
+        assert_eq!(
+            origins,
+            vec![
+                "/etc/app/levels.toml",
+                "/etc/app/logging.toml",
+                "/etc/app/db/1-primary.yaml",
+                "/etc/app/db/2-replica.yaml",
+                "/etc/app/application.toml",
+            ]
+        );
+        assert_none!(assert_ok!(layers[4].collect()).get(INCLUDE_KEY));
+        assert_some!(assert_ok!(layers[4].collect()).get("name"));
+    }
+
+    #[test]
+    fn test_expand_includes_rejects_cycles_and_missing_files() {
+        let fs = MemoryFs::new()
+            .with_file("/etc/app/application.toml", "include = \"logging.toml\"")
+            .with_file("/etc/app/logging.toml", "include = [\"application.toml\"]");
+        let err = assert_err!(expand(&fs));
+        assert_eq!(
+            err.to_string(),
+            "failed to include settings in /etc/app/logging.toml: including /etc/app/application.toml forms a cycle"
+        );
+
+        let fs = MemoryFs::new().with_file("/etc/app/application.toml", "include = \"missing.toml\"");
+        assert_err!(expand(&fs));
+
+        let mut fs = MemoryFs::new();
+        for depth in 0..=MAX_INCLUDE_DEPTH + 1 {
+            fs.insert(
+                format!("/etc/app/{depth}.toml"),
+                format!("include = \"{}.toml\"", depth + 1),
+            );
+        }
+        fs.insert("/etc/app/application.toml", "include = \"0.toml\"");
+        let err = assert_err!(expand(&fs));
+        assert!(err.to_string().ends_with("includes nested more than 8 deep"));
+    }
+}
@@ -0,0 +1,132 @@
+use config::{ConfigError, Map, Source, Value, ValueKind};
+
+use crate::Environment;
+
+/// Entry of an inline per-environment value used when the active environment has no entry.
+pub const DEFAULT_ENTRY: &str = "default";
+
+/// Wraps a configuration source, resolving the settings written with inline per-environment values
+/// against the active environment; e.g., `port: { default: 8080, production: 80 }`.
+///
+/// Only keys matching the declared patterns (see `LoadingOptions::per_environment_keys()`) are
+/// resolved, so ordinary nested tables are never mistaken for per-environment values. A setting
+/// with neither an entry for the environment nor a default is left unset by the layer.
+#[derive(Debug, Clone)]
+pub(crate) struct InlineEnvironmentSource<S> {
+    environment: Option<Environment>,
+    patterns: Vec<String>,
+    inner: S,
+}
+
+impl<S> InlineEnvironmentSource<S> {
+    pub(crate) const fn new(environment: Option<Environment>, patterns: Vec<String>, inner: S) -> Self {
+        Self { environment, patterns, inner }
+    }
+
+    fn is_per_environment(&self, key: &str) -> bool {
+        self.patterns.iter().any(|pattern| {
+            let mut key_segments = key.split('.');
+            let matched = pattern
+                .split('.')
+                .all(|segment| key_segments.next().is_some_and(|k| segment == "*" || segment == k));
+            matched && key_segments.next().is_none()
+        })
+    }
+
+    fn resolve(&self, prefix: Option<&str>, table: &mut Map<String, Value>) {
+        let keys: Vec<String> = table.keys().cloned().collect();
+        for key in keys {
+            let path = prefix.map_or_else(|| key.clone(), |p| format!("{p}.{key}"));
+            let Some(value) = table.get_mut(&key) else { continue };
+            let ValueKind::Table(ref mut nested) = value.kind else {
+                continue;
+            };
+
+            if !self.is_per_environment(&path) {
+                self.resolve(Some(path.as_str()), nested);
+                continue;
+            }
+
+            let env_entry = self.environment.as_ref().and_then(|env| nested.remove(env.as_ref()));
+            match env_entry.or_else(|| nested.remove(DEFAULT_ENTRY)) {
+                Some(resolved) => {
+                    table.insert(key, resolved);
+                },
+                None => {
+                    tracing::debug!(key=%path, environment=?self.environment, "no inline value for environment");
+                    table.remove(&key);
+                },
+            }
+        }
+    }
+}
+
+impl<S> Source for InlineEnvironmentSource<S>
+where
+    S: Source + Clone + Send + Sync + 'static,
+{
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut table = self.inner.collect()?;
+        if !self.patterns.is_empty() {
+            self.resolve(None, &mut table);
+        }
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::layer::LayerSource;
+    use crate::Layer;
+
+    const YAML: &str = "server:\n  port: { default: 8080, production: 80 }\n  tls: { production: true }\n  limits: { \
+                        default: 1, production: 2 }";
+
+    fn resolved(environment: Option<Environment>) -> Vec<(String, String)> {
+        let source = InlineEnvironmentSource::new(
+            environment,
+            vec!["server.port".to_string(), "*.tls".to_string()],
+            File::from_str(YAML, FileFormat::Yaml),
+        );
+        let layer = LayerSource::new(Layer::Config, "application.yaml", source);
+        assert_ok!(layer.collect_flattened())
+            .into_iter()
+            .map(|(key, value)| (key, value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_inline_environment_values() {
+        let pairs = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+
+        assert_eq!(
+            resolved(Some(Environment::from("production"))),
+            pairs(&[
+                ("server.limits.default", "1"),
+                ("server.limits.production", "2"),
+                ("server.port", "80"),
+                ("server.tls", "true"),
+            ])
+        );
+        assert_eq!(
+            resolved(Some(Environment::from("local"))),
+            pairs(&[
+                ("server.limits.default", "1"),
+                ("server.limits.production", "2"),
+                ("server.port", "8080"),
+            ])
+        );
+        assert_eq!(resolved(None).len(), 3);
+    }
+}
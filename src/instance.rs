@@ -0,0 +1,131 @@
+use std::path::{Path, PathBuf};
+
+use crate::fs::ConfigFile;
+use crate::{ConfigFs, SettingsError};
+
+/// Separates the application configuration basename from an instance name in the names of
+/// instance overlay files; e.g., `application@web-01.yaml`.
+pub const INSTANCE_SEPARATOR: char = '@';
+
+/// Identifies the running instance within a fleet, for per-instance settings overlays; see
+/// `InstanceOverlay`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct InstanceIdentity {
+    pub hostname: Option<String>,
+    /// An identity assigned by the platform; e.g., a cloud instance id or a pod name.
+    pub instance_id: Option<String>,
+}
+
+impl InstanceIdentity {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_hostname(self, hostname: impl Into<String>) -> Self {
+        Self { hostname: Some(hostname.into()), ..self }
+    }
+
+    pub fn with_instance_id(self, instance_id: impl Into<String>) -> Self {
+        Self { instance_id: Some(instance_id.into()), ..self }
+    }
+
+    /// Detects the hostname from the `HOSTNAME` environment variable or the kernel. The instance id
+    /// is left for the application to set.
+    pub fn detect() -> Self {
+        let hostname = std::env::var("HOSTNAME")
+            .ok()
+            .or_else(|| std::fs::read_to_string("/proc/sys/kernel/hostname").ok())
+            .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+            .map(|hostname| hostname.trim().to_string())
+            .filter(|hostname| !hostname.is_empty());
+        let identity = Self { hostname, instance_id: None };
+        tracing::info!(?identity, "detected instance identity");
+        identity
+    }
+
+    /// The instance's names, ordered from lowest to highest precedence: the hostname, then the
+    /// more specific instance id.
+    pub fn names(&self) -> Vec<&str> {
+        self.hostname
+            .iter()
+            .chain(self.instance_id.iter())
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// Per-instance overlays of the application configuration file, named for the instance's
+/// identity; e.g., `application@web-01.yaml` for the host `web-01`.
+///
+/// Overlays found in a resource directory override the application and environment configuration
+/// files, with an instance id overlay overriding a hostname overlay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstanceOverlay {
+    basename: String,
+    identity: InstanceIdentity,
+}
+
+impl InstanceOverlay {
+    pub fn new(basename: impl Into<String>, identity: InstanceIdentity) -> Self {
+        Self { basename: basename.into(), identity }
+    }
+
+    pub const fn identity(&self) -> &InstanceIdentity {
+        &self.identity
+    }
+
+    /// The paths of the overlay files in the directory, without their extensions, ordered from
+    /// lowest to highest precedence.
+    pub fn paths(&self, dir: &Path) -> Vec<PathBuf> {
+        self.identity
+            .names()
+            .into_iter()
+            .map(|name| dir.join(format!("{}{INSTANCE_SEPARATOR}{name}", self.basename)))
+            .collect()
+    }
+
+    /// The overlay files present in the directory, ordered from lowest to highest precedence.
+    pub fn files(&self, fs: &dyn ConfigFs, dir: &Path) -> Result<Vec<ConfigFile>, SettingsError> {
+        let mut files = Vec::default();
+        for path in self.paths(dir) {
+            if let Some(file) = ConfigFile::load(fs, &path)? {
+                tracing::info!(path=?file.path(), "adding instance overlay configuration source");
+                files.push(file);
+            }
+        }
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::fs::MemoryFs;
+
+    #[test]
+    fn test_instance_overlay_files() {
+        let fs = MemoryFs::new()
+            .with_file("/etc/app/application@web-01.yaml", "workers: 8")
+            .with_file("/etc/app/application@i-0abc.toml", "workers = 16")
+            .with_file("/etc/app/application@web-02.yaml", "workers: 4");
+        let identity = InstanceIdentity::new().with_hostname("web-01").with_instance_id("i-0abc");
+        assert_eq!(identity.names(), vec!["web-01", "i-0abc"]);
+
+        let overlay = InstanceOverlay::new("application", identity);
+        let files = assert_ok!(overlay.files(&fs, Path::new("/etc/app")));
+        let paths: Vec<_> = files.iter().map(ConfigFile::path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("/etc/app/application@web-01.yaml"),
+                Path::new("/etc/app/application@i-0abc.toml"),
+            ]
+        );
+
+        let overlay = InstanceOverlay::new("application", InstanceIdentity::new().with_hostname("web-03"));
+        assert!(assert_ok!(overlay.files(&fs, Path::new("/etc/app"))).is_empty());
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use config::{Map, Value, ValueKind};
+
+use crate::layer::flatten_into;
+use crate::{ConfigFs, SettingsError};
+
+/// Substitutes the references in the merged settings' string values.
+///
+/// References are written as `${env:VAR}` for an environment variable, `${file:/path}` for the
+/// trimmed contents of a file, and `${setting:other.key}` (or simply `${other.key}`) for another
+/// setting, which is itself interpolated first. `$${` is written for a literal `${`. Values keep
+/// their origins, and reference cycles are reported as errors.
+pub fn interpolate(table: Map<String, Value>, fs: &dyn ConfigFs) -> Result<Map<String, Value>, SettingsError> {
+    let mut flattened = BTreeMap::new();
+    flatten_into(None, table.clone(), &mut flattened);
+    let mut interpolator = Interpolator { fs, settings: flattened, resolved: HashMap::new() };
+
+    let mut table = table;
+    interpolator.interpolate_table(None, &mut table)?;
+    Ok(table)
+}
+
+struct Interpolator<'a> {
+    fs: &'a dyn ConfigFs,
+    settings: BTreeMap<String, Value>,
+    resolved: HashMap<String, String>,
+}
+
+impl Interpolator<'_> {
+    fn interpolate_table(&mut self, prefix: Option<&str>, table: &mut Map<String, Value>) -> Result<(), SettingsError> {
+        for (key, value) in table.iter_mut() {
+            let path = prefix.map_or_else(|| key.clone(), |p| format!("{p}.{key}"));
+            self.interpolate_value(&path, value)?;
+        }
+        Ok(())
+    }
+
+    fn interpolate_value(&mut self, path: &str, value: &mut Value) -> Result<(), SettingsError> {
+        match value.kind {
+            ValueKind::Table(ref mut nested) => self.interpolate_table(Some(path), nested),
+            ValueKind::Array(ref mut items) => {
+                for item in items.iter_mut() {
+                    self.interpolate_value(path, item)?;
+                }
+                Ok(())
+            },
+            ValueKind::String(ref mut rep) if rep.contains("${") => {
+                *rep = self.expand(path, rep, &mut vec![path.to_string()])?;
+                Ok(())
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn expand(&mut self, path: &str, rep: &str, stack: &mut Vec<String>) -> Result<String, SettingsError> {
+        let error = |message: String| SettingsError::Bootstrap { message, setting: path.to_string() };
+
+        let mut expanded = String::with_capacity(rep.len());
+        let mut rest = rep;
+        while let Some(start) = rest.find("${") {
+            if rest[..start].ends_with('$') {
+                expanded.push_str(&rest[..start - 1]);
+                expanded.push_str("${");
+                rest = &rest[start + 2..];
+                continue;
+            }
+
+            expanded.push_str(&rest[..start]);
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| error(format!("unterminated reference in `{rep}`")))?;
+            let reference = &rest[start + 2..start + end];
+            let value = match reference.split_once(':') {
+                Some(("env", var)) => {
+                    std::env::var(var).map_err(|err| error(format!("cannot interpolate ${{env:{var}}}: {err}")))?
+                },
+                Some(("file", file)) => self
+                    .fs
+                    .read_to_string(Path::new(file))
+                    .map(|contents| contents.trim().to_string())
+                    .map_err(|err| error(format!("cannot interpolate ${{file:{file}}}: {err}")))?,
+                Some(("setting", key)) => self.setting(key, stack)?,
+                None => self.setting(reference, stack)?,
+                Some((kind, _)) => return Err(error(format!("unknown reference kind `{kind}` in `{rep}`"))),
+            };
+            expanded.push_str(&value);
+            rest = &rest[start + end + 1..];
+        }
+        expanded.push_str(rest);
+        Ok(expanded)
+    }
+
+    fn setting(&mut self, key: &str, stack: &mut Vec<String>) -> Result<String, SettingsError> {
+        if let Some(resolved) = self.resolved.get(key) {
+            return Ok(resolved.clone());
+        }
+
+        if stack.iter().any(|k| k == key) {
+            stack.push(key.to_string());
+            return Err(SettingsError::Bootstrap {
+                message: format!("interpolation cycle: {}", stack.join(" -> ")),
+                setting: key.to_string(),
+            });
+        }
+
+        let value = self.settings.get(key).cloned().ok_or_else(|| SettingsError::Bootstrap {
+            message: "interpolated setting not found".to_string(),
+            setting: key.to_string(),
+        })?;
+        let resolved = match value.kind {
+            ValueKind::String(ref rep) if rep.contains("${") => {
+                stack.push(key.to_string());
+                let resolved = self.expand(key, rep, stack)?;
+                stack.pop();
+                resolved
+            },
+            _ => value.to_string(),
+        };
+        self.resolved.insert(key.to_string(), resolved.clone());
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{Config, File, FileFormat, Source};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::fs::MemoryFs;
+
+    fn yaml_table(yaml: &str) -> Map<String, Value> {
+        let config = assert_ok!(Config::builder()
+            .add_source(File::from_str(yaml, FileFormat::Yaml))
+            .build());
+        assert_ok!(config.collect())
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let fs = MemoryFs::new().with_file("secrets/db_password", "hunter2\n");
+        let yaml = "database:\n  user: app\n  host: ${setting:database.primary}\n  primary: db.local\n  port: 5432\n  \
+                    name: orders\n  password: '${file:secrets/db_password}'\n  url: \
+                    postgres://${database.user}@${database.host}:${database.port}/${database.name}\nliteral: cost is \
+                    $${price}\nhome: ${env:INTERPOLATE_TEST_UNSET_HOME}";
+
+        assert_err!(interpolate(yaml_table(yaml), &fs));
+
+        let yaml = yaml.replace("${env:INTERPOLATE_TEST_UNSET_HOME}", "none");
+        let actual = assert_ok!(interpolate(yaml_table(&yaml), &fs));
+        let mut flattened = BTreeMap::new();
+        flatten_into(None, actual, &mut flattened);
+        let get = |key: &str| assert_some!(flattened.get(key)).to_string();
+        assert_eq!(get("database.url"), "postgres://app@db.local:5432/orders");
+        assert_eq!(get("database.password"), "hunter2");
+        assert_eq!(get("literal"), "cost is ${price}");
+        assert_eq!(get("database.port"), "5432");
+
+        let cycle = yaml_table("a: ${b}\nb: x${c}\nc: ${a}");
+        let err = assert_err!(interpolate(cycle, &fs));
+        assert!(err.to_string().contains("interpolation cycle"), "{err}");
+    }
+}
@@ -0,0 +1,509 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use config::{Config, ConfigError, File, FileFormat, Map, Source, Value, ValueKind};
+use serde::{Deserialize, Serialize};
+
+use crate::SettingsError;
+
+/// The layers of configuration composed by `SettingsLoader::load()`, listed from lowest to
+/// highest precedence.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layer {
+    /// Explicit configuration file or the implicitly found application configuration.
+    Config,
+    /// Application environment (e.g., `local` or `production`) configuration overrides.
+    EnvironmentConfig,
+    /// Secrets file.
+    Secrets,
+    /// Environment variables.
+    EnvironmentVariables,
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Config => "config",
+            Self::EnvironmentConfig => "environment-config",
+            Self::Secrets => "secrets",
+            Self::EnvironmentVariables => "environment-variables",
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// The precedence of the layers relative to each other. CLI option overrides always take precedence
+/// over every layer.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PrecedenceProfile {
+    /// Environment variables override the secrets file, which overrides the configuration files.
+    #[default]
+    CliEnvFile,
+
+    /// The configuration files and secrets file, e.g., managed by orchestration, override ambient
+    /// environment variables.
+    CliFileEnv,
+
+    /// The layers listed from lowest to highest precedence. Unlisted layers take the lowest
+    /// precedence.
+    Custom(Vec<Layer>),
+}
+
+impl PrecedenceProfile {
+    /// The layers ordered from lowest to highest precedence.
+    pub fn order(&self) -> Vec<Layer> {
+        use Layer::*;
+        match self {
+            Self::CliEnvFile => vec![Config, EnvironmentConfig, Secrets, EnvironmentVariables],
+            Self::CliFileEnv => vec![EnvironmentVariables, Config, EnvironmentConfig, Secrets],
+            Self::Custom(order) => order.clone(),
+        }
+    }
+
+    /// The precedence rank of the layer; higher ranks override lower ranks.
+    pub fn rank(&self, layer: Layer) -> usize {
+        self.order()
+            .iter()
+            .position(|l| *l == layer)
+            .map_or(0, |position| position + 1)
+    }
+
+    /// Orders the layers by precedence, keeping the order of layers of equal rank.
+    pub fn sort(&self, layers: &mut [LayerSource]) {
+        layers.sort_by_key(|l| self.rank(l.layer()));
+    }
+}
+
+/// A configuration source loaded for a layer, along with a description of where it came from;
+/// e.g., the file path.
+#[derive(Debug, Clone)]
+pub struct LayerSource {
+    layer: Layer,
+    origin: String,
+    read_at: Option<SystemTime>,
+    optional: bool,
+    source: Box<dyn Source + Send + Sync>,
+    restricted: Option<Unrestricted>,
+}
+
+/// The restrictions applied to a layer, along with its source before they were applied.
+#[derive(Debug, Clone)]
+struct Unrestricted {
+    restrictions: Arc<Vec<KeyRestriction>>,
+    source: Box<dyn Source + Send + Sync>,
+}
+
+impl LayerSource {
+    pub fn new<S>(layer: Layer, origin: impl Into<String>, source: S) -> Self
+    where
+        S: Source + Send + Sync + 'static,
+    {
+        Self {
+            layer,
+            origin: origin.into(),
+            read_at: None,
+            optional: false,
+            source: Box::new(source),
+            restricted: None,
+        }
+    }
+
+    /// A layer of settings held in memory in the given format, such as defaults embedded in the
+    /// application or settings supplied by a test, without a file on disk.
+    pub fn from_contents(layer: Layer, origin: impl Into<String>, contents: &str, format: FileFormat) -> Self {
+        Self::new(layer, origin, File::from_str(contents, format))
+    }
+
+    /// A layer of settings held in memory as a JSON object, whose values record the origin.
+    pub fn from_value(
+        layer: Layer, origin: impl Into<String>, value: &serde_json::Value,
+    ) -> Result<Self, SettingsError> {
+        let origin = origin.into();
+        if !value.is_object() {
+            return Err(SettingsError::Bootstrap {
+                message: "in-memory settings layer must be an object".to_string(),
+                setting: origin,
+            });
+        }
+
+        let mut flattened = BTreeMap::new();
+        flatten_into(None, Config::try_from(value)?.collect()?, &mut flattened);
+        let mut table = Map::new();
+        for (key, value) in flattened {
+            insert_nested(&mut table, &origin, &key, value.kind);
+        }
+        Ok(Self::new(layer, origin, TableSource(table)))
+    }
+
+    pub fn with_read_at(self, read_at: SystemTime) -> Self {
+        Self { read_at: Some(read_at), ..self }
+    }
+
+    /// Marks the layer as optional, so the load may go on without it; e.g., an environment
+    /// configuration file or `conf.d` snippet. See `policy::LoadPolicy`.
+    pub fn optional(self) -> Self {
+        Self { optional: true, ..self }
+    }
+
+    /// Replaces the layer's configuration source, keeping its description.
+    pub(crate) fn with_source<S>(self, source: S) -> Self
+    where
+        S: Source + Send + Sync + 'static,
+    {
+        Self { source: Box::new(source), ..self }
+    }
+
+    pub(crate) fn restricted<S>(
+        layer: Layer, origin: impl Into<String>, restrictions: &Arc<Vec<KeyRestriction>>, source: S,
+    ) -> Self
+    where
+        S: Source + Clone + Send + Sync + 'static,
+    {
+        let unrestricted = Unrestricted {
+            restrictions: restrictions.clone(),
+            source: Box::new(source.clone()),
+        };
+        Self {
+            restricted: Some(unrestricted),
+            ..Self::new(
+                layer,
+                origin,
+                RestrictedSource::new(layer, restrictions.clone(), source),
+            )
+        }
+    }
+
+    /// Restricts the layer's settings to the keys the restrictions permit from it.
+    pub(crate) fn restrict(self, restrictions: &Arc<Vec<KeyRestriction>>) -> Self {
+        let (layer, origin, read_at, optional) = (self.layer, self.origin.clone(), self.read_at, self.optional);
+        let unrestricted = Unrestricted {
+            restrictions: restrictions.clone(),
+            source: self.source.clone(),
+        };
+        let restricted = Self::new(layer, origin, RestrictedSource::new(layer, restrictions.clone(), self));
+        Self {
+            read_at,
+            optional,
+            restricted: Some(unrestricted),
+            ..restricted
+        }
+    }
+
+    pub const fn layer(&self) -> Layer {
+        self.layer
+    }
+
+    pub const fn origin(&self) -> &str {
+        self.origin.as_str()
+    }
+
+    /// When the layer's settings were read, as reported by the loading options' clock.
+    pub const fn read_at(&self) -> Option<SystemTime> {
+        self.read_at
+    }
+
+    pub const fn is_optional(&self) -> bool {
+        self.optional
+    }
+
+    /// The settings supplied to this layer that its key restrictions do not permit, which are
+    /// dropped from the layer.
+    pub fn restriction_violations(&self) -> Result<Vec<RestrictionViolation>, ConfigError> {
+        let Some(ref unrestricted) = self.restricted else {
+            return Ok(Vec::default());
+        };
+
+        let mut flattened = BTreeMap::new();
+        flatten_into(None, unrestricted.source.collect()?, &mut flattened);
+        let violations = flattened
+            .into_keys()
+            .filter_map(|key| {
+                find_violated(&unrestricted.restrictions, &key, self.layer).map(|restriction| RestrictionViolation {
+                    key,
+                    layer: self.layer,
+                    origin: self.origin.clone(),
+                    pattern: restriction.pattern().to_string(),
+                })
+            })
+            .collect();
+        Ok(violations)
+    }
+
+    /// Collects the settings supplied by this layer keyed by their dotted paths, with nested tables
+    /// flattened down to their leaf values.
+    pub fn collect_flattened(&self) -> Result<BTreeMap<String, Value>, ConfigError> {
+        let mut flattened = BTreeMap::new();
+        flatten_into(None, self.source.collect()?, &mut flattened);
+        Ok(flattened)
+    }
+}
+
+impl Source for LayerSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        self.source.collect()
+    }
+}
+
+pub(crate) fn flatten_into(prefix: Option<&str>, table: Map<String, Value>, flattened: &mut BTreeMap<String, Value>) {
+    for (key, value) in table {
+        let path = prefix.map_or_else(|| key.clone(), |p| format!("{p}.{key}"));
+        match value.kind {
+            ValueKind::Table(nested) => flatten_into(Some(path.as_str()), nested, flattened),
+            _ => {
+                flattened.insert(path, value);
+            },
+        }
+    }
+}
+
+/// Inserts the value at the dotted key, creating (or replacing non-table values with) the nested
+/// tables along its path. Created values record the origin.
+pub(crate) fn insert_nested(table: &mut Map<String, Value>, origin: &String, key: &str, value: ValueKind) {
+    match key.split_once('.') {
+        None => {
+            table.insert(key.to_string(), Value::new(Some(origin), value));
+        },
+        Some((head, rest)) => {
+            let nested = table
+                .entry(head.to_string())
+                .or_insert_with(|| Value::new(Some(origin), Map::<String, Value>::new()));
+            if !matches!(nested.kind, ValueKind::Table(_)) {
+                *nested = Value::new(Some(origin), Map::<String, Value>::new());
+            }
+            if let ValueKind::Table(ref mut nested) = nested.kind {
+                insert_nested(nested, origin, rest, value);
+            }
+        },
+    }
+}
+
+/// Restricts settings keys matching a pattern to only be accepted from the given layers.
+///
+/// For example, `secrets.*` may be limited to the secrets file or environment variables. Values a
+/// disallowed layer supplies for matching keys are dropped during the load and reported as
+/// warnings.
+///
+/// Patterns are dotted key paths where a `*` segment matches any single key segment. A pattern
+/// matching a key also matches every key nested beneath it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyRestriction {
+    pattern: String,
+    allowed: Vec<Layer>,
+}
+
+impl KeyRestriction {
+    pub fn new(pattern: impl Into<String>, allowed: impl IntoIterator<Item = Layer>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            allowed: allowed.into_iter().collect(),
+        }
+    }
+
+    pub const fn pattern(&self) -> &str {
+        self.pattern.as_str()
+    }
+
+    pub const fn allowed(&self) -> &[Layer] {
+        self.allowed.as_slice()
+    }
+
+    pub fn matches(&self, key: &str) -> bool {
+        key_pattern_matches(&self.pattern, key)
+    }
+
+    pub fn permits(&self, key: &str, layer: Layer) -> bool {
+        !self.matches(key) || self.allowed.contains(&layer)
+    }
+}
+
+/// A setting supplied by a layer that a `KeyRestriction` does not permit from it, so it is dropped
+/// from the load.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestrictionViolation {
+    pub key: String,
+    pub layer: Layer,
+    /// The origin of the layer supplying the setting; e.g., its file path.
+    pub origin: String,
+    /// The pattern of the restriction not permitting the setting from the layer.
+    pub pattern: String,
+}
+
+impl fmt::Display for RestrictionViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "setting {} from {} dropped: {} may not be supplied by the {} layer",
+            self.key, self.origin, self.pattern, self.layer
+        )
+    }
+}
+
+fn find_violated<'r>(restrictions: &'r [KeyRestriction], key: &str, layer: Layer) -> Option<&'r KeyRestriction> {
+    restrictions.iter().find(|r| !r.permits(key, layer))
+}
+
+/// Settings held in memory, such as a layer's settings after they are transformed.
+#[derive(Debug, Clone)]
+pub(crate) struct TableSource(pub(crate) Map<String, Value>);
+
+impl Source for TableSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        Ok(self.0.clone())
+    }
+}
+
+/// Whether a dotted key pattern, where a `*` segment matches any single key segment, matches the
+/// key or a key it is nested beneath.
+pub(crate) fn key_pattern_matches(pattern: &str, key: &str) -> bool {
+    let mut key_segments = key.split('.');
+    for pattern_segment in pattern.split('.') {
+        match key_segments.next() {
+            Some(key_segment) if pattern_segment == "*" || pattern_segment == key_segment => continue,
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Wraps a configuration source for a layer, dropping values for keys the restrictions do not
+/// permit from that layer.
+#[derive(Debug, Clone)]
+pub(crate) struct RestrictedSource<S> {
+    layer: Layer,
+    restrictions: Arc<Vec<KeyRestriction>>,
+    inner: S,
+}
+
+impl<S> RestrictedSource<S> {
+    pub(crate) const fn new(layer: Layer, restrictions: Arc<Vec<KeyRestriction>>, inner: S) -> Self {
+        Self { layer, restrictions, inner }
+    }
+
+    fn retain_permitted(&self, prefix: Option<&str>, table: &mut Map<String, Value>) {
+        table.retain(|key, value| {
+            let path = prefix.map_or_else(|| key.clone(), |p| format!("{p}.{key}"));
+            if let ValueKind::Table(ref mut nested) = value.kind {
+                self.retain_permitted(Some(path.as_str()), nested);
+                return true;
+            }
+
+            let violation = find_violated(&self.restrictions, &path, self.layer);
+            if let Some(restriction) = violation {
+                tracing::warn!(
+                    key=%path, layer=%self.layer, pattern=%restriction.pattern(), allowed=?restriction.allowed(),
+                    "dropping setting from layer not permitted to supply it"
+                );
+            }
+            violation.is_none()
+        });
+    }
+}
+
+impl<S> Source for RestrictedSource<S>
+where
+    S: Source + Clone + Send + Sync + 'static,
+{
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut table = self.inner.collect()?;
+        if !self.restrictions.is_empty() {
+            self.retain_permitted(None, &mut table);
+        }
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_key_restriction_matches() {
+        let secrets = KeyRestriction::new("secrets.*", [Layer::Secrets, Layer::EnvironmentVariables]);
+        assert!(secrets.matches("secrets.db"));
+        assert!(secrets.matches("secrets.db.password"));
+        assert!(!secrets.matches("secrets"));
+        assert!(!secrets.matches("database.password"));
+        assert!(secrets.permits("secrets.db", Layer::Secrets));
+        assert!(!secrets.permits("secrets.db", Layer::Config));
+        assert!(secrets.permits("database.password", Layer::Config));
+
+        let passwords = KeyRestriction::new("*.password", [Layer::Secrets]);
+        assert!(passwords.matches("database.password"));
+        assert!(!passwords.matches("database.username"));
+    }
+
+    #[test]
+    fn test_restricted_source_drops_disallowed_keys() {
+        let restrictions = Arc::new(vec![KeyRestriction::new("*.password", [Layer::Secrets])]);
+        let file = File::from_str("database: { username: billy, password: oops }", FileFormat::Yaml);
+
+        let config_layer = RestrictedSource::new(Layer::Config, restrictions.clone(), file.clone());
+        let actual = assert_ok!(config_layer.collect());
+        let database = assert_some!(actual.get("database")).clone();
+        let database = assert_ok!(database.into_table());
+        assert_eq!(database.len(), 1);
+        assert_none!(database.get("password"));
+
+        let secrets_layer = RestrictedSource::new(Layer::Secrets, restrictions.clone(), file.clone());
+        let actual = assert_ok!(secrets_layer.collect());
+        let database = assert_ok!(assert_some!(actual.get("database")).clone().into_table());
+        assert_eq!(database.len(), 2);
+
+        let layer = LayerSource::restricted(Layer::Config, "application.yaml", &restrictions, file.clone());
+        let violations = assert_ok!(layer.restriction_violations());
+        assert_eq!(
+            violations,
+            vec![RestrictionViolation {
+                key: "database.password".to_string(),
+                layer: Layer::Config,
+                origin: "application.yaml".to_string(),
+                pattern: "*.password".to_string(),
+            }]
+        );
+        let layer = LayerSource::new(Layer::Secrets, "secrets.yaml", file).restrict(&restrictions);
+        assert!(assert_ok!(layer.restriction_violations()).is_empty());
+    }
+
+    #[test]
+    fn test_in_memory_layers() {
+        let contents = LayerSource::from_contents(
+            Layer::Config,
+            "embedded defaults",
+            "database: { port: 5432 }",
+            FileFormat::Yaml,
+        );
+        assert_eq!(contents.origin(), "embedded defaults");
+        let actual = assert_ok!(contents.collect_flattened());
+        assert_eq!(assert_some!(actual.get("database.port")).to_string(), "5432");
+
+        let value = serde_json::json!({ "database": { "host": "localhost", "tags": ["a", "b"] } });
+        let layer = assert_ok!(LayerSource::from_value(Layer::Config, "test settings", &value));
+        let actual = assert_ok!(layer.collect_flattened());
+        let host = assert_some!(actual.get("database.host"));
+        assert_eq!(host.to_string(), "localhost");
+        assert_eq!(host.origin(), Some("test settings"));
+        assert_ok!(assert_some!(actual.get("database.tags")).clone().into_array());
+
+        assert_err!(LayerSource::from_value(Layer::Config, "scalar", &serde_json::json!(3)));
+    }
+}
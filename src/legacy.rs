@@ -0,0 +1,105 @@
+use std::path::{Path, PathBuf};
+
+use path_absolutize::*;
+
+use crate::layer::LayerSource;
+use crate::{Layer, SettingsError};
+
+/// A deprecated directory settings files used to be kept in, along with where they should now be
+/// moved.
+///
+/// Legacy locations are searched after the implicit search paths, so existing deployments keep
+/// loading, but each settings file found in one is reported with a warning naming its destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacyLocation {
+    dir: PathBuf,
+    moved_to: PathBuf,
+}
+
+impl LegacyLocation {
+    pub fn new(dir: impl Into<PathBuf>, moved_to: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), moved_to: moved_to.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        self.dir.as_path()
+    }
+
+    pub fn moved_to(&self) -> &Path {
+        self.moved_to.as_path()
+    }
+
+    /// The path the settings file should be moved to, if it is held in this legacy location.
+    pub fn migration_target(&self, file: &Path) -> Result<Option<PathBuf>, SettingsError> {
+        let dir = self.dir.absolutize()?;
+        let file = file.absolutize()?;
+        Ok(file.strip_prefix(&dir).ok().map(|relative| self.moved_to.join(relative)))
+    }
+}
+
+/// A settings file found in a legacy location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacyFile {
+    pub layer: Layer,
+    pub file: PathBuf,
+    pub moved_to: PathBuf,
+}
+
+/// Finds the layers read from files held in legacy locations.
+pub fn find_legacy_files(
+    layers: &[LayerSource], locations: &[LegacyLocation],
+) -> Result<Vec<LegacyFile>, SettingsError> {
+    let mut legacy_files = Vec::new();
+    for layer in layers.iter().filter(|l| l.layer() != Layer::EnvironmentVariables) {
+        let file = Path::new(layer.origin());
+        for location in locations {
+            if let Some(moved_to) = location.migration_target(file)? {
+                legacy_files.push(LegacyFile {
+                    layer: layer.layer(),
+                    file: file.to_path_buf(),
+                    moved_to,
+                });
+                break;
+            }
+        }
+    }
+    Ok(legacy_files)
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_find_legacy_files() {
+        let locations = vec![LegacyLocation::new("config", "resources")];
+        let legacy = assert_ok!(Path::new("config/application.yaml").absolutize()).into_owned();
+        let current = assert_ok!(Path::new("resources/local.yaml").absolutize()).into_owned();
+        let layers = vec![
+            LayerSource::new(
+                Layer::Config,
+                legacy.display().to_string(),
+                File::from_str("", FileFormat::Yaml),
+            ),
+            LayerSource::new(
+                Layer::EnvironmentConfig,
+                current.display().to_string(),
+                File::from_str("", FileFormat::Yaml),
+            ),
+        ];
+
+        let actual = assert_ok!(find_legacy_files(&layers, &locations));
+        assert_eq!(
+            actual,
+            vec![LegacyFile {
+                layer: Layer::Config,
+                file: legacy,
+                moved_to: PathBuf::from("resources/application.yaml"),
+            }]
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use config::{Config, Value, ValueKind};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_path_to_error::Segment;
+
+use crate::SettingsError;
+
+/// A setting that failed to deserialize during a lenient load and was replaced by its default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    /// Path of the failing setting; empty for the settings root.
+    pub key: String,
+    pub message: String,
+}
+
+/// Deserializes what it can of the configuration into settings, substituting defaults for fields
+/// that fail.
+///
+/// The corresponding part of `T::default()` stands in for each failing field, and the per-field
+/// errors are returned alongside the partially-populated settings.
+///
+/// Fields missing from a table are filled in from the default, while values that fail to
+/// deserialize are replaced by their default (or dropped when the default has no such value). An
+/// error is returned only if a failure cannot be repaired this way.
+pub fn deserialize_lenient<T>(config: Config) -> Result<(T, Vec<FieldError>), SettingsError>
+where
+    T: DeserializeOwned + Default + Serialize,
+{
+    let defaults: Value = Config::try_from(&T::default())?.try_deserialize()?;
+    let mut value: Value = config.try_deserialize()?;
+    let mut errors: Vec<FieldError> = Vec::new();
+
+    loop {
+        let err = match serde_path_to_error::deserialize::<_, T>(value.clone()) {
+            Ok(settings) => return Ok((settings, errors)),
+            Err(err) => err,
+        };
+
+        let segments: Vec<Segment> = err.path().iter().cloned().collect();
+        let key = if segments.is_empty() {
+            String::default()
+        } else {
+            err.path().to_string()
+        };
+        let repeated = errors.iter().any(|e| e.key == key);
+        if repeated || !repair(&mut value, &segments, lookup(&defaults, &segments)) {
+            return Err(SettingsError::Configuration(err.into_inner()));
+        }
+
+        tracing::warn!(%key, error=%err.inner(), "substituting default for setting that failed to load");
+        errors.push(FieldError { key, message: err.inner().to_string() });
+    }
+}
+
+pub(crate) fn lookup<'v>(root: &'v Value, segments: &[Segment]) -> Option<&'v Value> {
+    segments
+        .iter()
+        .try_fold(root, |value, segment| match (&value.kind, segment) {
+            (ValueKind::Table(table), Segment::Map { key }) => table.get(key),
+            (ValueKind::Array(array), Segment::Seq { index }) => array.get(*index),
+            _ => None,
+        })
+}
+
+fn lookup_mut<'v>(root: &'v mut Value, segments: &[Segment]) -> Option<&'v mut Value> {
+    segments
+        .iter()
+        .try_fold(root, |value, segment| match (&mut value.kind, segment) {
+            (ValueKind::Table(table), Segment::Map { key }) => table.get_mut(key),
+            (ValueKind::Array(array), Segment::Seq { index }) => array.get_mut(*index),
+            _ => None,
+        })
+}
+
+/// Repairs the value at the path using its default, returning whether anything changed.
+fn repair(root: &mut Value, segments: &[Segment], default: Option<&Value>) -> bool {
+    let Some(target) = lookup_mut(root, segments) else {
+        return false;
+    };
+
+    match (&mut target.kind, default.map(|d| &d.kind)) {
+        (ValueKind::Table(table), Some(ValueKind::Table(default_table))) => {
+            let missing: Vec<_> = default_table
+                .iter()
+                .filter(|(key, _)| !table.contains_key(*key))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+
+            if !missing.is_empty() {
+                table.extend(missing);
+                return true;
+            }
+        },
+        _ if segments.is_empty() => return false,
+        _ => {},
+    }
+
+    match default {
+        Some(default) if default.kind != target.kind => {
+            *target = default.clone();
+            true
+        },
+        Some(_) => false,
+        None => remove(root, segments),
+    }
+}
+
+fn remove(root: &mut Value, segments: &[Segment]) -> bool {
+    let Some((last, parent)) = segments.split_last() else {
+        return false;
+    };
+
+    match (lookup_mut(root, parent).map(|p| &mut p.kind), last) {
+        (Some(ValueKind::Table(table)), Segment::Map { key }) => table.remove(key).is_some(),
+        (Some(ValueKind::Array(array)), Segment::Seq { index }) if *index < array.len() => {
+            array.remove(*index);
+            true
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::FileFormat;
+    use pretty_assertions::assert_eq;
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct TestSettings {
+        name: String,
+        port: u16,
+        http: TestHttp,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct TestHttp {
+        host: String,
+        workers: u8,
+    }
+
+    impl Default for TestHttp {
+        fn default() -> Self {
+            Self { host: "127.0.0.1".to_string(), workers: 4 }
+        }
+    }
+
+    fn config(yaml: &str) -> Config {
+        assert_ok!(Config::builder()
+            .add_source(config::File::from_str(yaml, FileFormat::Yaml))
+            .build())
+    }
+
+    #[test]
+    fn test_lenient_substitutes_defaults() {
+        let (actual, errors) = assert_ok!(deserialize_lenient::<TestSettings>(config(
+            "name: app\nport: not-a-port\nhttp: { host: example.com, workers: 1000 }"
+        )));
+
+        assert_eq!(
+            actual,
+            TestSettings {
+                name: "app".to_string(),
+                port: 0,
+                http: TestHttp { host: "example.com".to_string(), workers: 4 },
+            }
+        );
+        let mut keys: Vec<_> = errors.iter().map(|e| e.key.as_str()).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, vec!["http.workers", "port"]);
+    }
+
+    #[test]
+    fn test_lenient_fills_missing_fields() {
+        let (actual, errors) = assert_ok!(deserialize_lenient::<TestSettings>(config(
+            "http: { host: example.com }"
+        )));
+        assert_eq!(
+            actual,
+            TestSettings {
+                name: String::default(),
+                port: 0,
+                http: TestHttp { host: "example.com".to_string(), workers: 4 },
+            }
+        );
+        assert_eq!(errors.len(), 2);
+    }
+}
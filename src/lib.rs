@@ -7,22 +7,92 @@ clippy::nursery,
 future_incompatible,
 rust_2018_idioms
 )]
+#![allow(clippy::multiple_crate_versions)]
 
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
+pub use cache::LayerCache;
+pub use clock::Clock;
+pub use composite::CompositeOptions;
+pub use conf_dir::{ConfigDir, SortOrder};
 use config::builder::DefaultState;
 use config::ConfigBuilder;
+pub use decrypt::ValueDecryptor;
+pub use env_alias::EnvVarAlias;
+pub use env_filter::EnvVarFilter;
 pub use environment::Environment;
 pub use error::SettingsError;
+pub use fs::ConfigFs;
+pub use handle::Settings;
+pub use instance::InstanceIdentity;
+pub use layer::{KeyRestriction, Layer, LayerSource, PrecedenceProfile};
+pub use legacy::LegacyLocation;
+pub use permissions::SecretsPermissions;
+pub use policy::LoadPolicy;
+pub use quick::QuickSettings;
+pub use secrets::SecretsProvider;
 
 pub use crate::settings_loader::SettingsLoader;
 
+#[cfg(feature = "attestation")]
+pub mod attestation;
+pub mod audit;
+pub mod cache;
+pub mod clock;
+pub mod coerce;
 pub mod common;
+pub mod composite;
+pub mod conf_dir;
+pub mod conflict;
+pub mod conformance;
+pub mod decrypt;
+pub mod deprecation;
+pub mod describe;
+pub mod diff;
+pub mod env_alias;
+pub mod env_filter;
 pub mod environment;
 pub mod error;
+pub mod export;
+pub mod fs;
+pub mod global;
+pub mod handle;
+#[cfg(feature = "http")]
+pub mod http_source;
+pub mod include;
+pub mod inline_env;
+pub mod instance;
 mod internals;
+pub mod interpolate;
+pub mod layer;
+pub mod legacy;
+pub mod lenient;
+pub mod lint;
+pub mod log_filter;
+pub mod merge;
+pub mod migrations;
+pub mod overlay;
+pub mod overrides;
+pub mod permissions;
+pub mod policy;
+pub mod preset;
+mod provenance;
+pub mod quick;
+pub mod redact;
+pub mod report;
+pub mod required;
+pub mod runtime;
+pub mod scope;
+pub mod secrets;
 pub mod settings_loader;
+pub mod strict;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 mod tracing;
+#[cfg(feature = "watch")]
+pub mod watch;
 
 const APP_ENVIRONMENT: &str = "APP_ENVIRONMENT";
 
@@ -35,6 +105,224 @@ pub trait LoadingOptions: Sized {
 
     fn implicit_search_paths(&self) -> Vec<PathBuf>;
 
+    /// Short aliases environment variables may use to target a settings section directly; e.g.,
+    /// `DB -> database` maps `DB__HOST` to `database.host`. None by default.
+    fn env_var_aliases(&self) -> Vec<EnvVarAlias> {
+        Vec::default()
+    }
+
+    /// Settings preset files applied above the configuration files and below the environment
+    /// configuration files, in order; see `preset::Preset`. None by default.
+    fn presets(&self) -> Vec<PathBuf> {
+        Vec::default()
+    }
+
+    /// `conf.d` style directories whose settings files are each loaded as a configuration layer,
+    /// above the presets and below the environment configuration files; see
+    /// `conf_dir::ConfigDir`. None by default.
+    fn config_dirs(&self) -> Vec<ConfigDir> {
+        Vec::default()
+    }
+
+    /// Identifies the running instance, so overlays of the application configuration file named
+    /// for it, e.g., `application@web-01.yaml`, are loaded above the environment configuration
+    /// files; see `instance::InstanceOverlay`. None by default; `InstanceIdentity::detect()`
+    /// provides the hostname.
+    fn instance_identity(&self) -> Option<InstanceIdentity> {
+        None
+    }
+
+    /// Deprecated directories still searched for settings files, after the implicit search paths,
+    /// with a warning that guides moving their files.
+    fn legacy_locations(&self) -> Vec<LegacyLocation> {
+        Vec::default()
+    }
+
+    /// Patterns of the settings written with inline per-environment values; e.g.,
+    /// `port: { default: 8080, production: 80 }`.
+    ///
+    /// Patterns are dotted key paths where a `*` segment matches any single key segment. Matching
+    /// settings resolve to the entry for the active environment, falling back to its `default`
+    /// entry. None by default.
+    fn per_environment_keys(&self) -> Vec<String> {
+        Vec::default()
+    }
+
+    /// The Kubernetes `ConfigMap` and `Secret` volumes loaded as layers above the settings files
+    /// and secrets file, respectively; none by default. `KubernetesScope::detect()` provides the
+    /// conventional mount points when running in-cluster.
+    #[cfg(feature = "kubernetes")]
+    fn kubernetes_scope(&self) -> Option<common::kubernetes::KubernetesScope> {
+        None
+    }
+
+    /// Audit log recording the changes of each reload of watched settings files; none by default.
+    fn audit_log(&self) -> Option<audit::AuditLog> {
+        None
+    }
+
+    /// Settings fetched from URLs, merged in order above the application configuration file; none
+    /// by default. Each source is fetched once per load.
+    #[cfg(feature = "http")]
+    fn http_sources(&self) -> Vec<http_source::HttpSource> {
+        Vec::default()
+    }
+
+    /// The precedence of the layers relative to each other; environment variables over the secrets
+    /// file over the configuration files by default.
+    fn precedence_profile(&self) -> PrecedenceProfile {
+        PrecedenceProfile::default()
+    }
+
+    /// Whether `${...}` references in the merged settings' string values are substituted before
+    /// deserialization; see `interpolate::interpolate()`. Disabled by default.
+    fn interpolate_values(&self) -> bool {
+        false
+    }
+
+    /// The types of settings whose string values, such as those from environment variables, are
+    /// converted before deserialization; see `coerce::Coercion`. None by default, leaving the
+    /// conversion to the settings type's deserialization.
+    fn value_coercions(&self) -> Vec<coerce::Coercion> {
+        Vec::default()
+    }
+
+    /// Decryptors for setting values encrypted at rest, written as `enc:<scheme>:<ciphertext>`;
+    /// see `decrypt::ValueDecryptor`. None by default, in which case encrypted values are rejected.
+    fn value_decryptors(&self) -> Vec<Arc<dyn ValueDecryptor>> {
+        Vec::default()
+    }
+
+    /// How the load treats optional layers, such as environment configuration files and `conf.d`
+    /// snippets, that fail to parse; see `policy::LoadPolicy`. Any invalid layer fails the load by
+    /// default.
+    fn load_policy(&self) -> LoadPolicy {
+        LoadPolicy::default()
+    }
+
+    /// Whether the load checks that the secrets file is not readable by its group or anyone else
+    /// on Unix, warning or failing if it is; see `permissions::SecretsPermissions`. Not checked by
+    /// default.
+    fn secrets_permissions(&self) -> SecretsPermissions {
+        SecretsPermissions::default()
+    }
+
+    /// Deprecated settings, warned about by the load, and in the load report, whenever a layer
+    /// supplies them; see `deprecation::DeprecatedKey`. None by default.
+    fn deprecated_keys(&self) -> Vec<deprecation::DeprecatedKey> {
+        Vec::default()
+    }
+
+    /// How the load treats deprecated settings that layers still supply; see
+    /// `deprecation::DeprecationPolicy`. Deprecated settings are only warned about by default.
+    fn deprecation_policy(&self) -> deprecation::DeprecationPolicy {
+        deprecation::DeprecationPolicy::default()
+    }
+
+    /// The running application's version, e.g., `env!("CARGO_PKG_VERSION")`, compared with the
+    /// removal versions of deprecated settings under `DeprecationPolicy::RejectRemoved`; none by
+    /// default.
+    fn application_version(&self) -> Option<String> {
+        None
+    }
+
+    /// Settings that must be supplied, in every environment or only in certain environments; see
+    /// `required::RequiredKey`. Loading fails listing the missing settings. None by default.
+    fn required_keys(&self) -> Vec<required::RequiredKey> {
+        Vec::default()
+    }
+
+    /// Whether loading rejects settings keys the settings type does not define, reporting each
+    /// with the layer supplying it, rather than silently ignoring them. Disabled by default.
+    fn strict(&self) -> bool {
+        false
+    }
+
+    /// Settings layers held in memory, e.g., via `LayerSource::from_contents()`, such as embedded
+    /// defaults or settings supplied by tests; none by default. Each takes precedence just below
+    /// the files of its layer, so files can override embedded defaults.
+    fn in_memory_layers(&self) -> Vec<LayerSource> {
+        Vec::default()
+    }
+
+    /// Detected runtime resources exposed as the lowest precedence `runtime.system.*` settings;
+    /// none by default. `RuntimeContext::detect()` provides them for the running process.
+    fn runtime_context(&self) -> Option<runtime::RuntimeContext> {
+        None
+    }
+
+    /// Migrations upgrading settings files written for older schema versions as they are loaded;
+    /// none by default.
+    fn migrations(&self) -> migrations::Migrations {
+        migrations::Migrations::default()
+    }
+
+    /// The clock used to timestamp loaded configuration layers; the system clock by default.
+    fn clock(&self) -> Arc<dyn Clock> {
+        Arc::new(clock::SystemClock)
+    }
+
+    /// Cache of parsed settings files shared across loads, so unchanged files are not re-parsed;
+    /// none by default.
+    fn layer_cache(&self) -> Option<Arc<LayerCache>> {
+        None
+    }
+
+    /// The filesystem settings files are read from; the local disk by default.
+    fn config_fs(&self) -> Arc<dyn ConfigFs> {
+        Arc::new(fs::RealFs)
+    }
+
+    /// Restricts which layers may supply settings for keys matching a pattern.
+    fn key_restrictions(&self) -> Vec<KeyRestriction> {
+        Vec::default()
+    }
+
+    /// How arrays supplied by several layers are combined for keys matching a pattern. Without a
+    /// matching rule, a higher layer's array replaces those of lower layers.
+    fn array_merge_rules(&self) -> Vec<merge::ArrayMergeRule> {
+        Vec::default()
+    }
+
+    /// Restricts which environment variables the environment variables layer may consume; all of
+    /// them by default.
+    fn env_var_filter(&self) -> EnvVarFilter {
+        EnvVarFilter::allow_all()
+    }
+
+    /// Providers of secrets fetched from external stores, which are merged into the secrets layer
+    /// by `secrets::load_with_providers()`. None by default.
+    fn secrets_providers(&self) -> Vec<Arc<dyn SecretsProvider>> {
+        Vec::default()
+    }
+
+    /// How long secrets fetched from secrets providers are cached by a `secrets::LoadedConfig`
+    /// before they are refreshed; indefinitely by default.
+    fn secrets_ttl(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Patterns of the settings that require a restart to take effect when changed while watching
+    /// the settings files; none by default. Patterns follow `KeyRestriction`'s dotted form.
+    #[cfg(feature = "watch")]
+    fn restart_required_keys(&self) -> Vec<String> {
+        Vec::default()
+    }
+
+    /// Coordinates restarting the application when a watched change touches a restart-required
+    /// setting; none by default.
+    #[cfg(feature = "watch")]
+    fn restart_coordinator(&self) -> Option<Arc<dyn watch::RestartCoordinator>> {
+        None
+    }
+
+    /// Listeners notified of the settings changed by each reload of watched settings files; none
+    /// by default.
+    #[cfg(feature = "watch")]
+    fn change_listeners(&self) -> Vec<Arc<dyn watch::SettingsChangeListener>> {
+        Vec::default()
+    }
+
     fn load_overrides(&self, config: ConfigBuilder<DefaultState>) -> Result<ConfigBuilder<DefaultState>, Self::Error> {
         Ok(config)
     }
@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use config::{Config, Source, Value};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::deprecation::{find_deprecated, DeprecatedKey};
+use crate::layer::{flatten_into, LayerSource};
+use crate::permissions::insecure_permissions;
+use crate::provenance::{is_sensitive, value_origin};
+use crate::strict::{describe_unknown_keys, deserialize_tracking_unknown};
+use crate::{Layer, SettingsError};
+
+/// How much a lint matters, from least to most.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// The kinds of issue the linter looks for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LintRule {
+    /// A setting the settings type does not define, so it is ignored.
+    UnusedKey,
+    /// A setting explicitly given its default value.
+    RedundantOverride,
+    /// A sensitive setting, such as a password, held outside the secrets layer.
+    SecretOutsideSecretsFile,
+    /// A secrets file readable by its group or by anyone.
+    InsecureSecretsFile,
+    /// A setting declared deprecated by `LoadingOptions::deprecated_keys()`.
+    DeprecatedKey,
+}
+
+impl LintRule {
+    pub const fn severity(&self) -> Severity {
+        match self {
+            Self::RedundantOverride => Severity::Info,
+            Self::UnusedKey | Self::SecretOutsideSecretsFile | Self::DeprecatedKey => Severity::Warning,
+            Self::InsecureSecretsFile => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for LintRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::UnusedKey => "unused-key",
+            Self::RedundantOverride => "redundant-override",
+            Self::SecretOutsideSecretsFile => "secret-outside-secrets-file",
+            Self::InsecureSecretsFile => "insecure-secrets-file",
+            Self::DeprecatedKey => "deprecated-key",
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// A non-fatal issue found in the settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    pub rule: LintRule,
+    pub severity: Severity,
+    pub key: Option<String>,
+    /// The layer the issue was found in; e.g., its file path.
+    pub origin: Option<String>,
+    pub message: String,
+}
+
+impl Lint {
+    fn new(rule: LintRule, key: Option<String>, origin: Option<String>, message: impl Into<String>) -> Self {
+        Self {
+            rule,
+            severity: rule.severity(),
+            key,
+            origin,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}]", self.severity, self.rule)?;
+        if let Some(ref key) = self.key {
+            write!(f, " {key}")?;
+        }
+        if let Some(ref origin) = self.origin {
+            write!(f, " in {origin}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// The issues found in the settings by `SettingsLoader::lint()`, e.g., for a `config lint`
+/// command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintReport {
+    lints: Vec<Lint>,
+}
+
+impl LintReport {
+    /// Lints the configuration merged from the layers against the settings type, whose default
+    /// values identify redundant overrides.
+    pub fn of<T>(config: &Config, layers: &[LayerSource], deprecated: &[DeprecatedKey]) -> Result<Self, SettingsError>
+    where
+        T: DeserializeOwned + Serialize + Default,
+    {
+        let mut lints = Vec::default();
+        let mut layer_settings = Vec::with_capacity(layers.len());
+        for layer in layers {
+            layer_settings.push((layer.origin().to_string(), layer.collect_flattened()?));
+        }
+
+        let (settings, ignored): (T, _) = deserialize_tracking_unknown(config)?;
+        for unknown in describe_unknown_keys(config, layers, ignored) {
+            let message = unknown.suggestion.as_ref().map_or_else(
+                || "not defined by the settings type, so it is ignored".to_string(),
+                |suggestion| format!("not defined by the settings type; did you mean {suggestion}?"),
+            );
+            lints.push(Lint::new(
+                LintRule::UnusedKey,
+                Some(unknown.key),
+                unknown.origin,
+                message,
+            ));
+        }
+
+        let mut supplied = BTreeMap::new();
+        flatten_into(None, config.collect()?, &mut supplied);
+        let effective = flatten_settings(&settings)?;
+        let defaults = flatten_settings(&T::default())?;
+        for (key, value) in &supplied {
+            let is_default = effective
+                .get(key)
+                .zip(defaults.get(key))
+                .is_some_and(|(effective, default)| effective.to_string() == default.to_string());
+            if is_default {
+                let origin = value_origin(&layer_settings, key, value);
+                let message = "set to its default value, so the setting can be removed";
+                lints.push(Lint::new(
+                    LintRule::RedundantOverride,
+                    Some(key.clone()),
+                    origin,
+                    message,
+                ));
+            }
+        }
+
+        for (layer, (origin, settings)) in layers.iter().zip(&layer_settings) {
+            match layer.layer() {
+                Layer::Config | Layer::EnvironmentConfig => {
+                    for key in settings.keys().filter(|key| is_sensitive(key)) {
+                        let message = "sensitive setting held outside the secrets file";
+                        lints.push(Lint::new(
+                            LintRule::SecretOutsideSecretsFile,
+                            Some(key.clone()),
+                            Some(origin.clone()),
+                            message,
+                        ));
+                    }
+                },
+                Layer::Secrets => {
+                    if let Some(mode) = insecure_permissions(Path::new(origin))? {
+                        let message = format!("secrets file mode {mode:o} is readable beyond its owner");
+                        lints.push(Lint::new(
+                            LintRule::InsecureSecretsFile,
+                            None,
+                            Some(origin.clone()),
+                            message,
+                        ));
+                    }
+                },
+                Layer::EnvironmentVariables => {},
+            }
+        }
+
+        for warning in find_deprecated(layers, deprecated) {
+            let message = format!("is {}", warning.deprecation);
+            lints.push(Lint::new(
+                LintRule::DeprecatedKey,
+                Some(warning.key),
+                Some(warning.origin),
+                message,
+            ));
+        }
+
+        Ok(Self { lints })
+    }
+
+    pub fn lints(&self) -> &[Lint] {
+        &self.lints
+    }
+
+    pub const fn is_clean(&self) -> bool {
+        self.lints.is_empty()
+    }
+
+    /// The severity of the most severe lint, if any.
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.lints.iter().map(|l| l.severity).max()
+    }
+}
+
+impl fmt::Display for LintReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for lint in &self.lints {
+            writeln!(f, "{lint}")?;
+        }
+        Ok(())
+    }
+}
+
+fn flatten_settings<T: Serialize>(settings: &T) -> Result<BTreeMap<String, Value>, SettingsError> {
+    let mut flattened = BTreeMap::new();
+    flatten_into(None, Config::try_from(settings)?.collect()?, &mut flattened);
+    Ok(flattened)
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct LintSettings {
+        #[serde(default)]
+        port: u16,
+        #[serde(default)]
+        database: Database,
+    }
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Database {
+        #[serde(default)]
+        host: String,
+        #[serde(default)]
+        password: String,
+        #[serde(default)]
+        pool: u32,
+    }
+
+    #[test]
+    fn test_lint_report() {
+        let layers = vec![
+            LayerSource::new(
+                Layer::Config,
+                "application.yaml",
+                File::from_str(
+                    "port: 8080\ndatabase: { host: localhost, password: hunter2, pool: 0, pol: 4 }",
+                    FileFormat::Yaml,
+                ),
+            ),
+            LayerSource::new(
+                Layer::Secrets,
+                "secrets.yaml",
+                File::from_str("database: { password: s3cret }", FileFormat::Yaml),
+            ),
+        ];
+        let mut builder = Config::builder();
+        for layer in &layers {
+            builder = builder.add_source(layer.clone());
+        }
+        let config = assert_ok!(builder.build());
+        let deprecated = vec![DeprecatedKey::new("database.pool")
+            .since("0.14")
+            .replaced_by("database.pool_size")];
+
+        let report = assert_ok!(LintReport::of::<LintSettings>(&config, &layers, &deprecated));
+        let actual: Vec<_> = report.lints().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            actual,
+            vec![
+                "warning [unused-key] database.pol in application.yaml: not defined by the settings type; did you \
+                 mean database.pool?",
+                "info [redundant-override] database.pool in application.yaml: set to its default value, so the \
+                 setting can be removed",
+                "warning [secret-outside-secrets-file] database.password in application.yaml: sensitive setting held \
+                 outside the secrets file",
+                "warning [deprecated-key] database.pool in application.yaml: is deprecated since 0.14; use \
+                 database.pool_size instead",
+            ]
+        );
+        assert_eq!(report.max_severity(), Some(Severity::Warning));
+        assert!(!report.is_clean());
+    }
+}
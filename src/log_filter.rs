@@ -0,0 +1,166 @@
+use std::fmt;
+
+use config::{Config, ValueKind};
+use tracing_subscriber::{reload, EnvFilter};
+
+use crate::diff::SettingsDiff;
+use crate::layer::key_pattern_matches;
+use crate::SettingsError;
+
+/// Settings section holding the logging filter by default.
+pub const DEFAULT_LOGGING_KEY: &str = "logging";
+
+/// Binds a logging filter setting to a `tracing_subscriber` reloadable `EnvFilter`, so changes to
+/// the filter take effect without restarting the application.
+///
+/// The filter is read from the section at the key; e.g.,
+///
+/// ```yaml
+/// logging:
+///   level: info
+///   targets:
+///     my_app::db: debug
+/// ```
+///
+/// When watching settings files, register the reloader as a `SettingsChangeListener` via
+/// `LoadingOptions::change_listeners()`, and the filter is updated as the section changes.
+pub struct LogFilterReloader<S> {
+    key: String,
+    handle: reload::Handle<EnvFilter, S>,
+}
+
+impl<S> fmt::Debug for LogFilterReloader<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogFilterReloader").field("key", &self.key).finish()
+    }
+}
+
+impl<S> LogFilterReloader<S> {
+    pub fn new(handle: reload::Handle<EnvFilter, S>) -> Self {
+        Self { key: DEFAULT_LOGGING_KEY.to_string(), handle }
+    }
+
+    #[must_use]
+    pub fn with_key(self, key: impl Into<String>) -> Self {
+        Self { key: key.into(), ..self }
+    }
+
+    pub const fn key(&self) -> &str {
+        self.key.as_str()
+    }
+
+    /// Reloads the filter from the configuration's logging section, returning whether the section
+    /// was found.
+    pub fn reload_from(&self, config: &Config) -> Result<bool, SettingsError> {
+        let Some(directives) = filter_directives(config, &self.key)? else {
+            return Ok(false);
+        };
+
+        let filter = EnvFilter::try_new(&directives).map_err(|err| SettingsError::Bootstrap {
+            message: format!("invalid logging filter {directives:?}: {err}"),
+            setting: self.key.clone(),
+        })?;
+        self.handle.reload(filter).map_err(|err| SettingsError::Bootstrap {
+            message: format!("failed to reload logging filter: {err}"),
+            setting: self.key.clone(),
+        })?;
+        tracing::info!(%directives, "reloaded logging filter from settings");
+        Ok(true)
+    }
+
+    /// Reloads the filter if the diff touches the logging section.
+    pub fn reload_on_change(&self, diff: &SettingsDiff, config: &Config) -> Result<bool, SettingsError> {
+        if diff.changes().iter().any(|c| key_pattern_matches(&self.key, &c.key)) {
+            self.reload_from(config)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+#[cfg(feature = "watch")]
+impl<S: 'static> crate::watch::SettingsChangeListener for LogFilterReloader<S> {
+    fn settings_changed(&self, diff: &SettingsDiff, config: &Config) {
+        if let Err(err) = self.reload_on_change(diff, config) {
+            tracing::warn!(error=%err, "failed to reload logging filter from changed settings");
+        }
+    }
+}
+
+/// The `EnvFilter` directives held by the logging section at the key, if it is present.
+pub fn filter_directives(config: &Config, key: &str) -> Result<Option<String>, SettingsError> {
+    let level: Option<String> = config.get(&format!("{key}.level")).ok();
+    let targets = match config.get::<config::Value>(&format!("{key}.targets")) {
+        Ok(targets) => match targets.kind {
+            ValueKind::Table(targets) => targets,
+            _ => {
+                return Err(SettingsError::Bootstrap {
+                    message: "logging targets must be a table of target to level".to_string(),
+                    setting: format!("{key}.targets"),
+                })
+            },
+        },
+        Err(_) => config::Map::new(),
+    };
+
+    if level.is_none() && targets.is_empty() {
+        return Ok(None);
+    }
+
+    let mut targets: Vec<_> = targets
+        .into_iter()
+        .map(|(target, level)| format!("{target}={level}"))
+        .collect();
+    targets.sort();
+    let directives: Vec<_> = level.into_iter().chain(targets).collect();
+    Ok(Some(directives.join(",")))
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+    use tracing_subscriber::Registry;
+
+    use super::*;
+
+    fn config(yaml: &str) -> Config {
+        assert_ok!(Config::builder()
+            .add_source(File::from_str(yaml, FileFormat::Yaml))
+            .build())
+    }
+
+    #[test]
+    fn test_filter_directives() {
+        let actual = assert_ok!(filter_directives(
+            &config("logging: { level: warn, targets: { my_app::db: debug, hyper: error } }"),
+            "logging"
+        ));
+        assert_eq!(actual.as_deref(), Some("warn,hyper=error,my_app::db=debug"));
+        assert_none!(assert_ok!(filter_directives(&config("foo: bar"), "logging")));
+        assert_err!(filter_directives(&config("logging: { targets: debug }"), "logging"));
+    }
+
+    #[test]
+    fn test_reload_on_change() {
+        let (layer, handle) = reload::Layer::<_, Registry>::new(EnvFilter::new("info"));
+        let reloader = LogFilterReloader::new(handle.clone());
+        let current = || assert_ok!(handle.with_current(ToString::to_string));
+
+        let before = config("logging: { level: info }\nfoo: bar");
+        let unrelated = config("logging: { level: info }\nfoo: baz");
+        let diff = assert_ok!(SettingsDiff::between_configs(&before, &unrelated));
+        assert!(!assert_ok!(reloader.reload_on_change(&diff, &unrelated)));
+        assert_eq!(current(), "info");
+
+        let after = config("logging: { level: debug, targets: { hyper: warn } }\nfoo: baz");
+        let diff = assert_ok!(SettingsDiff::between_configs(&unrelated, &after));
+        assert!(assert_ok!(reloader.reload_on_change(&diff, &after)));
+        assert_eq!(current(), "hyper=warn,debug");
+
+        let invalid = config("logging: { level: 'not a [level' }");
+        assert_err!(reloader.reload_from(&invalid));
+        drop(layer);
+    }
+}
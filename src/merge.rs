@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+
+use config::{Source, Value, ValueKind};
+use serde::{Deserialize, Serialize};
+
+use crate::layer::{flatten_into, insert_nested, key_pattern_matches, LayerSource, TableSource};
+use crate::SettingsError;
+
+/// How an overlay value is merged onto a base value by `merge()`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergeStrategy {
+    /// Tables are merged key by key, recursively, while any other overlay value replaces the base
+    /// value. This is how configuration layers are merged by `SettingsLoader::load()`.
+    #[default]
+    Deep,
+
+    /// Each top-level overlay entry replaces the base entry wholesale, without merging nested
+    /// tables.
+    Shallow,
+}
+
+/// Merges the overlay onto the base value using the same semantics as the configuration layers.
+///
+/// Applications can then apply their own runtime overrides (e.g., a tenant patch held in a
+/// database) consistently with the files.
+pub fn merge(base: Value, overlay: Value, strategy: MergeStrategy) -> Value {
+    if !matches!((&base.kind, &overlay.kind), (ValueKind::Table(_), ValueKind::Table(_))) {
+        return overlay;
+    }
+
+    let origin = base.origin().map(ToString::to_string);
+    let (ValueKind::Table(mut base_table), ValueKind::Table(overlay_table)) = (base.kind, overlay.kind) else {
+        unreachable!("both values are tables");
+    };
+    for (key, overlay_value) in overlay_table {
+        let merged = match (strategy, base_table.remove(&key)) {
+            (MergeStrategy::Deep, Some(base_value)) => merge(base_value, overlay_value, strategy),
+            _ => overlay_value,
+        };
+        base_table.insert(key, merged);
+    }
+    Value::new(origin.as_ref(), ValueKind::Table(base_table))
+}
+
+/// How arrays supplied for a key by several configuration layers are combined.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArrayMergeStrategy {
+    /// The highest precedence layer's array replaces those of lower layers.
+    #[default]
+    Replace,
+
+    /// Arrays are concatenated, lowest precedence layer first; e.g., so an override file can add
+    /// endpoints.
+    Append,
+
+    /// Tables in the arrays (e.g., TOML arrays of tables) are matched by the named field and deep
+    /// merged, with unmatched elements appended.
+    MergeByKey(String),
+}
+
+/// Applies an `ArrayMergeStrategy` to the arrays at keys matching a pattern, declared by
+/// `LoadingOptions::array_merge_rules()`. Patterns follow `KeyRestriction`'s dotted form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArrayMergeRule {
+    pattern: String,
+    strategy: ArrayMergeStrategy,
+}
+
+impl ArrayMergeRule {
+    pub fn new(pattern: impl Into<String>, strategy: ArrayMergeStrategy) -> Self {
+        Self { pattern: pattern.into(), strategy }
+    }
+
+    pub const fn pattern(&self) -> &str {
+        self.pattern.as_str()
+    }
+
+    pub const fn strategy(&self) -> &ArrayMergeStrategy {
+        &self.strategy
+    }
+
+    pub fn matches(&self, key: &str) -> bool {
+        key_pattern_matches(&self.pattern, key)
+    }
+}
+
+/// Combines the arrays the layers supply for keys matching the rules.
+///
+/// Each layer's array then holds its combination with those of lower layers. The layers are
+/// expected in precedence order, lowest first, and layers without matching arrays are returned
+/// unchanged.
+pub fn merge_layer_arrays(
+    layers: Vec<LayerSource>, rules: &[ArrayMergeRule],
+) -> Result<Vec<LayerSource>, SettingsError> {
+    if rules.is_empty() {
+        return Ok(layers);
+    }
+
+    let mut combined: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+    let mut merged = Vec::with_capacity(layers.len());
+    for layer in layers {
+        let mut table = layer.collect()?;
+        let mut flattened = BTreeMap::new();
+        flatten_into(None, table.clone(), &mut flattened);
+
+        let mut changed = false;
+        for (key, value) in flattened {
+            let strategy = rules.iter().find(|r| r.matches(&key)).map(ArrayMergeRule::strategy);
+            let (Some(strategy), ValueKind::Array(elements)) = (strategy, value.kind) else {
+                combined.remove(&key);
+                continue;
+            };
+
+            let elements = match (strategy, combined.remove(&key)) {
+                (ArrayMergeStrategy::Replace, _) | (_, None) => elements,
+                (ArrayMergeStrategy::Append, Some(lower)) => lower.into_iter().chain(elements).collect(),
+                (ArrayMergeStrategy::MergeByKey(field), Some(lower)) => merge_by_key(lower, elements, field),
+            };
+            insert_nested(
+                &mut table,
+                &layer.origin().to_string(),
+                &key,
+                ValueKind::Array(elements.clone()),
+            );
+            combined.insert(key, elements);
+            changed = true;
+        }
+
+        merged.push(if changed { layer.with_source(TableSource(table)) } else { layer });
+    }
+    Ok(merged)
+}
+
+fn merge_by_key(lower: Vec<Value>, upper: Vec<Value>, field: &str) -> Vec<Value> {
+    let key_of = |value: &Value| match value.kind {
+        ValueKind::Table(ref table) => table.get(field).map(ToString::to_string),
+        _ => None,
+    };
+
+    let mut merged = lower;
+    for element in upper {
+        let position = key_of(&element).and_then(|k| merged.iter().position(|m| key_of(m).as_ref() == Some(&k)));
+        match position {
+            Some(i) => {
+                let base = merged.remove(i);
+                merged.insert(i, merge(base, element, MergeStrategy::Deep));
+            },
+            None => merged.push(element),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{Config, File, FileFormat};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::Layer;
+
+    const BASE: &str = "database: { host: localhost, port: 5432, options: { ssl: false, pool: 4 } }\nhosts: [a, b]";
+    const OVERLAY: &str = "database: { port: 6543, options: { ssl: true } }\nhosts: [c]\nfoo: bar";
+
+    fn yaml_value(yaml: &str) -> Value {
+        assert_ok!(assert_ok!(Config::builder()
+            .add_source(File::from_str(yaml, FileFormat::Yaml))
+            .build())
+        .try_deserialize())
+    }
+
+    fn json(value: Value) -> serde_json::Value {
+        assert_ok!(value.try_deserialize())
+    }
+
+    #[test]
+    fn test_deep_merge_matches_layers() {
+        let layered: Value = assert_ok!(assert_ok!(Config::builder()
+            .add_source(File::from_str(BASE, FileFormat::Yaml))
+            .add_source(File::from_str(OVERLAY, FileFormat::Yaml))
+            .build())
+        .try_deserialize());
+
+        let actual = merge(yaml_value(BASE), yaml_value(OVERLAY), MergeStrategy::Deep);
+        assert_eq!(json(actual.clone()), json(layered));
+        assert_eq!(
+            json(actual),
+            serde_json::json!({
+                "database": { "host": "localhost", "port": 6543, "options": { "ssl": true, "pool": 4 } },
+                "hosts": ["c"],
+                "foo": "bar",
+            })
+        );
+    }
+
+    #[test]
+    fn test_merge_layer_arrays() {
+        let base = "[[endpoints]]\nname = \"a\"\nurl = \"a.local\"\n\n[[endpoints]]\nname = \"b\"\nurl = \
+                    \"b.local\"\n\n[server]\nhosts = [\"h1\"]\nports = [80]";
+        let overlay = "[[endpoints]]\nname = \"b\"\nurl = \"b.internal\"\n\n[[endpoints]]\nname = \"c\"\nurl = \
+                       \"c.local\"\n\n[server]\nhosts = [\"h2\"]\nports = [8080]";
+        let layers = vec![
+            LayerSource::new(
+                Layer::Config,
+                "application.toml",
+                File::from_str(base, FileFormat::Toml),
+            ),
+            LayerSource::new(
+                Layer::EnvironmentConfig,
+                "local.toml",
+                File::from_str(overlay, FileFormat::Toml),
+            ),
+        ];
+        let rules = vec![
+            ArrayMergeRule::new("endpoints", ArrayMergeStrategy::MergeByKey("name".to_string())),
+            ArrayMergeRule::new("server.hosts", ArrayMergeStrategy::Append),
+        ];
+
+        let mut builder = Config::builder();
+        for layer in assert_ok!(merge_layer_arrays(layers, &rules)) {
+            builder = builder.add_source(layer);
+        }
+        let actual: serde_json::Value = assert_ok!(assert_ok!(builder.build()).try_deserialize());
+        assert_eq!(
+            actual,
+            serde_json::json!({
+                "endpoints": [
+                    { "name": "a", "url": "a.local" },
+                    { "name": "b", "url": "b.internal" },
+                    { "name": "c", "url": "c.local" },
+                ],
+                "server": { "hosts": ["h1", "h2"], "ports": [8080] },
+            })
+        );
+    }
+
+    #[test]
+    fn test_shallow_merge() {
+        let actual = merge(yaml_value(BASE), yaml_value(OVERLAY), MergeStrategy::Shallow);
+        assert_eq!(
+            json(actual),
+            serde_json::json!({
+                "database": { "port": 6543, "options": { "ssl": true } },
+                "hosts": ["c"],
+                "foo": "bar",
+            })
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use std::fmt;
+use std::sync::Arc;
+
+use config::{ConfigError, Map, Source, Value, ValueKind};
+
+/// Key recording the schema version a settings file is written for. Files without it are treated
+/// as version 0.
+pub const VERSION_KEY: &str = "settings_version";
+
+type Transform = Arc<dyn Fn(Value) -> Result<Value, String> + Send + Sync>;
+
+/// A step of a `ConfigMigration`.
+#[derive(Clone)]
+pub enum MigrationStep {
+    /// Moves a setting, or a whole section, to a new dotted key.
+    Rename { from: String, to: String },
+
+    /// Replaces a setting's value with the transformed value.
+    Transform { key: String, transform: Transform },
+}
+
+impl fmt::Debug for MigrationStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rename { from, to } => f.debug_struct("Rename").field("from", from).field("to", to).finish(),
+            Self::Transform { key, .. } => f.debug_struct("Transform").field("key", key).finish(),
+        }
+    }
+}
+
+/// Steps upgrading settings files to a schema version; e.g., after renaming a settings field.
+#[derive(Debug, Clone)]
+pub struct ConfigMigration {
+    version: u32,
+    description: String,
+    steps: Vec<MigrationStep>,
+}
+
+impl ConfigMigration {
+    pub fn new(version: u32, description: impl Into<String>) -> Self {
+        Self {
+            version,
+            description: description.into(),
+            steps: Vec::default(),
+        }
+    }
+
+    pub fn rename_key(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.steps.push(MigrationStep::Rename { from: from.into(), to: to.into() });
+        self
+    }
+
+    /// Moves a section; the same as renaming its key.
+    pub fn move_section(self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.rename_key(from, to)
+    }
+
+    pub fn transform<F>(mut self, key: impl Into<String>, transform: F) -> Self
+    where
+        F: Fn(Value) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        self.steps
+            .push(MigrationStep::Transform { key: key.into(), transform: Arc::new(transform) });
+        self
+    }
+
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub const fn description(&self) -> &str {
+        self.description.as_str()
+    }
+
+    fn apply(&self, table: &mut Map<String, Value>) -> Result<(), String> {
+        for step in &self.steps {
+            match step {
+                MigrationStep::Rename { from, to } => {
+                    if let Some(value) = take(table, from) {
+                        put(table, to, value);
+                    }
+                },
+                MigrationStep::Transform { key, transform } => {
+                    if let Some(value) = take(table, key) {
+                        put(table, key, transform(value)?);
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The registered migrations, applied in version order to each settings file written for an
+/// older schema version as it is loaded; see `LoadingOptions::migrations()`.
+#[derive(Debug, Default, Clone)]
+pub struct Migrations {
+    migrations: Vec<ConfigMigration>,
+}
+
+impl Migrations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(mut self, migration: ConfigMigration) -> Self {
+        self.migrations.push(migration);
+        self.migrations.sort_by_key(ConfigMigration::version);
+        self
+    }
+
+    /// Registers the other migrations alongside these, ordered by version.
+    pub fn merge(self, other: Self) -> Self {
+        other.migrations.into_iter().fold(self, Self::register)
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.migrations.is_empty()
+    }
+
+    /// The latest schema version; 0 if no migrations are registered.
+    pub fn current_version(&self) -> u32 {
+        self.migrations.last().map_or(0, ConfigMigration::version)
+    }
+
+    /// Applies the migrations pending for the settings' version, returning the versions applied.
+    /// The settings are then marked with the current version.
+    pub fn migrate(&self, table: &mut Map<String, Value>) -> Result<Vec<u32>, String> {
+        let version = match table.get(VERSION_KEY) {
+            Some(version) => version
+                .clone()
+                .into_uint()
+                .map_err(|err| format!("invalid {VERSION_KEY}: {err}"))?,
+            None => 0,
+        };
+
+        let mut applied = Vec::new();
+        for migration in self.migrations.iter().filter(|m| version < u64::from(m.version)) {
+            migration
+                .apply(table)
+                .map_err(|err| format!("migration to version {} failed: {err}", migration.version))?;
+            applied.push(migration.version);
+        }
+
+        if !applied.is_empty() {
+            table.insert(VERSION_KEY.to_string(), Value::from(u64::from(self.current_version())));
+        }
+        Ok(applied)
+    }
+}
+
+fn take(table: &mut Map<String, Value>, key: &str) -> Option<Value> {
+    match key.split_once('.') {
+        None => table.remove(key),
+        Some((head, rest)) => match table.get_mut(head)?.kind {
+            ValueKind::Table(ref mut nested) => take(nested, rest),
+            _ => None,
+        },
+    }
+}
+
+fn put(table: &mut Map<String, Value>, key: &str, value: Value) {
+    match key.split_once('.') {
+        None => {
+            table.insert(key.to_string(), value);
+        },
+        Some((head, rest)) => {
+            let nested = table
+                .entry(head.to_string())
+                .or_insert_with(|| Value::from(Map::<String, Value>::new()));
+            if !matches!(nested.kind, ValueKind::Table(_)) {
+                *nested = Value::from(Map::<String, Value>::new());
+            }
+            if let ValueKind::Table(ref mut nested) = nested.kind {
+                put(nested, rest, value);
+            }
+        },
+    }
+}
+
+/// Wraps a settings file's source, migrating its settings to the current schema version.
+#[derive(Debug, Clone)]
+pub(crate) struct MigratedSource<S> {
+    origin: String,
+    migrations: Migrations,
+    inner: S,
+}
+
+impl<S> MigratedSource<S> {
+    pub(crate) const fn new(origin: String, migrations: Migrations, inner: S) -> Self {
+        Self { origin, migrations, inner }
+    }
+}
+
+impl<S> Source for MigratedSource<S>
+where
+    S: Source + Clone + Send + Sync + 'static,
+{
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut table = self.inner.collect()?;
+        if self.migrations.is_empty() || table.is_empty() {
+            return Ok(table);
+        }
+
+        let applied = self
+            .migrations
+            .migrate(&mut table)
+            .map_err(|err| ConfigError::Message(format!("{}: {err}", self.origin)))?;
+        if !applied.is_empty() {
+            tracing::warn!(
+                origin=%self.origin, ?applied, version=%self.migrations.current_version(),
+                "migrated settings file written for an older schema version; update the file"
+            );
+        }
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::layer::LayerSource;
+    use crate::Layer;
+
+    fn migrations() -> Migrations {
+        Migrations::new()
+            .register(
+                ConfigMigration::new(2, "timeouts in seconds").transform("http.timeout_secs", |v| {
+                    let millis = v.into_int().map_err(|err| err.to_string())?;
+                    Ok(Value::from(millis / 1000))
+                }),
+            )
+            .register(
+                ConfigMigration::new(1, "rename db section")
+                    .move_section("db", "database")
+                    .rename_key("http.timeout", "http.timeout_secs"),
+            )
+    }
+
+    fn migrated(yaml: &'static str) -> Result<Vec<(String, String)>, ConfigError> {
+        let source = MigratedSource::new(
+            "application.yaml".to_string(),
+            migrations(),
+            File::from_str(yaml, FileFormat::Yaml),
+        );
+        let layer = LayerSource::new(Layer::Config, "application.yaml", source);
+        Ok(layer
+            .collect_flattened()?
+            .into_iter()
+            .map(|(key, value)| (key, value.to_string()))
+            .collect())
+    }
+
+    #[test]
+    fn test_migrate_settings() {
+        let pairs = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+        let expected = pairs(&[
+            ("database.host", "localhost"),
+            ("http.timeout_secs", "30"),
+            ("settings_version", "2"),
+        ]);
+
+        assert_eq!(
+            assert_ok!(migrated("db: { host: localhost }\nhttp: { timeout: 30000 }")),
+            expected
+        );
+        assert_eq!(
+            assert_ok!(migrated(
+                "settings_version: 1\ndatabase: { host: localhost }\nhttp: { timeout_secs: 30000 }"
+            )),
+            expected
+        );
+        assert_eq!(
+            assert_ok!(migrated(
+                "settings_version: 2\ndatabase: { host: localhost }\nhttp: { timeout_secs: 30 }"
+            )),
+            expected
+        );
+        assert_err!(migrated("http: { timeout: soon }"));
+        assert_eq!(migrations().current_version(), 2);
+    }
+}
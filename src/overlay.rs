@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use config::{Config, File, FileFormat, Map, Source, Value, ValueKind};
+use serde::de::DeserializeOwned;
+
+use crate::conflict::type_name;
+use crate::layer::{flatten_into, insert_nested, key_pattern_matches};
+use crate::SettingsError;
+
+/// Largest override document accepted by default, in bytes.
+pub const DEFAULT_MAX_DOCUMENT_LEN: usize = 16 * 1024;
+
+/// Origin recorded for the settings supplied by an override document.
+const OVERLAY_ORIGIN: &str = "overlay";
+
+/// Loaded base settings onto which small, untrusted override documents, e.g., from a request
+/// header or a tenant record, are overlaid per request.
+///
+/// Only keys matching the allow-list may be overridden, using `KeyRestriction`'s dotted pattern
+/// form, and an override must keep the type of the base value it replaces. The base is flattened
+/// once and shared, so each overlay holds only its own overrides rather than a full re-merge.
+#[derive(Debug, Clone)]
+pub struct SettingsOverlay {
+    base: Arc<BTreeMap<String, Value>>,
+    allowed: Vec<String>,
+    max_document_len: usize,
+}
+
+impl SettingsOverlay {
+    pub fn new<I, P>(base: &Config, allowed: I) -> Result<Self, SettingsError>
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<String>,
+    {
+        let mut flattened = BTreeMap::new();
+        flatten_into(None, base.collect()?, &mut flattened);
+        Ok(Self {
+            base: Arc::new(flattened),
+            allowed: allowed.into_iter().map(Into::into).collect(),
+            max_document_len: DEFAULT_MAX_DOCUMENT_LEN,
+        })
+    }
+
+    #[must_use]
+    pub const fn with_max_document_len(mut self, max_document_len: usize) -> Self {
+        self.max_document_len = max_document_len;
+        self
+    }
+
+    pub const fn allowed(&self) -> &[String] {
+        self.allowed.as_slice()
+    }
+
+    /// Validates the override document and overlays it onto the base settings.
+    pub fn overlay(&self, document: &str, format: FileFormat) -> Result<OverlayView, SettingsError> {
+        if self.max_document_len < document.len() {
+            return Err(SettingsError::OverlayRejected {
+                key: String::default(),
+                reason: format!(
+                    "document of {} bytes exceeds the {} byte limit",
+                    document.len(),
+                    self.max_document_len
+                ),
+            });
+        }
+
+        let mut overrides = BTreeMap::new();
+        flatten_into(None, File::from_str(document, format).collect()?, &mut overrides);
+        for (key, value) in &overrides {
+            self.validate(key, value)?;
+        }
+
+        Ok(OverlayView { base: Arc::clone(&self.base), overrides })
+    }
+
+    fn validate(&self, key: &str, value: &Value) -> Result<(), SettingsError> {
+        let reject = |reason: String| SettingsError::OverlayRejected { key: key.to_string(), reason };
+
+        if !self.allowed.iter().any(|pattern| key_pattern_matches(pattern, key)) {
+            return Err(reject("key is not overridable".to_string()));
+        }
+
+        if self
+            .base
+            .keys()
+            .any(|k| k.strip_prefix(key).is_some_and(|rest| rest.starts_with('.')))
+        {
+            return Err(reject("cannot replace a section of settings".to_string()));
+        }
+
+        let mut parents = key.match_indices('.').map(|(i, _)| &key[..i]);
+        if let Some(parent) = parents.find(|p| self.base.contains_key(*p)) {
+            return Err(reject(format!("cannot nest settings beneath {parent}")));
+        }
+
+        if let Some(base) = self.base.get(key) {
+            if let (Some(expected), Some(actual)) = (type_name(&base.kind), type_name(&value.kind)) {
+                if expected != actual {
+                    return Err(reject(format!("expected {expected} but found {actual}")));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The base settings with one override document's values laid over them, created by
+/// `SettingsOverlay::overlay()`.
+#[derive(Debug, Clone)]
+pub struct OverlayView {
+    base: Arc<BTreeMap<String, Value>>,
+    overrides: BTreeMap<String, Value>,
+}
+
+impl OverlayView {
+    /// The overridden settings, keyed by dotted path.
+    pub const fn overrides(&self) -> &BTreeMap<String, Value> {
+        &self.overrides
+    }
+
+    /// The setting, or section of settings, at the dotted key.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<T, SettingsError> {
+        if let Some(value) = self.overrides.get(key).or_else(|| self.base.get(key)) {
+            return Ok(value.clone().try_deserialize()?);
+        }
+
+        let prefix = format!("{key}.");
+        let section = self.section(|k| k.strip_prefix(prefix.as_str()));
+        if section.is_empty() {
+            return Err(config::ConfigError::NotFound(key.to_string()).into());
+        }
+        Ok(Value::new(None, ValueKind::Table(section)).try_deserialize()?)
+    }
+
+    /// Loads the full settings with the overrides applied.
+    pub fn load<T: DeserializeOwned>(&self) -> Result<T, SettingsError> {
+        let settings = self.section(Some);
+        Ok(Value::new(None, ValueKind::Table(settings)).try_deserialize()?)
+    }
+
+    fn section<'k>(&'k self, relative_key: impl Fn(&'k str) -> Option<&'k str>) -> Map<String, Value> {
+        let origin = OVERLAY_ORIGIN.to_string();
+        let mut table = Map::new();
+        let settings = self.base.iter().chain(self.overrides.iter());
+        for (key, value) in settings {
+            if let Some(relative) = relative_key(key) {
+                insert_nested(&mut table, &origin, relative, value.kind.clone());
+            }
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Limits {
+        rate: u32,
+        burst: u32,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct TestSettings {
+        name: String,
+        limits: Limits,
+    }
+
+    fn base() -> SettingsOverlay {
+        let config = assert_ok!(Config::builder()
+            .add_source(File::from_str(
+                "name: base\nlimits: { rate: 10, burst: 20 }\ndatabase: { password: secret }",
+                FileFormat::Yaml
+            ))
+            .build());
+        assert_ok!(SettingsOverlay::new(&config, ["limits.*", "name"]))
+    }
+
+    #[test]
+    fn test_overlay_view() {
+        let overlay = base();
+        let view = assert_ok!(overlay.overlay(r#"{"limits": {"rate": 50}}"#, FileFormat::Json));
+        assert_eq!(view.overrides().keys().collect::<Vec<_>>(), vec!["limits.rate"]);
+        assert_eq!(assert_ok!(view.get::<u32>("limits.rate")), 50);
+        assert_eq!(assert_ok!(view.get::<u32>("limits.burst")), 20);
+        assert_eq!(assert_ok!(view.get::<Limits>("limits")), Limits { rate: 50, burst: 20 });
+        assert_err!(view.get::<String>("missing"));
+        assert_eq!(
+            assert_ok!(view.load::<TestSettings>()),
+            TestSettings {
+                name: "base".to_string(),
+                limits: Limits { rate: 50, burst: 20 },
+            }
+        );
+
+        let untouched = assert_ok!(overlay.overlay("{}", FileFormat::Json));
+        assert_eq!(assert_ok!(untouched.get::<u32>("limits.rate")), 10);
+    }
+
+    #[test]
+    fn test_overlay_rejects_invalid_overrides() {
+        let overlay = base();
+        let rejected = |document: &str| match assert_err!(overlay.overlay(document, FileFormat::Json)) {
+            SettingsError::OverlayRejected { key, .. } => key,
+            err => panic!("expected overlay rejection but got: {err:?}"),
+        };
+
+        assert_eq!(rejected(r#"{"database": {"password": "guess"}}"#), "database.password");
+        assert_eq!(rejected(r#"{"limits": {"rate": "fast"}}"#), "limits.rate");
+        assert_eq!(rejected(r#"{"name": {"nested": "x"}}"#), "name.nested");
+
+        let small = base().with_max_document_len(8);
+        assert_err!(small.overlay(r#"{"name": "too long"}"#, FileFormat::Json));
+    }
+}
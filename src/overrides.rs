@@ -0,0 +1,224 @@
+use config::{ConfigError, Map, Source, Value, ValueKind};
+
+use crate::layer::insert_nested;
+use crate::SettingsError;
+
+/// Origin recorded for the settings supplied by `--set` overrides.
+pub const SET_ORIGIN: &str = "--set";
+
+/// CLI option overrides parsed from `--set key=value` arguments by `parse_set_args()`, added to the
+/// configuration in `LoadingOptions::load_overrides()`; e.g., `Ok(config.add_source(overrides))`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SetOverrides(Map<String, Value>);
+
+impl SetOverrides {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Source for SetOverrides {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        Ok(self.0.clone())
+    }
+}
+
+/// Parses `--set` (or `-o`) arguments, following Helm's syntax, into CLI option overrides.
+///
+/// Each argument holds one or more comma separated `dotted.key=value` assignments, with later
+/// assignments overriding earlier ones. A value's type is inferred from its form:
+/// - `true` and `false` are booleans, `null` and `~` are null, and numbers are integers or floats,
+/// - `{a,b,c}` is a list of values,
+/// - a single or double quoted value is a string verbatim, so `'8080'` or `"a,b"` remain strings,
+/// - anything else is a string.
+///
+/// A backslash escapes a comma outside of quotes; e.g., `hosts=a\,b` sets the string `a,b`.
+pub fn parse_set_args<S: AsRef<str>>(args: &[S]) -> Result<SetOverrides, SettingsError> {
+    let origin = SET_ORIGIN.to_string();
+    let mut table = Map::new();
+    for arg in args {
+        for assignment in split_top_level(arg.as_ref(), ',', true) {
+            let invalid = |message: &str| SettingsError::Bootstrap {
+                message: message.to_string(),
+                setting: assignment.clone(),
+            };
+
+            let (key, value) = assignment
+                .split_once('=')
+                .ok_or_else(|| invalid("--set override must be of the form key=value"))?;
+            let key = key.trim();
+            if key.split('.').any(str::is_empty) {
+                return Err(invalid(
+                    "--set override key must be a dotted path of non-empty segments",
+                ));
+            }
+            insert_nested(&mut table, &origin, key, parse_value(value.trim(), &origin));
+        }
+    }
+    Ok(SetOverrides(table))
+}
+
+fn parse_value(rep: &str, origin: &String) -> ValueKind {
+    if let Some(quoted) = unquote(rep) {
+        return ValueKind::String(quoted);
+    }
+
+    if let Some(items) = rep.strip_prefix('{').and_then(|r| r.strip_suffix('}')) {
+        let values = if items.trim().is_empty() {
+            Vec::new()
+        } else {
+            split_top_level(items, ',', false)
+                .iter()
+                .map(|item| Value::new(Some(origin), parse_value(item.trim(), origin)))
+                .collect()
+        };
+        return ValueKind::Array(values);
+    }
+
+    match rep {
+        "true" => ValueKind::Boolean(true),
+        "false" => ValueKind::Boolean(false),
+        "null" | "~" => ValueKind::Nil,
+        _ => rep.parse::<i64>().map_or_else(
+            |_| {
+                rep.parse::<f64>()
+                    .ok()
+                    .filter(|_| rep.contains(|c: char| c.is_ascii_digit()))
+                    .map_or_else(|| ValueKind::String(rep.to_string()), ValueKind::Float)
+            },
+            ValueKind::I64,
+        ),
+    }
+}
+
+/// The content of a single or double quoted value, with `\"` and `\\` escapes in double quotes
+/// resolved.
+fn unquote(rep: &str) -> Option<String> {
+    if rep.len() < 2 {
+        return None;
+    }
+
+    if let Some(single) = rep.strip_prefix('\'').and_then(|r| r.strip_suffix('\'')) {
+        return Some(single.to_string());
+    }
+
+    let double = rep.strip_prefix('"').and_then(|r| r.strip_suffix('"'))?;
+    let mut unquoted = String::with_capacity(double.len());
+    let mut chars = double.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some(escaped @ ('"' | '\\'))) => {
+                unquoted.push(escaped);
+                chars.next();
+            },
+            _ => unquoted.push(c),
+        }
+    }
+    Some(unquoted)
+}
+
+/// Splits the input on the separator where it is outside of quotes and braces. Backslash escapes
+/// outside of quotes are resolved if `unescape` is set and otherwise retained.
+fn split_top_level(input: &str, separator: char, unescape: bool) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut depth = 0_usize;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                current.push(c);
+                current.extend(chars.next());
+            },
+            (Some(q), _) if c == q => {
+                quote = None;
+                current.push(c);
+            },
+            (Some(_), _) => current.push(c),
+            (None, '\\') => {
+                if !unescape {
+                    current.push(c);
+                }
+                current.extend(chars.next());
+            },
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                current.push(c);
+            },
+            (None, '{') => {
+                depth += 1;
+                current.push(c);
+            },
+            (None, '}') => {
+                depth = depth.saturating_sub(1);
+                current.push(c);
+            },
+            (None, _) if c == separator && depth == 0 => parts.push(std::mem::take(&mut current)),
+            (None, _) => current.push(c),
+        }
+    }
+
+    if !current.is_empty() || !parts.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::Config;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn json(overrides: SetOverrides) -> serde_json::Value {
+        let config = assert_ok!(Config::builder().add_source(overrides).build());
+        assert_ok!(config.try_deserialize())
+    }
+
+    #[test]
+    fn test_parse_set_args() {
+        let overrides = assert_ok!(parse_set_args(&[
+            "database.port=5433",
+            "features.x=true,features.y=false",
+            "database.host=db.internal",
+            "ratio=0.5,nothing=null",
+            "hosts={a, 'b,c', 3}",
+            r#"quoted="8080",escaped=a\,b,inner="say \"hi\"""#,
+            "database.port=5434",
+        ]));
+        assert!(!overrides.is_empty());
+
+        let origin = assert_some!(assert_ok!(overrides.collect()).remove("database"));
+        assert_eq!(origin.origin(), Some(SET_ORIGIN));
+        assert_eq!(
+            json(overrides),
+            serde_json::json!({
+                "database": { "port": 5434, "host": "db.internal" },
+                "features": { "x": true, "y": false },
+                "ratio": 0.5,
+                "nothing": null,
+                "hosts": ["a", "b,c", 3],
+                "quoted": "8080",
+                "escaped": "a,b",
+                "inner": "say \"hi\"",
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_set_args_errors() {
+        assert!(assert_ok!(parse_set_args::<&str>(&[])).is_empty());
+        assert_err!(parse_set_args(&["database.port"]));
+        assert_err!(parse_set_args(&["database..port=1"]));
+        assert_err!(parse_set_args(&["=1"]));
+        assert_err!(parse_set_args(&["a=1,b"]));
+    }
+}
@@ -0,0 +1,88 @@
+use std::path::Path;
+
+use crate::SettingsError;
+
+/// How a load treats a secrets file whose Unix permissions let its group or anyone else read or
+/// write it. Permissions are not checked on other platforms, nor for files not on disk.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum SecretsPermissions {
+    /// The secrets file's permissions are not checked.
+    #[default]
+    Ignore,
+    /// A secrets file readable beyond its owner is loaded with a warning.
+    Warn,
+    /// A secrets file readable beyond its owner fails the load with
+    /// `SettingsError::InsecureSecretsFile`.
+    Deny,
+}
+
+impl SecretsPermissions {
+    /// Checks the secrets file's permissions under the policy.
+    pub fn check(self, path: &Path) -> Result<(), SettingsError> {
+        if self == Self::Ignore {
+            return Ok(());
+        }
+
+        let Some(mode) = insecure_permissions(path)? else {
+            return Ok(());
+        };
+        if self == Self::Deny {
+            tracing::error!(?path, mode=%format!("{mode:o}"), "secrets file is readable beyond its owner");
+            return Err(SettingsError::InsecureSecretsFile { path: path.to_path_buf(), mode });
+        }
+        tracing::warn!(?path, mode=%format!("{mode:o}"), "secrets file is readable beyond its owner; restrict it to mode 600");
+        Ok(())
+    }
+}
+
+/// The file's Unix permissions if its group or anyone else may read or write it; `None` for a
+/// file restricted to its owner, not on disk, or on other platforms.
+pub(crate) fn insecure_permissions(path: &Path) -> Result<Option<u32>, SettingsError> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        if path.is_file() {
+            let mode = std::fs::metadata(path)?.permissions().mode() & 0o777;
+            return Ok((mode & 0o077 != 0).then_some(mode));
+        }
+    }
+    let _ = path;
+    Ok(None)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    use claim::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_secrets_permissions_check() {
+        let path = std::env::temp_dir().join(format!("settings-secrets-permissions-{}.yaml", std::process::id()));
+        assert_ok!(fs::write(&path, "password: hunter2"));
+
+        assert_ok!(fs::set_permissions(&path, fs::Permissions::from_mode(0o644)));
+        assert_eq!(assert_ok!(insecure_permissions(&path)), Some(0o644));
+        assert_ok!(SecretsPermissions::Ignore.check(&path));
+        assert_ok!(SecretsPermissions::Warn.check(&path));
+        let err = assert_err!(SecretsPermissions::Deny.check(&path));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "secrets file {} is readable beyond its owner (mode 644); restrict it to mode 600",
+                path.display()
+            )
+        );
+
+        assert_ok!(fs::set_permissions(&path, fs::Permissions::from_mode(0o600)));
+        assert_none!(assert_ok!(insecure_permissions(&path)));
+        assert_ok!(SecretsPermissions::Deny.check(&path));
+        assert_ok!(SecretsPermissions::Deny.check(Path::new("not/a/secrets.yaml")));
+        let _ignored = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+
+use config::Source;
+
+use crate::layer::LayerSource;
+use crate::Layer;
+
+/// How a load treats optional settings layers that fail to parse; e.g., a user's personal
+/// override file with a syntax error.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum LoadPolicy {
+    /// Any layer failing to parse fails the load.
+    #[default]
+    Strict,
+    /// Optional layers failing to parse are skipped with a warning, so the load carries on with
+    /// the remaining layers. Required layers, such as the application configuration and secrets
+    /// files, still fail the load.
+    SkipInvalidLayers,
+}
+
+impl LoadPolicy {
+    /// Splits the layers into those kept for the load and the optional layers skipped because they
+    /// fail to parse, which only happens for `SkipInvalidLayers`.
+    pub fn apply(self, layers: Vec<LayerSource>) -> (Vec<LayerSource>, Vec<SkippedLayer>) {
+        if self == Self::Strict {
+            return (layers, Vec::default());
+        }
+
+        let mut kept = Vec::with_capacity(layers.len());
+        let mut skipped = Vec::default();
+        for layer in layers {
+            match layer.collect() {
+                Err(err) if layer.is_optional() => {
+                    tracing::warn!(
+                        error=%err, layer=%layer.layer(), origin=%layer.origin(),
+                        "skipping invalid optional settings layer"
+                    );
+                    skipped.push(SkippedLayer {
+                        layer: layer.layer(),
+                        origin: layer.origin().to_string(),
+                        error: err.to_string(),
+                    });
+                },
+                _ => kept.push(layer),
+            }
+        }
+        (kept, skipped)
+    }
+}
+
+/// An optional settings layer skipped by `LoadPolicy::SkipInvalidLayers`, with why it failed to
+/// parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedLayer {
+    pub layer: Layer,
+    pub origin: String,
+    pub error: String,
+}
+
+impl fmt::Display for SkippedLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "skipped {} {}: {}", self.layer, self.origin, self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn layers() -> Vec<LayerSource> {
+        let toml = |contents: &str| File::from_str(contents, FileFormat::Toml);
+        vec![
+            LayerSource::new(Layer::Config, "application.toml", toml("port = 8080")),
+            LayerSource::new(Layer::Config, "conf.d/10-broken.toml", toml("port = ")).optional(),
+            LayerSource::new(Layer::EnvironmentConfig, "local.toml", toml("port = 8081")).optional(),
+        ]
+    }
+
+    #[test]
+    fn test_load_policy_skips_invalid_optional_layers() {
+        let (kept, skipped) = LoadPolicy::Strict.apply(layers());
+        assert_eq!(kept.len(), 3);
+        assert!(skipped.is_empty());
+
+        let (kept, skipped) = LoadPolicy::SkipInvalidLayers.apply(layers());
+        let origins: Vec<_> = kept.iter().map(LayerSource::origin).collect();
+        assert_eq!(origins, vec!["application.toml", "local.toml"]);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].layer, Layer::Config);
+        assert!(skipped[0]
+            .to_string()
+            .starts_with("skipped config conf.d/10-broken.toml: "));
+
+        let mut required = layers();
+        required.insert(
+            0,
+            LayerSource::new(Layer::Secrets, "secrets.toml", File::from_str("[", FileFormat::Toml)),
+        );
+        let (kept, skipped) = LoadPolicy::SkipInvalidLayers.apply(required);
+        assert_eq!(kept.len(), 3);
+        assert_eq!(skipped.len(), 1);
+    }
+}
@@ -0,0 +1,209 @@
+use std::path::{Path, PathBuf};
+
+use config::{Map, Source, Value, ValueKind};
+
+use crate::fs::ConfigFile;
+use crate::layer::{LayerSource, TableSource};
+use crate::migrations::{Migrations, VERSION_KEY};
+use crate::{ConfigFs, Layer, SettingsError};
+
+/// A named bundle of settings that applications ship, or users share, such as recommended
+/// production settings; e.g.,
+///
+/// ```yaml
+/// name: production
+/// description: Recommended production settings
+/// settings_version: 2
+/// settings:
+///   database: { pool_size: 20 }
+/// ```
+///
+/// `settings_version` is the schema version the preset's settings are written for (see
+/// `LoadingOptions::migrations()`). Presets written for an older version are migrated as they are
+/// applied, and presets requiring a newer version than the application's are rejected.
+///
+/// Presets are applied above the application's configuration files and below the environment
+/// configuration files. Each preset's layer is described by its name, so provenance reports which
+/// preset supplied a setting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preset {
+    name: String,
+    description: Option<String>,
+    version: u32,
+    path: PathBuf,
+    settings: Map<String, Value>,
+}
+
+impl Preset {
+    /// Reads the preset file at the path, which may omit the extension.
+    pub fn load(fs: &dyn ConfigFs, path: &Path) -> Result<Self, SettingsError> {
+        let file = ConfigFile::load_required(fs, path)?;
+        let path = file.path().to_path_buf();
+        let invalid = |message: &str| SettingsError::Bootstrap {
+            message: message.to_string(),
+            setting: path.display().to_string(),
+        };
+
+        let mut table = file.collect()?;
+        let name = match table.remove("name").map(|v| v.kind) {
+            Some(ValueKind::String(name)) if !name.is_empty() => name,
+            _ => return Err(invalid("preset must be named")),
+        };
+        let description = table.remove("description").map(|v| v.to_string());
+        let version = match table.remove(VERSION_KEY) {
+            Some(version) => {
+                let version = version
+                    .into_uint()
+                    .map_err(|err| invalid(&format!("invalid preset {VERSION_KEY}: {err}")))?;
+                u32::try_from(version).map_err(|err| invalid(&format!("invalid preset {VERSION_KEY}: {err}")))?
+            },
+            None => 0,
+        };
+        let settings = match table.remove("settings").map(|v| v.kind) {
+            Some(ValueKind::Table(settings)) => settings,
+            None => Map::new(),
+            Some(_) => return Err(invalid("preset settings must be a table")),
+        };
+
+        Ok(Self { name, description, version, path, settings })
+    }
+
+    pub const fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// The settings schema version the preset is written for.
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// The preset's settings as a configuration layer, migrated to the current schema version.
+    pub fn layer(&self, migrations: &Migrations) -> Result<LayerSource, SettingsError> {
+        let current = migrations.current_version();
+        if current < self.version {
+            return Err(SettingsError::IncompatiblePreset {
+                name: self.name.clone(),
+                required: self.version,
+                current,
+            });
+        }
+
+        let mut settings = self.settings.clone();
+        if !migrations.is_empty() && !settings.is_empty() {
+            settings.insert(VERSION_KEY.to_string(), Value::from(u64::from(self.version)));
+            let applied = migrations
+                .migrate(&mut settings)
+                .map_err(|message| SettingsError::Bootstrap { message, setting: self.path.display().to_string() })?;
+            if !applied.is_empty() {
+                tracing::info!(preset=%self.name, ?applied, "migrated preset settings");
+            }
+        }
+
+        let origin = format!("preset {} ({})", self.name, self.path.display());
+        Ok(LayerSource::new(Layer::Config, origin, TableSource(settings)))
+    }
+}
+
+/// Applies the preset to the layers, ordered from lowest to highest precedence, after the
+/// configuration layers and any presets already applied.
+pub fn apply_preset(
+    layers: &mut Vec<LayerSource>, preset: &Preset, migrations: &Migrations,
+) -> Result<(), SettingsError> {
+    insert_preset_layer(layers, preset.layer(migrations)?);
+    Ok(())
+}
+
+pub(crate) fn insert_preset_layer(layers: &mut Vec<LayerSource>, preset: LayerSource) {
+    let position = layers
+        .iter()
+        .rposition(|l| l.layer() == Layer::Config)
+        .map_or(0, |p| p + 1);
+    tracing::info!(preset=%preset.origin(), "applying settings preset");
+    layers.insert(position, preset);
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{Config, File, FileFormat};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::fs::MemoryFs;
+    use crate::migrations::ConfigMigration;
+
+    const PRESET: &str = "name: production\ndescription: Recommended production settings\nsettings_version: \
+                          1\nsettings:\n  database: { pool: 20 }";
+
+    #[test]
+    fn test_apply_preset() {
+        let fs = MemoryFs::new().with_file("presets/production.yaml", PRESET);
+        let preset = assert_ok!(Preset::load(&fs, Path::new("presets/production")));
+        assert_eq!(preset.name(), "production");
+        assert_eq!(preset.description(), Some("Recommended production settings"));
+        assert_eq!(preset.version(), 1);
+
+        let migrations = Migrations::new()
+            .register(ConfigMigration::new(2, "rename pool").rename_key("database.pool", "database.pool_size"));
+        let mut layers = vec![
+            LayerSource::new(
+                Layer::Config,
+                "application.yaml",
+                File::from_str("database: { pool: 5, host: localhost }", FileFormat::Yaml),
+            ),
+            LayerSource::new(
+                Layer::EnvironmentConfig,
+                "production.yaml",
+                File::from_str("database: { host: db.prod }", FileFormat::Yaml),
+            ),
+        ];
+        assert_ok!(apply_preset(&mut layers, &preset, &migrations));
+
+        let origins: Vec<_> = layers.iter().map(LayerSource::origin).collect();
+        assert_eq!(
+            origins,
+            vec![
+                "application.yaml",
+                "preset production (presets/production.yaml)",
+                "production.yaml"
+            ]
+        );
+
+        let mut builder = Config::builder();
+        for layer in layers {
+            builder = builder.add_source(layer);
+        }
+        let actual: serde_json::Value = assert_ok!(assert_ok!(builder.build()).try_deserialize());
+        assert_eq!(
+            actual,
+            serde_json::json!({
+                "database": { "pool": 5, "pool_size": 20, "host": "db.prod" },
+                "settings_version": 2,
+            })
+        );
+
+        let err = assert_err!(preset.layer(&Migrations::new()));
+        assert!(matches!(
+            err,
+            SettingsError::IncompatiblePreset { required: 1, current: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn test_invalid_preset() {
+        let fs = MemoryFs::new()
+            .with_file("unnamed.yaml", "settings: { foo: bar }")
+            .with_file("scalar.yaml", "name: scalar\nsettings: 3");
+        assert_err!(Preset::load(&fs, Path::new("unnamed.yaml")));
+        assert_err!(Preset::load(&fs, Path::new("scalar.yaml")));
+        assert_err!(Preset::load(&fs, Path::new("missing.yaml")));
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+
+use config::{Config, Value, ValueKind};
+use serde::de::DeserializeOwned;
+use serde_path_to_error::Segment;
+
+use crate::audit::REDACTED;
+use crate::layer::LayerSource;
+use crate::lenient::lookup;
+use crate::{Layer, SettingsError};
+
+/// Fragments of a setting's name marking its value as sensitive, so it is redacted from errors.
+const SENSITIVE_KEY_FRAGMENTS: &[&str] = &["password", "secret", "token", "credential", "private_key"];
+
+/// Locates the setting that fails to deserialize into the settings, describing it as
+/// `SettingsError::Deserialization` with its value and the layer supplying it.
+///
+/// Values supplied by the secrets layer, or held by settings named as sensitive (e.g., `password`),
+/// are redacted.
+pub fn locate_deserialize_error<T: DeserializeOwned>(config: &Config, layers: &[LayerSource]) -> Option<SettingsError> {
+    let value: Value = config.clone().try_deserialize().ok()?;
+    let err = serde_path_to_error::deserialize::<_, T>(value.clone()).err()?;
+    let segments: Vec<Segment> = err.path().iter().cloned().collect();
+    if segments.is_empty() {
+        return None;
+    }
+
+    let key = err.path().to_string();
+    let setting_key = key.split('[').next().unwrap_or_default();
+    let supplier = layers.iter().rev().find(|l| {
+        l.collect_flattened()
+            .is_ok_and(|settings| settings.contains_key(setting_key))
+    });
+
+    let redact = supplier.is_some_and(|l| l.layer() == Layer::Secrets) || is_sensitive(&key);
+    let value = lookup(&value, &segments)
+        .filter(|v| !matches!(v.kind, ValueKind::Table(_) | ValueKind::Array(_)))
+        .map(|v| if redact { REDACTED.to_string() } else { v.to_string() });
+
+    Some(SettingsError::Deserialization {
+        key,
+        value,
+        origin: supplier.map(|l| l.origin().to_string()),
+        layer: supplier.map(LayerSource::layer),
+        message: err.inner().to_string(),
+    })
+}
+
+/// Whether the setting is named as sensitive, so its value should be redacted.
+pub fn is_sensitive(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SENSITIVE_KEY_FRAGMENTS.iter().any(|fragment| key.contains(fragment))
+}
+
+/// The origin recorded on a merged value, or else that of the highest layer supplying it, given
+/// each layer's origin and flattened settings from lowest to highest precedence.
+pub fn value_origin(layer_settings: &[(String, BTreeMap<String, Value>)], key: &str, value: &Value) -> Option<String> {
+    value.origin().map(ToString::to_string).or_else(|| {
+        let rendered = value.to_string();
+        layer_settings
+            .iter()
+            .rev()
+            .find(|(_, settings)| settings.get(key).is_some_and(|v| v.to_string() == rendered))
+            .map(|(origin, _)| origin.clone())
+    })
+}
+
+pub fn display_provenance(value: Option<&str>, origin: Option<&str>) -> String {
+    let mut provenance = String::new();
+    if let Some(value) = value {
+        provenance.push_str(&format!(" = {value}"));
+    }
+    if let Some(origin) = origin {
+        provenance.push_str(&format!(" from {origin}"));
+    }
+    provenance
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Database {
+        port: u16,
+        password: u32,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct TestSettings {
+        database: Database,
+    }
+
+    fn layers(secrets: &str) -> Vec<LayerSource> {
+        vec![
+            LayerSource::new(
+                Layer::Config,
+                "application.yaml",
+                File::from_str("database: { port: 5432, password: 1 }", FileFormat::Yaml),
+            ),
+            LayerSource::new(
+                Layer::Secrets,
+                "secrets.yaml",
+                File::from_str(secrets, FileFormat::Yaml),
+            ),
+        ]
+    }
+
+    fn locate(layers: &[LayerSource]) -> Option<SettingsError> {
+        let mut builder = Config::builder();
+        for layer in layers {
+            builder = builder.add_source(layer.clone());
+        }
+        locate_deserialize_error::<TestSettings>(&assert_ok!(builder.build()), layers)
+    }
+
+    #[test]
+    fn test_locate_deserialize_error() {
+        let err = assert_some!(locate(&layers("database: { port: not-a-port }")));
+        let SettingsError::Deserialization { ref key, ref value, ref origin, layer, .. } = err else {
+            panic!("expected deserialization error but got: {err:?}");
+        };
+        assert_eq!(key, "database.port");
+        assert_eq!(value.as_deref(), Some(REDACTED));
+        assert_eq!(origin.as_deref(), Some("secrets.yaml"));
+        assert_eq!(layer, Some(Layer::Secrets));
+        assert!(err
+            .to_string()
+            .starts_with("invalid setting database.port = <redacted> from secrets.yaml: "));
+
+        let plain = vec![LayerSource::new(
+            Layer::Config,
+            "application.yaml",
+            File::from_str("database: { port: 70000, password: 1 }", FileFormat::Yaml),
+        )];
+        let err = assert_some!(locate(&plain));
+        assert!(err
+            .to_string()
+            .starts_with("invalid setting database.port = 70000 from application.yaml: "));
+
+        let mut sensitive = plain;
+        sensitive.push(LayerSource::new(
+            Layer::EnvironmentConfig,
+            "local.yaml",
+            File::from_str("database: { port: 5432, password: hunter2 }", FileFormat::Yaml),
+        ));
+        let err = assert_some!(locate(&sensitive));
+        assert!(err
+            .to_string()
+            .starts_with("invalid setting database.password = <redacted> from local.yaml: "));
+
+        assert_none!(locate(&layers("database: { port: 5433 }")));
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use config::{Config, Source, Value};
+use serde::de::DeserializeOwned;
+
+use crate::fs::{ConfigFile, RealFs};
+use crate::layer::{flatten_into, LayerSource};
+use crate::provenance::value_origin;
+use crate::{ConfigFs, Layer, SettingsError};
+
+/// Environment variable prefix QuickSettings reads alongside a settings file, matching the
+/// `SettingsLoader` default.
+pub const DEFAULT_ENV_PREFIX: &str = "app";
+
+/// Separator of the nested keys in environment variable names; e.g., `APP__DATABASE__HOST`.
+pub const ENV_SEPARATOR: &str = "__";
+
+/// Settings loaded without a settings type or `LoadingOptions`, for quick scripts and small tools.
+///
+/// Values are looked up dynamically by their dotted keys, or the whole configuration deserialized
+/// once a settings type is worth writing. The settings file's format is detected from its
+/// extension, and environment variables (`APP__DATABASE__HOST` for `database.host`) override it.
+///
+/// ```no_run
+/// use settings_loader::QuickSettings;
+///
+/// let settings = QuickSettings::load("resources/application").unwrap();
+/// let port: u16 = settings.get("server.port").unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct QuickSettings {
+    config: Config,
+    layers: Vec<LayerSource>,
+    layer_settings: Vec<(String, BTreeMap<String, Value>)>,
+}
+
+impl QuickSettings {
+    /// Loads the settings file at the path, which may omit the extension, overridden by `APP__*`
+    /// environment variables. If no settings file is found and the argument is not path-like, it
+    /// is taken as an environment variable prefix and only those variables are loaded.
+    pub fn load(path_or_env_prefix: &str) -> Result<Self, SettingsError> {
+        let path = Path::new(path_or_env_prefix);
+        let is_path_like = path.components().count() > 1 || path.extension().is_some();
+        if is_path_like || ConfigFile::locate(&RealFs, path).is_some() {
+            Self::from_file(path)
+        } else {
+            Self::from_env(path_or_env_prefix)
+        }
+    }
+
+    /// Loads the settings file at the path, which may omit the extension, overridden by `APP__*`
+    /// environment variables.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, SettingsError> {
+        Self::from_file_in(&RealFs, path.as_ref())
+    }
+
+    /// Loads only the environment variables with the prefix; e.g., `MY_TOOL__VERBOSE` for the
+    /// prefix `my_tool`.
+    pub fn from_env(prefix: &str) -> Result<Self, SettingsError> {
+        Self::from_layers(vec![env_layer(prefix)])
+    }
+
+    fn from_file_in(fs: &dyn ConfigFs, path: &Path) -> Result<Self, SettingsError> {
+        let file = ConfigFile::load_required(fs, path)?;
+        let origin = file.path().display().to_string();
+        Self::from_layers(vec![
+            LayerSource::new(Layer::Config, origin, file),
+            env_layer(DEFAULT_ENV_PREFIX),
+        ])
+    }
+
+    fn from_layers(layers: Vec<LayerSource>) -> Result<Self, SettingsError> {
+        let mut builder = Config::builder();
+        let mut layer_settings = Vec::with_capacity(layers.len());
+        for layer in &layers {
+            builder = builder.add_source(layer.clone());
+            layer_settings.push((layer.origin().to_string(), layer.collect_flattened()?));
+        }
+        Ok(Self { config: builder.build()?, layers, layer_settings })
+    }
+
+    /// The value of the setting at the dotted key.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<T, SettingsError> {
+        Ok(self.config.get(key)?)
+    }
+
+    /// The value of the setting at the dotted key, or the default if it is not set.
+    pub fn get_or<T: DeserializeOwned>(&self, key: &str, default: T) -> Result<T, SettingsError> {
+        match self.config.get(key) {
+            Ok(value) => Ok(value),
+            Err(config::ConfigError::NotFound(_)) => Ok(default),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Where the setting at the dotted key was loaded from.
+    pub fn origin(&self, key: &str) -> Option<String> {
+        let value: Value = self.config.get(key).ok()?;
+        value_origin(&self.layer_settings, key, &value)
+    }
+
+    /// The dotted keys of every setting, in alphabetical order.
+    pub fn keys(&self) -> Result<Vec<String>, SettingsError> {
+        let mut flattened = BTreeMap::new();
+        flatten_into(None, self.config.collect()?, &mut flattened);
+        Ok(flattened.into_keys().collect())
+    }
+
+    /// Deserializes all the settings into a settings type.
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, SettingsError> {
+        Ok(self.config.clone().try_deserialize()?)
+    }
+
+    pub const fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn layers(&self) -> &[LayerSource] {
+        &self.layers
+    }
+}
+
+fn env_layer(prefix: &str) -> LayerSource {
+    let origin = format!("environment variables {}{ENV_SEPARATOR}*", prefix.to_uppercase());
+    LayerSource::new(
+        Layer::EnvironmentVariables,
+        origin,
+        config::Environment::with_prefix(prefix).separator(ENV_SEPARATOR),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::fs::MemoryFs;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    #[test]
+    fn test_quick_settings_from_file() {
+        let fs = MemoryFs::new().with_file("tool/settings.toml", "[server]\nhost = \"localhost\"\nport = 8080");
+        let settings = assert_ok!(QuickSettings::from_file_in(&fs, Path::new("tool/settings")));
+
+        assert_eq!(assert_ok!(settings.get::<u16>("server.port")), 8080);
+        assert_eq!(assert_ok!(settings.get_or("server.timeout", 30_u64)), 30);
+        assert_err!(settings.get::<u16>("server.host"));
+        assert_eq!(
+            assert_ok!(settings.get::<Server>("server")),
+            Server { host: "localhost".to_string(), port: 8080 }
+        );
+        assert_eq!(assert_ok!(settings.keys()), vec!["server.host", "server.port"]);
+        assert!(assert_some!(settings.origin("server.host")).ends_with("settings.toml"));
+        assert_err!(QuickSettings::from_file_in(&fs, Path::new("tool/missing")));
+    }
+
+    #[test]
+    fn test_quick_settings_from_env() {
+        std::env::set_var("QUICK_SETTINGS_TEST__SERVER__HOST", "example.com");
+        std::env::set_var("QUICK_SETTINGS_TEST__SERVER__PORT", "443");
+        let settings = assert_ok!(QuickSettings::load("quick_settings_test"));
+        std::env::remove_var("QUICK_SETTINGS_TEST__SERVER__HOST");
+        std::env::remove_var("QUICK_SETTINGS_TEST__SERVER__PORT");
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct ToolSettings {
+            server: Server,
+        }
+        let actual: ToolSettings = assert_ok!(settings.deserialize());
+        assert_eq!(actual.server, Server { host: "example.com".to_string(), port: 443 });
+        assert_eq!(
+            settings.origin("server.port").as_deref(),
+            Some("environment variables QUICK_SETTINGS_TEST__*")
+        );
+        assert_err!(QuickSettings::load("missing/settings.yaml"));
+    }
+}
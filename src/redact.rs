@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use config::{Map, Value};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::REDACTED;
+use crate::layer::{flatten_into, key_pattern_matches};
+use crate::provenance::is_sensitive;
+
+/// Wraps a sensitive setting, such as a connection string, so it is masked when printed via
+/// `Debug` or `Display`. (De)serialization is transparent.
+#[derive(Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Redacted<T>(T);
+
+impl<T> Redacted<T> {
+    pub const fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// The wrapped value; reaching it is explicit so accidental printing is not.
+    pub const fn expose(&self) -> &T {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Redacted<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> Deref for Redacted<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Redacted<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T> fmt::Display for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// Settings that can be printed with their sensitive fields masked, for settings types whose
+/// fields are not wrapped in `Redacted`.
+pub trait RedactedSettings: Serialize {
+    /// Dotted patterns, as in `KeyRestriction`, of the fields to mask; e.g., `database.password`.
+    fn redacted_keys() -> Vec<&'static str>;
+
+    /// The settings as JSON with the redacted fields masked.
+    ///
+    /// `Redacted` fields serialize their values, so list them too if this JSON is logged.
+    fn redacted(&self) -> serde_json::Value {
+        let mut json = serde_json::to_value(self).unwrap_or(serde_json::Value::Null);
+        mask(None, &mut json, &Self::redacted_keys());
+        json
+    }
+}
+
+fn mask(prefix: Option<&str>, json: &mut serde_json::Value, patterns: &[&str]) {
+    if let serde_json::Value::Object(ref mut object) = *json {
+        for (key, value) in object.iter_mut() {
+            let path = prefix.map_or_else(|| key.clone(), |p| format!("{p}.{key}"));
+            if patterns.iter().any(|pattern| key_pattern_matches(pattern, &path)) {
+                *value = serde_json::Value::String(REDACTED.to_string());
+            } else {
+                mask(Some(path.as_str()), value, patterns);
+            }
+        }
+    }
+}
+
+/// The merged settings by dotted key, for logging, with the values of sensitive keys and of keys
+/// matching the patterns masked.
+pub(crate) fn redacted_config(table: Map<String, Value>, patterns: &[String]) -> BTreeMap<String, String> {
+    let mut flattened = BTreeMap::new();
+    flatten_into(None, table, &mut flattened);
+    flattened
+        .into_iter()
+        .map(|(key, value)| {
+            let masked = is_sensitive(&key) || patterns.iter().any(|pattern| key_pattern_matches(pattern, &key));
+            let value = if masked { REDACTED.to_string() } else { value.to_string() };
+            (key, value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct DatabaseSettings {
+        host: String,
+        url: Redacted<String>,
+        password: String,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct TestSettings {
+        database: DatabaseSettings,
+    }
+
+    impl RedactedSettings for TestSettings {
+        fn redacted_keys() -> Vec<&'static str> {
+            vec!["database.password"]
+        }
+    }
+
+    #[test]
+    fn test_redacted_settings() {
+        let settings: TestSettings = assert_ok!(serde_json::from_str(
+            r#"{"database": {"host": "localhost", "url": "postgres://u:p@localhost", "password": "hunter2"}}"#
+        ));
+        assert_eq!(settings.database.url.expose(), "postgres://u:p@localhost");
+        assert!(!format!("{settings:?}").contains("postgres://"));
+        assert_eq!(settings.database.url.to_string(), REDACTED);
+
+        assert_eq!(
+            settings.redacted(),
+            serde_json::json!({
+                "database": { "host": "localhost", "url": "postgres://u:p@localhost", "password": REDACTED }
+            })
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use config::{Config, Source};
+
+use crate::layer::{flatten_into, LayerSource};
+use crate::policy::SkippedLayer;
+use crate::provenance::value_origin;
+use crate::{Layer, PrecedenceProfile, SettingsError};
+
+/// How a settings layer contributed to a load.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerReport {
+    pub layer: Layer,
+    pub origin: String,
+    /// The number of settings the layer supplies, whether or not higher layers override them.
+    pub settings: usize,
+    /// How long the layer took to read and parse.
+    pub parse_duration: Duration,
+}
+
+/// Describes how the settings were loaded, returned by `SettingsLoader::load_with_report()`;
+/// e.g., to log at startup for supportability.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadReport {
+    /// The layers loaded, ordered from lowest to highest precedence.
+    pub layers: Vec<LayerReport>,
+    /// The precedence profile the layers were ordered by.
+    pub precedence_profile: PrecedenceProfile,
+    /// Optional layers skipped by `LoadPolicy::SkipInvalidLayers` because they failed to parse.
+    pub skipped: Vec<SkippedLayer>,
+    /// Environment configuration files searched for but not found.
+    pub missing: Vec<PathBuf>,
+    /// The number of settings supplied by environment variables.
+    pub env_vars_applied: usize,
+    /// Problems that did not fail the load; e.g., keys the settings type ignores.
+    pub warnings: Vec<String>,
+    /// The number of effective settings supplied by each layer's origin.
+    pub provenance: BTreeMap<String, usize>,
+    /// How long the whole load took.
+    pub duration: Duration,
+}
+
+impl LoadReport {
+    /// Reports on the layers and the configuration merged from them, timing how long each layer
+    /// takes to parse.
+    pub fn of_layers(layers: &[LayerSource], config: &Config) -> Result<Self, SettingsError> {
+        let mut report = Self::default();
+        let mut layer_settings = Vec::with_capacity(layers.len());
+        for layer in layers {
+            let started = Instant::now();
+            let settings = layer.collect_flattened()?;
+            report.layers.push(LayerReport {
+                layer: layer.layer(),
+                origin: layer.origin().to_string(),
+                settings: settings.len(),
+                parse_duration: started.elapsed(),
+            });
+            if layer.layer() == Layer::EnvironmentVariables {
+                report.env_vars_applied += settings.len();
+            }
+            layer_settings.push((layer.origin().to_string(), settings));
+        }
+
+        let mut effective = BTreeMap::new();
+        flatten_into(None, config.collect()?, &mut effective);
+        for (key, value) in effective {
+            if let Some(origin) = value_origin(&layer_settings, &key, &value) {
+                *report.provenance.entry(origin).or_default() += 1;
+            }
+        }
+        Ok(report)
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "settings loaded in {:?}", self.duration)?;
+        writeln!(f, "  layers ordered by {:?} precedence", self.precedence_profile)?;
+        for layer in &self.layers {
+            let effective = self.provenance.get(&layer.origin).copied().unwrap_or_default();
+            writeln!(
+                f,
+                "  {} {}: {} settings, {} effective, parsed in {:?}",
+                layer.layer, layer.origin, layer.settings, effective, layer.parse_duration
+            )?;
+        }
+        for skipped in &self.skipped {
+            writeln!(f, "  {skipped}")?;
+        }
+        for missing in &self.missing {
+            writeln!(f, "  missing {}", missing.display())?;
+        }
+        writeln!(f, "  {} settings from environment variables", self.env_vars_applied)?;
+        for warning in &self.warnings {
+            writeln!(f, "  warning: {warning}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_load_report_of_layers() {
+        let layers = vec![
+            LayerSource::new(
+                Layer::Config,
+                "application.yaml",
+                File::from_str("database: { host: localhost, port: 5432 }\nfoo: bar", FileFormat::Yaml),
+            ),
+            LayerSource::new(
+                Layer::EnvironmentVariables,
+                "environment variables APP__*",
+                File::from_str(r#"{"database": {"port": 6543}}"#, FileFormat::Json),
+            ),
+        ];
+        let mut builder = Config::builder();
+        for layer in &layers {
+            builder = builder.add_source(layer.clone());
+        }
+        let config = assert_ok!(builder.build());
+
+        let mut report = assert_ok!(LoadReport::of_layers(&layers, &config));
+        assert_eq!(report.layers.len(), 2);
+        assert_eq!(report.layers[0].settings, 3);
+        assert_eq!(report.env_vars_applied, 1);
+        assert_eq!(
+            report.provenance,
+            BTreeMap::from([
+                ("application.yaml".to_string(), 2),
+                ("environment variables APP__*".to_string(), 1),
+            ])
+        );
+
+        report.missing.push(PathBuf::from("resources/production"));
+        report.warnings.push("ignored setting foo".to_string());
+        let actual = report.to_string();
+        assert!(actual.starts_with("settings loaded in "));
+        assert!(actual.contains("  layers ordered by CliEnvFile precedence\n"));
+        assert!(actual.contains("  config application.yaml: 3 settings, 2 effective, parsed in "));
+        assert!(actual.contains("  missing resources/production\n"));
+        assert!(actual.contains("  1 settings from environment variables\n"));
+        assert!(actual.ends_with("  warning: ignored setting foo\n"));
+    }
+}
@@ -0,0 +1,107 @@
+use config::{Config, Value, ValueKind};
+
+use crate::{Environment, SettingsError};
+
+/// A setting that must be supplied by some layer, in every environment or only in the listed ones.
+///
+/// E.g., `tls.cert_path` may be required in production but not locally. Declared by
+/// `LoadingOptions::required_keys()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequiredKey {
+    key: String,
+    environments: Vec<Environment>,
+}
+
+impl RequiredKey {
+    /// Requires the setting at the dotted key in every environment.
+    pub fn new(key: impl Into<String>) -> Self {
+        Self { key: key.into(), environments: Vec::default() }
+    }
+
+    /// Requires the setting only in the environment, along with any others already listed.
+    pub fn in_environment(mut self, environment: impl Into<Environment>) -> Self {
+        self.environments.push(environment.into());
+        self
+    }
+
+    pub const fn key(&self) -> &str {
+        self.key.as_str()
+    }
+
+    /// The environments the setting is required in; every environment if empty.
+    pub fn environments(&self) -> &[Environment] {
+        &self.environments
+    }
+
+    /// Whether the setting is required in the environment.
+    pub fn applies_to(&self, environment: Option<&Environment>) -> bool {
+        self.environments.is_empty() || environment.is_some_and(|env| self.environments.contains(env))
+    }
+}
+
+/// Checks the configuration supplies the settings required in the environment, reporting every
+/// missing setting at once.
+pub fn check_required(
+    config: &Config, required: &[RequiredKey], environment: Option<&Environment>,
+) -> Result<(), SettingsError> {
+    let missing: Vec<_> = required
+        .iter()
+        .filter(|r| r.applies_to(environment))
+        .filter(|r| {
+            config
+                .get::<Value>(&r.key)
+                .map_or(true, |value| matches!(value.kind, ValueKind::Nil))
+        })
+        .map(|r| r.key.clone())
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+    tracing::error!(?missing, ?environment, "required settings are missing");
+    Err(SettingsError::MissingRequiredKeys {
+        environment: environment.map(ToString::to_string),
+        keys: missing,
+    })
+}
+
+pub(crate) fn display_environment(environment: Option<&str>) -> String {
+    environment.map_or_else(String::new, |env| format!(" in {env}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_check_required() {
+        let config = assert_ok!(Config::builder()
+            .add_source(File::from_str(
+                "tls: { enabled: true }\ndatabase: { host: localhost, password: ~ }",
+                FileFormat::Yaml
+            ))
+            .build());
+        let required = vec![
+            RequiredKey::new("database.host"),
+            RequiredKey::new("tls.cert_path").in_environment("production"),
+            RequiredKey::new("database.password")
+                .in_environment("production")
+                .in_environment("staging"),
+        ];
+
+        assert_ok!(check_required(&config, &required, None));
+        assert_ok!(check_required(&config, &required, Some(&"local".into())));
+        let err = assert_err!(check_required(&config, &required, Some(&"production".into())));
+        assert_eq!(
+            err.to_string(),
+            "required settings missing in production: tls.cert_path, database.password"
+        );
+
+        let err = assert_err!(check_required(&config, &[RequiredKey::new("server.port")], None));
+        assert_eq!(err.to_string(), "required settings missing: server.port");
+    }
+}
@@ -0,0 +1,134 @@
+use std::path::Path;
+
+use config::{ConfigError, Map, Source, Value, ValueKind};
+
+use crate::layer::insert_nested;
+
+/// Key under which the runtime context's settings are exposed.
+pub const RUNTIME_SYSTEM_KEY: &str = "runtime.system";
+
+/// Resources detected for the running process, exposed as the lowest precedence settings under
+/// `runtime.system` via `LoadingOptions::runtime_context()`.
+///
+/// Settings can then refer to them; e.g., `workers: ${runtime.system.cpus}` with interpolation
+/// enabled. The keys are `cpus`, `memory_bytes`, and, when running in a container with limits,
+/// `cpu_limit` and `memory_limit_bytes`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RuntimeContext {
+    pub cpus: usize,
+    pub memory_bytes: Option<u64>,
+    pub cpu_limit: Option<f64>,
+    pub memory_limit_bytes: Option<u64>,
+}
+
+impl RuntimeContext {
+    /// Detects the available parallelism, the total memory, and the cgroup (v2 or v1) limits.
+    pub fn detect() -> Self {
+        let read = |path: &str| std::fs::read_to_string(Path::new(path)).ok();
+        let memory_limit_bytes = read("/sys/fs/cgroup/memory.max")
+            .or_else(|| read("/sys/fs/cgroup/memory/memory.limit_in_bytes"))
+            .and_then(|limit| parse_memory_limit(&limit));
+        let cpu_limit = read("/sys/fs/cgroup/cpu.max").and_then(|max| parse_cpu_max(&max));
+
+        let context = Self {
+            cpus: std::thread::available_parallelism().map_or(1, |cpus| cpus.get()),
+            memory_bytes: read("/proc/meminfo").and_then(|meminfo| parse_meminfo_total(&meminfo)),
+            cpu_limit,
+            memory_limit_bytes,
+        };
+        tracing::info!(?context, "detected runtime context");
+        context
+    }
+}
+
+impl Source for RuntimeContext {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let origin = "runtime context".to_string();
+        let mut table = Map::new();
+        let mut insert = |key: &str, value: ValueKind| {
+            insert_nested(&mut table, &origin, &format!("{RUNTIME_SYSTEM_KEY}.{key}"), value);
+        };
+        insert("cpus", ValueKind::U64(self.cpus as u64));
+        if let Some(memory) = self.memory_bytes {
+            insert("memory_bytes", ValueKind::U64(memory));
+        }
+        if let Some(cpu_limit) = self.cpu_limit {
+            insert("cpu_limit", ValueKind::Float(cpu_limit));
+        }
+        if let Some(memory_limit) = self.memory_limit_bytes {
+            insert("memory_limit_bytes", ValueKind::U64(memory_limit));
+        }
+        Ok(table)
+    }
+}
+
+fn parse_meminfo_total(meminfo: &str) -> Option<u64> {
+    let line = meminfo.lines().find(|line| line.starts_with("MemTotal:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Parses a cgroup memory limit, where `max` (or an implausibly large v1 value) means unlimited.
+fn parse_memory_limit(limit: &str) -> Option<u64> {
+    const UNLIMITED_V1: u64 = 1 << 62;
+    limit.trim().parse().ok().filter(|limit| *limit < UNLIMITED_V1)
+}
+
+/// Parses a cgroup v2 `cpu.max` of `<quota> <period>` into a number of CPUs.
+fn parse_cpu_max(max: &str) -> Option<f64> {
+    let mut parts = max.split_whitespace();
+    let quota: f64 = parts.next()?.parse().ok()?;
+    let period: f64 = parts.next()?.parse().ok()?;
+    (0.0 < period).then(|| quota / period)
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::layer::LayerSource;
+    use crate::Layer;
+
+    #[test]
+    fn test_parse_runtime_resources() {
+        assert_eq!(
+            parse_meminfo_total("MemTotal:       16318404 kB\nMemFree:         1234 kB\n"),
+            Some(16_318_404 * 1024)
+        );
+        assert_eq!(parse_memory_limit("536870912\n"), Some(536_870_912));
+        assert_none!(parse_memory_limit("max\n"));
+        assert_none!(parse_memory_limit("9223372036854771712"));
+        assert_eq!(parse_cpu_max("150000 100000\n"), Some(1.5));
+        assert_none!(parse_cpu_max("max 100000\n"));
+    }
+
+    #[test]
+    fn test_runtime_context_layer() {
+        let context = RuntimeContext {
+            cpus: 8,
+            memory_bytes: Some(1024),
+            cpu_limit: Some(2.0),
+            memory_limit_bytes: None,
+        };
+        let layer = LayerSource::new(Layer::Config, "runtime context", context);
+        let actual: Vec<_> = assert_ok!(layer.collect_flattened())
+            .into_iter()
+            .map(|(key, value)| (key, value.to_string()))
+            .collect();
+        assert_eq!(
+            actual,
+            vec![
+                ("runtime.system.cpu_limit".to_string(), "2".to_string()),
+                ("runtime.system.cpus".to_string(), "8".to_string()),
+                ("runtime.system.memory_bytes".to_string(), "1024".to_string()),
+            ]
+        );
+        assert_ok!(RuntimeContext::detect().collect());
+    }
+}
@@ -0,0 +1,139 @@
+use config::{Config, ConfigError, Map, Source, Value, ValueKind};
+use serde::de::DeserializeOwned;
+
+use crate::layer::LayerSource;
+use crate::SettingsError;
+
+/// A view of one settings namespace, e.g., `plugins.my_plugin`, drawn from a host application's
+/// layers; created by `LoadedConfig::scoped_view()`.
+///
+/// Keys outside the namespace are not visible, and keys within it are relative to it. Each layer
+/// keeps its description, so provenance reported for the view matches the host's files, and
+/// settings loaded from the view are validated independently of the host's settings.
+#[derive(Debug, Clone)]
+pub struct ScopedView {
+    scope: String,
+    layers: Vec<LayerSource>,
+}
+
+impl ScopedView {
+    pub fn new(scope: impl Into<String>, layers: &[LayerSource]) -> Self {
+        let scope = scope.into();
+        let layers = layers
+            .iter()
+            .map(|layer| {
+                let source = ScopedSource { scope: scope.clone(), inner: layer.clone() };
+                layer.clone().with_source(source)
+            })
+            .collect();
+        Self { scope, layers }
+    }
+
+    pub const fn scope(&self) -> &str {
+        self.scope.as_str()
+    }
+
+    /// The namespace's settings supplied by each of the host's layers, lowest precedence first.
+    pub const fn layers(&self) -> &[LayerSource] {
+        self.layers.as_slice()
+    }
+
+    /// The namespace's merged configuration.
+    pub fn config(&self) -> Result<Config, SettingsError> {
+        let mut builder = Config::builder();
+        for layer in &self.layers {
+            builder = builder.add_source(layer.clone());
+        }
+        Ok(builder.build()?)
+    }
+
+    /// Loads the namespace's settings.
+    pub fn load<T: DeserializeOwned>(&self) -> Result<T, SettingsError> {
+        Ok(self.config()?.try_deserialize()?)
+    }
+}
+
+/// A layer's settings beneath the scope, with keys relative to it.
+#[derive(Debug, Clone)]
+struct ScopedSource {
+    scope: String,
+    inner: LayerSource,
+}
+
+impl Source for ScopedSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut table = self.inner.collect()?;
+        for segment in self.scope.split('.') {
+            table = match table.remove(segment).map(|value| value.kind) {
+                Some(ValueKind::Table(nested)) => nested,
+                _ => return Ok(Map::new()),
+            };
+        }
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::Layer;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct PluginSettings {
+        endpoint: String,
+        retries: u32,
+    }
+
+    #[test]
+    fn test_scoped_view() {
+        let layers = vec![
+            LayerSource::new(
+                Layer::Config,
+                "application.yaml",
+                File::from_str(
+                    "database: { host: localhost }\nplugins: { my_plugin: { endpoint: a.local, retries: 3 } }",
+                    FileFormat::Yaml,
+                ),
+            ),
+            LayerSource::new(
+                Layer::Secrets,
+                "secrets.yaml",
+                File::from_str("plugins: { my_plugin: { endpoint: b.local } }", FileFormat::Yaml),
+            ),
+        ];
+
+        let view = ScopedView::new("plugins.my_plugin", &layers);
+        assert_eq!(view.scope(), "plugins.my_plugin");
+        let actual: PluginSettings = assert_ok!(view.load());
+        assert_eq!(actual, PluginSettings { endpoint: "b.local".to_string(), retries: 3 });
+
+        let provenance: Vec<_> = view
+            .layers()
+            .iter()
+            .map(|l| {
+                (
+                    l.origin(),
+                    assert_ok!(l.collect_flattened()).into_keys().collect::<Vec<_>>(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            provenance,
+            vec![
+                ("application.yaml", vec!["endpoint".to_string(), "retries".to_string()]),
+                ("secrets.yaml", vec!["endpoint".to_string()]),
+            ]
+        );
+
+        assert_err!(ScopedView::new("plugins.other", &layers).load::<PluginSettings>());
+    }
+}
@@ -0,0 +1,206 @@
+use std::fmt;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use aws_credential_types::Credentials;
+use aws_sigv4::http_request::{sign, SignableBody, SignableRequest, SigningSettings};
+use aws_sigv4::sign::v4;
+use aws_smithy_runtime_api::client::identity::Identity;
+use config::{Map, Value};
+
+use super::{nest_under, parse_json_secrets, SecretsProvider};
+use crate::SettingsError;
+
+const SERVICE_NAME: &str = "secretsmanager";
+const CONTENT_TYPE: &str = "application/x-amz-json-1.1";
+const GET_SECRET_VALUE_TARGET: &str = "secretsmanager.GetSecretValue";
+
+/// Reads a secret from AWS Secrets Manager, whose secret string is a JSON object holding the
+/// secret settings.
+#[derive(Debug, Clone)]
+pub struct AwsSecretsManagerProvider {
+    secret_id: String,
+    region: String,
+    credentials: Credentials,
+    endpoint: Option<String>,
+    key_prefix: Option<String>,
+    client: reqwest::Client,
+}
+
+impl AwsSecretsManagerProvider {
+    pub fn new(secret_id: impl Into<String>, region: impl Into<String>, credentials: Credentials) -> Self {
+        Self {
+            secret_id: secret_id.into(),
+            region: region.into(),
+            credentials,
+            endpoint: None,
+            key_prefix: None,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Creates a provider for the secret using the region and credentials from the standard
+    /// `AWS_REGION` (or `AWS_DEFAULT_REGION`), `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and
+    /// optional `AWS_SESSION_TOKEN` environment variables.
+    pub fn from_env(secret_id: impl Into<String>) -> Result<Self, SettingsError> {
+        let region = std::env::var("AWS_REGION").or_else(|_| std::env::var("AWS_DEFAULT_REGION"))?;
+        let credentials = Credentials::new(
+            std::env::var("AWS_ACCESS_KEY_ID")?,
+            std::env::var("AWS_SECRET_ACCESS_KEY")?,
+            std::env::var("AWS_SESSION_TOKEN").ok(),
+            None,
+            "environment",
+        );
+        Ok(Self::new(secret_id, region, credentials))
+    }
+
+    /// Overrides the regional Secrets Manager endpoint; e.g., for a VPC endpoint or LocalStack.
+    pub fn with_endpoint(self, endpoint: impl Into<String>) -> Self {
+        Self { endpoint: Some(endpoint.into()), ..self }
+    }
+
+    /// Nests the secret's settings beneath the dotted key prefix; e.g., `database`.
+    pub fn with_key_prefix(self, key_prefix: impl Into<String>) -> Self {
+        Self { key_prefix: Some(key_prefix.into()), ..self }
+    }
+
+    fn endpoint(&self) -> String {
+        self.endpoint
+            .clone()
+            .unwrap_or_else(|| format!("https://{SERVICE_NAME}.{}.amazonaws.com/", self.region))
+    }
+
+    fn error(&self, message: impl fmt::Display) -> SettingsError {
+        SettingsError::SecretsProvider {
+            provider: self.name(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Signs the `GetSecretValue` request with AWS Signature Version 4, returning the headers to
+    /// add to the request.
+    fn signing_headers(
+        &self, url: &str, body: &[u8], time: SystemTime,
+    ) -> Result<Vec<(String, String)>, SettingsError> {
+        let identity: Identity = self.credentials.clone().into();
+        let params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(&self.region)
+            .name(SERVICE_NAME)
+            .time(time)
+            .settings(SigningSettings::default())
+            .build()
+            .map_err(|err| self.error(err))?
+            .into();
+
+        let headers = [
+            ("content-type", CONTENT_TYPE),
+            ("x-amz-target", GET_SECRET_VALUE_TARGET),
+        ];
+        let request = SignableRequest::new("POST", url, headers.into_iter(), SignableBody::Bytes(body))
+            .map_err(|err| self.error(err))?;
+        let (instructions, _signature) = sign(request, &params).map_err(|err| self.error(err))?.into_parts();
+
+        Ok(instructions
+            .headers()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect())
+    }
+
+    fn secret_values(&self, response: &serde_json::Value) -> Result<Map<String, Value>, SettingsError> {
+        let secret_string = response
+            .get("SecretString")
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(|| self.error("response does not hold a secret string"))?;
+        let values = parse_json_secrets(&self.name(), secret_string)?;
+        Ok(nest_under(self.key_prefix.as_deref(), values))
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for AwsSecretsManagerProvider {
+    fn name(&self) -> String {
+        format!("aws secrets manager {}", self.secret_id)
+    }
+
+    async fn fetch(&self) -> Result<Map<String, Value>, SettingsError> {
+        let url = self.endpoint();
+        let body = serde_json::json!({ "SecretId": self.secret_id }).to_string();
+
+        let mut request = self
+            .client
+            .post(&url)
+            .header("content-type", CONTENT_TYPE)
+            .header("x-amz-target", GET_SECRET_VALUE_TARGET);
+        for (name, value) in self.signing_headers(&url, body.as_bytes(), SystemTime::now())? {
+            request = request.header(name, value);
+        }
+
+        let response: serde_json::Value = request
+            .body(body)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|err| self.error(err))?
+            .json()
+            .await
+            .map_err(|err| self.error(err))?;
+
+        self.secret_values(&response)
+    }
+
+    fn scheme(&self) -> Option<&str> {
+        Some("aws")
+    }
+
+    /// Fetches the secret with the path as its id, using this provider's region and credentials.
+    async fn fetch_path(&self, path: &str) -> Result<Map<String, Value>, SettingsError> {
+        let referenced = Self {
+            secret_id: path.to_string(),
+            key_prefix: None,
+            ..self.clone()
+        };
+        referenced.fetch().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn provider() -> AwsSecretsManagerProvider {
+        let credentials = Credentials::new("AKIDEXAMPLE", "secret", None, None, "test");
+        AwsSecretsManagerProvider::new("prod/app", "us-east-1", credentials)
+    }
+
+    #[test]
+    fn test_aws_signing_headers() {
+        let provider = provider();
+        let url = provider.endpoint();
+        assert_eq!(url, "https://secretsmanager.us-east-1.amazonaws.com/");
+
+        let headers = assert_ok!(provider.signing_headers(&url, br#"{"SecretId":"prod/app"}"#, SystemTime::UNIX_EPOCH));
+        let authorization = assert_some!(headers.iter().find(|(name, _)| name == "authorization"));
+        assert!(authorization
+            .1
+            .starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/19700101/us-east-1/secretsmanager/aws4_request"));
+        assert!(authorization.1.contains("x-amz-target"));
+    }
+
+    #[test]
+    fn test_aws_secret_values() {
+        let provider = provider().with_key_prefix("database");
+        let response = serde_json::json!({ "Name": "prod/app", "SecretString": r#"{"password":"hunter2"}"# });
+        let actual = assert_ok!(provider.secret_values(&response));
+        let database = assert_ok!(assert_some!(actual.get("database")).clone().into_table());
+        assert_eq!(
+            assert_ok!(assert_some!(database.get("password")).clone().into_string()),
+            "hunter2"
+        );
+
+        assert_err!(provider.secret_values(&serde_json::json!({ "SecretBinary": "AAAA" })));
+    }
+}
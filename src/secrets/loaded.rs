@@ -0,0 +1,261 @@
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use serde::de::DeserializeOwned;
+
+use super::materialize::{MaterializeOptions, MaterializedSecret};
+use super::{fetch_secrets, insert_secrets_layers, resolve_secret_refs, SecretsProvider};
+use crate::layer::LayerSource;
+use crate::scope::ScopedView;
+use crate::{LoadingOptions, SettingsError, SettingsLoader};
+
+/// Settings loaded with provider-backed secrets, which can be refreshed without re-reading the
+/// settings files.
+///
+/// The file layers read at load are retained. A refresh fetches the providers' secrets and resolves
+/// secret references again, so secret rotation is decoupled from full configuration reloads.
+/// Fetched secrets are cached for `LoadingOptions::secrets_ttl()`, after which
+/// `refresh_expired_secrets()` refreshes them.
+#[derive(Debug)]
+pub struct LoadedConfig<S: SettingsLoader> {
+    options: S::Options,
+    file_layers: Vec<LayerSource>,
+    layers: Vec<LayerSource>,
+    providers: Vec<Arc<dyn SecretsProvider>>,
+    secrets_refreshed_at: SystemTime,
+    settings: S,
+}
+
+impl<S> LoadedConfig<S>
+where
+    S: SettingsLoader + DeserializeOwned + Send,
+    S::Options: Send + Sync,
+{
+    #[tracing::instrument(level = "info")]
+    pub async fn load(options: S::Options) -> Result<Self, SettingsError> {
+        let file_layers = S::load_layers(&options)?;
+        let providers = options.secrets_providers();
+        let (settings, layers) = Self::resolve(&options, &file_layers, &providers).await?;
+        let secrets_refreshed_at = options.clock().now();
+        Ok(Self {
+            options,
+            file_layers,
+            layers,
+            providers,
+            secrets_refreshed_at,
+            settings,
+        })
+    }
+
+    /// Fetches the secrets from the providers and resolves secret references again, rebuilding the
+    /// settings from the retained file layers.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn refresh_secrets(&mut self) -> Result<&S, SettingsError> {
+        let (settings, layers) = Self::resolve(&self.options, &self.file_layers, &self.providers).await?;
+        self.settings = settings;
+        self.layers = layers;
+        self.secrets_refreshed_at = self.options.clock().now();
+        Ok(&self.settings)
+    }
+
+    /// Refreshes the secrets if they have outlived their time-to-live, returning the current
+    /// settings.
+    pub async fn refresh_expired_secrets(&mut self) -> Result<&S, SettingsError> {
+        if self.secrets_expired() {
+            tracing::info!(refreshed_at=?self.secrets_refreshed_at, "cached secrets expired");
+            self.refresh_secrets().await
+        } else {
+            Ok(&self.settings)
+        }
+    }
+
+    async fn resolve(
+        options: &S::Options, file_layers: &[LayerSource], providers: &[Arc<dyn SecretsProvider>],
+    ) -> Result<(S, Vec<LayerSource>), SettingsError> {
+        let mut layers = file_layers.to_vec();
+        insert_secrets_layers(options, &mut layers, fetch_secrets(providers).await?);
+        let layers = resolve_secret_refs(layers, providers).await?;
+        Ok((S::load_from_layers(options, layers.clone())?, layers))
+    }
+}
+
+impl<S: SettingsLoader> LoadedConfig<S> {
+    pub const fn settings(&self) -> &S {
+        &self.settings
+    }
+
+    pub fn into_settings(self) -> S {
+        self.settings
+    }
+
+    /// A view of the settings namespace, e.g., `plugins.my_plugin`, which an embedded library can
+    /// load its own settings from without seeing unrelated keys. CLI option overrides are not
+    /// included in the view.
+    pub fn scoped_view(&self, scope: impl Into<String>) -> ScopedView {
+        ScopedView::new(scope, &self.layers)
+    }
+
+    /// Writes the value of the setting at the key, e.g., `kafka.ssl.key`, to a file with owner-only
+    /// permissions, for libraries that read secrets from file paths. The file is removed when the
+    /// returned `MaterializedSecret` is dropped.
+    pub fn materialize_secret(
+        &self, key: &str, materialize: &MaterializeOptions,
+    ) -> Result<MaterializedSecret, SettingsError> {
+        let config = S::build_config(&self.options, self.layers.clone())?;
+        let secret: String = config.get(key)?;
+        MaterializedSecret::write(key, &secret, materialize)
+    }
+
+    pub const fn secrets_refreshed_at(&self) -> SystemTime {
+        self.secrets_refreshed_at
+    }
+
+    /// Whether the cached secrets have outlived `LoadingOptions::secrets_ttl()`.
+    pub fn secrets_expired(&self) -> bool {
+        self.options.secrets_ttl().is_some_and(|ttl| {
+            let expires_at = self.secrets_refreshed_at + ttl;
+            expires_at <= self.options.clock().now()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use claim::*;
+    use config::{Map, Value};
+    use pretty_assertions::assert_eq;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::fs::MemoryFs;
+    use crate::secrets::parse_json_secrets;
+    use crate::{Clock, ConfigFs};
+
+    #[derive(Debug, Default)]
+    struct RotatingProvider(AtomicUsize);
+
+    #[async_trait]
+    impl SecretsProvider for RotatingProvider {
+        fn name(&self) -> String {
+            "rotating".to_string()
+        }
+
+        fn scheme(&self) -> Option<&str> {
+            Some("rotating")
+        }
+
+        async fn fetch(&self) -> Result<Map<String, Value>, SettingsError> {
+            Ok(Map::new())
+        }
+
+        async fn fetch_path(&self, path: &str) -> Result<Map<String, Value>, SettingsError> {
+            let version = self.0.fetch_add(1, Ordering::SeqCst);
+            parse_json_secrets(path, &format!(r#"{{"password": "v{version}"}}"#))
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct ManualClock(Mutex<Duration>);
+
+    impl Clock for ManualClock {
+        fn now(&self) -> SystemTime {
+            SystemTime::UNIX_EPOCH + *self.0.lock().unwrap()
+        }
+    }
+
+    #[derive(Debug)]
+    struct TestOptions {
+        fs: Arc<MemoryFs>,
+        provider: Arc<RotatingProvider>,
+        clock: Arc<ManualClock>,
+    }
+
+    impl LoadingOptions for TestOptions {
+        type Error = SettingsError;
+
+        fn config_path(&self) -> Option<PathBuf> {
+            Some(PathBuf::from("loaded/application.yaml"))
+        }
+
+        fn secrets_path(&self) -> Option<PathBuf> {
+            None
+        }
+
+        fn implicit_search_paths(&self) -> Vec<PathBuf> {
+            Vec::default()
+        }
+
+        fn clock(&self) -> Arc<dyn Clock> {
+            self.clock.clone()
+        }
+
+        fn config_fs(&self) -> Arc<dyn ConfigFs> {
+            self.fs.clone()
+        }
+
+        fn secrets_providers(&self) -> Vec<Arc<dyn SecretsProvider>> {
+            vec![self.provider.clone()]
+        }
+
+        fn secrets_ttl(&self) -> Option<Duration> {
+            Some(Duration::from_secs(60))
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestSettings {
+        host: String,
+        password: String,
+    }
+
+    impl SettingsLoader for TestSettings {
+        type Options = TestOptions;
+
+        fn environment_prefix() -> &'static str {
+            "loaded_test"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_secrets() {
+        let fs = MemoryFs::new().with_file(
+            "loaded/application.yaml",
+            "host: localhost\npassword: { secret_ref: 'rotating:app/db#password' }",
+        );
+        let clock = Arc::new(ManualClock::default());
+        let options = TestOptions {
+            fs: Arc::new(fs),
+            provider: Arc::new(RotatingProvider::default()),
+            clock: clock.clone(),
+        };
+
+        let mut loaded = assert_ok!(LoadedConfig::<TestSettings>::load(options).await);
+        assert_eq!(loaded.settings().password, "v0");
+        assert!(!loaded.secrets_expired());
+
+        *clock.0.lock().unwrap() = Duration::from_secs(30);
+        assert_eq!(assert_ok!(loaded.refresh_expired_secrets().await).password, "v0");
+
+        *clock.0.lock().unwrap() = Duration::from_secs(60);
+        assert!(loaded.secrets_expired());
+        assert_eq!(assert_ok!(loaded.refresh_expired_secrets().await).password, "v1");
+        assert_eq!(
+            loaded.secrets_refreshed_at(),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(60)
+        );
+
+        assert_eq!(assert_ok!(loaded.refresh_secrets().await).password, "v2");
+        let materialize = MaterializeOptions::new().in_dir(std::env::temp_dir());
+        let secret = assert_ok!(loaded.materialize_secret("password", &materialize));
+        assert_eq!(assert_ok!(std::fs::read_to_string(secret.path())), "v2");
+        assert_err!(loaded.materialize_secret("missing", &materialize));
+        drop(secret);
+        assert_eq!(loaded.into_settings().host, "localhost");
+    }
+}
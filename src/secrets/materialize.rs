@@ -0,0 +1,148 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::SettingsError;
+
+/// Directory backed by memory on Linux, preferred so materialized secrets never reach disk.
+const SHARED_MEMORY_DIR: &str = "/dev/shm";
+
+/// Default permissions of materialized secret files: readable and writable by the owner only.
+const DEFAULT_MODE: u32 = 0o600;
+
+static MATERIALIZED: AtomicUsize = AtomicUsize::new(0);
+
+/// How a secret is written to a file by `LoadedConfig::materialize_secret()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaterializeOptions {
+    dir: Option<PathBuf>,
+    mode: u32,
+}
+
+impl Default for MaterializeOptions {
+    fn default() -> Self {
+        Self { dir: None, mode: DEFAULT_MODE }
+    }
+}
+
+impl MaterializeOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the file in the directory rather than `/dev/shm`, or the temporary directory where
+    /// `/dev/shm` is unavailable.
+    pub fn in_dir(self, dir: impl Into<PathBuf>) -> Self {
+        Self { dir: Some(dir.into()), ..self }
+    }
+
+    /// Sets the file's Unix permissions; `0o600` by default. Ignored on other platforms.
+    pub const fn with_mode(mut self, mode: u32) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn dir(&self) -> PathBuf {
+        self.dir.clone().unwrap_or_else(|| {
+            let shared_memory = Path::new(SHARED_MEMORY_DIR);
+            if shared_memory.is_dir() {
+                shared_memory.to_path_buf()
+            } else {
+                std::env::temp_dir()
+            }
+        })
+    }
+
+    pub const fn mode(&self) -> u32 {
+        self.mode
+    }
+}
+
+/// A secret written to a file, for libraries that take secrets as file paths (e.g., TLS keys).
+///
+/// The file is overwritten and removed when this is dropped, so keep it alive for as long as the
+/// library reads the path.
+#[derive(Debug)]
+pub struct MaterializedSecret {
+    path: PathBuf,
+    len: usize,
+}
+
+impl MaterializedSecret {
+    /// Writes the secret for the setting at the key to a new file.
+    pub fn write(key: &str, secret: &str, options: &MaterializeOptions) -> Result<Self, SettingsError> {
+        let name: String = key
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let sequence = MATERIALIZED.fetch_add(1, Ordering::SeqCst);
+        let path = options
+            .dir()
+            .join(format!("settings-secret-{name}-{}-{sequence}", std::process::id()));
+
+        let mut open = OpenOptions::new();
+        open.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut open, options.mode);
+        let mut file = open.open(&path)?;
+        let materialized = Self { path, len: secret.len() };
+        file.write_all(secret.as_bytes())?;
+        file.sync_all()?;
+        tracing::info!(%key, path=?materialized.path, "materialized secret to file");
+        Ok(materialized)
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+}
+
+impl Drop for MaterializedSecret {
+    fn drop(&mut self) {
+        let _ignored = fs::write(&self.path, vec![0_u8; self.len]);
+        if let Err(err) = fs::remove_file(&self.path) {
+            tracing::warn!(error=%err, path=?self.path, "failed to remove materialized secret file");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_materialized_secret_is_removed_on_drop() {
+        let dir = std::env::temp_dir().join(format!("settings-materialize-{}", std::process::id()));
+        assert_ok!(fs::create_dir_all(&dir));
+        let options = MaterializeOptions::new().in_dir(&dir);
+
+        let secret = assert_ok!(MaterializedSecret::write(
+            "kafka.ssl.key",
+            "-----BEGIN KEY-----",
+            &options
+        ));
+        let path = secret.path().to_path_buf();
+        assert!(assert_some!(path.file_name())
+            .to_string_lossy()
+            .starts_with("settings-secret-kafka_ssl_key-"));
+        assert_eq!(assert_ok!(fs::read_to_string(&path)), "-----BEGIN KEY-----");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = assert_ok!(fs::metadata(&path)).permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let other = assert_ok!(MaterializedSecret::write("kafka.ssl.key", "another", &options));
+        assert_ne!(other.path(), path);
+
+        drop(secret);
+        assert!(!path.exists());
+        drop(other);
+        let _ignored = fs::remove_dir_all(&dir);
+    }
+}
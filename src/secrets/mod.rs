@@ -0,0 +1,204 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use config::{ConfigError, FileFormat, Format, Map, Source, Value, ValueKind};
+pub use loaded::LoadedConfig;
+pub use materialize::{MaterializeOptions, MaterializedSecret};
+pub use reference::{resolve_secret_refs, SecretRef, SECRET_REF_KEY};
+use serde::de::DeserializeOwned;
+
+use crate::layer::LayerSource;
+use crate::{Layer, LoadingOptions, SettingsError, SettingsLoader};
+
+#[cfg(feature = "aws-secrets")]
+pub mod aws;
+mod loaded;
+pub mod materialize;
+mod reference;
+#[cfg(feature = "vault")]
+pub mod vault;
+
+/// Fetches secret settings from an external secrets store rather than a local secrets file; e.g.,
+/// HashiCorp Vault (`vault` feature) or AWS Secrets Manager (`aws-secrets` feature).
+///
+/// Providers are returned by `LoadingOptions::secrets_providers()` and consulted by
+/// `load_with_providers()`. Their values are merged into the secrets layer after the secrets file,
+/// so they follow the same precedence rules: they override configuration files and are overridden
+/// by environment variables and CLI options.
+#[async_trait]
+pub trait SecretsProvider: Debug + Send + Sync {
+    /// Describes the provider and the secret it reads, which is used as the origin of its layer.
+    fn name(&self) -> String;
+
+    /// Fetches the secret settings as a table, nested the same as in a secrets file.
+    async fn fetch(&self) -> Result<Map<String, Value>, SettingsError>;
+
+    /// Scheme naming the provider in secret references (see `SecretRef`); e.g., `vault`. Providers
+    /// without a scheme do not resolve references.
+    fn scheme(&self) -> Option<&str> {
+        None
+    }
+
+    /// Fetches the secret at the path given by a secret reference.
+    async fn fetch_path(&self, path: &str) -> Result<Map<String, Value>, SettingsError> {
+        Err(SettingsError::SecretsProvider {
+            provider: self.name(),
+            message: format!("secret references are not supported; cannot fetch {path}"),
+        })
+    }
+}
+
+/// Secret settings fetched from a provider, used as a configuration source for the secrets layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProvidedSecrets {
+    provider: String,
+    values: Map<String, Value>,
+}
+
+impl ProvidedSecrets {
+    pub fn new(provider: impl Into<String>, values: Map<String, Value>) -> Self {
+        Self { provider: provider.into(), values }
+    }
+
+    pub const fn provider(&self) -> &str {
+        self.provider.as_str()
+    }
+
+    pub const fn values(&self) -> &Map<String, Value> {
+        &self.values
+    }
+}
+
+impl Source for ProvidedSecrets {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        Ok(self.values.clone())
+    }
+}
+
+/// Fetches secrets from each provider in turn. Providers later in the list take precedence over
+/// earlier ones when they supply the same key.
+pub async fn fetch_secrets(providers: &[Arc<dyn SecretsProvider>]) -> Result<Vec<ProvidedSecrets>, SettingsError> {
+    let mut secrets = Vec::with_capacity(providers.len());
+    for provider in providers {
+        let name = provider.name();
+        tracing::info!(provider=%name, "fetching secrets from provider");
+        let values = provider.fetch().await?;
+        secrets.push(ProvidedSecrets::new(name, values));
+    }
+    Ok(secrets)
+}
+
+/// Adds layers for the fetched secrets to the secrets layer, after any secrets file.
+pub(crate) fn insert_secrets_layers<O: LoadingOptions>(
+    options: &O, layers: &mut Vec<LayerSource>, secrets: Vec<ProvidedSecrets>,
+) {
+    let clock = options.clock();
+    let restrictions = Arc::new(options.key_restrictions());
+    let profile = options.precedence_profile();
+    let secrets_rank = profile.rank(Layer::Secrets);
+    let at = layers
+        .iter()
+        .position(|l| secrets_rank < profile.rank(l.layer()))
+        .unwrap_or(layers.len());
+    let secrets_layers = secrets.into_iter().map(|secrets| {
+        LayerSource::restricted(Layer::Secrets, secrets.provider().to_string(), &restrictions, secrets)
+            .with_read_at(clock.now())
+    });
+    layers.splice(at..at, secrets_layers);
+}
+
+/// Load settings as `SettingsLoader::load()` does, additionally merging the secrets fetched from
+/// the options' secrets providers into the secrets layer and resolving secret references.
+#[tracing::instrument(level = "info")]
+pub async fn load_with_providers<S>(options: &S::Options) -> Result<S, SettingsError>
+where
+    S: SettingsLoader + DeserializeOwned,
+    S::Options: Sync,
+{
+    let providers = options.secrets_providers();
+    let secrets = fetch_secrets(&providers).await?;
+    let layers = resolve_secret_refs(S::layers_with_secrets(options, secrets)?, &providers).await?;
+    S::load_from_layers(options, layers)
+}
+
+/// Parses a JSON object holding secret settings, such as a Vault secret's data or an AWS Secrets
+/// Manager secret string.
+#[cfg_attr(not(any(feature = "vault", feature = "aws-secrets")), allow(dead_code))]
+pub(crate) fn parse_json_secrets(provider: &str, json: &str) -> Result<Map<String, Value>, SettingsError> {
+    FileFormat::Json
+        .parse(Some(&provider.to_string()), json)
+        .map_err(|err| SettingsError::SecretsProvider {
+            provider: provider.to_string(),
+            message: format!("secret is not a JSON object of settings: {err}"),
+        })
+}
+
+/// Nests the secret settings beneath the dotted key prefix, if given; e.g., so a secret holding
+/// `password` may supply `database.password`.
+#[cfg_attr(not(any(feature = "vault", feature = "aws-secrets")), allow(dead_code))]
+pub(crate) fn nest_under(prefix: Option<&str>, values: Map<String, Value>) -> Map<String, Value> {
+    let Some(prefix) = prefix else {
+        return values;
+    };
+
+    prefix.rsplit('.').fold(values, |nested, segment| {
+        let mut table = Map::new();
+        table.insert(segment.to_string(), Value::new(None, ValueKind::Table(nested)));
+        table
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct StaticProvider(&'static str, &'static str);
+
+    #[async_trait]
+    impl SecretsProvider for StaticProvider {
+        fn name(&self) -> String {
+            self.0.to_string()
+        }
+
+        async fn fetch(&self) -> Result<Map<String, Value>, SettingsError> {
+            parse_json_secrets(self.0, self.1)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_secrets() {
+        let providers: Vec<Arc<dyn SecretsProvider>> = vec![
+            Arc::new(StaticProvider("first", r#"{"database": {"password": "one"}}"#)),
+            Arc::new(StaticProvider("second", r#"{"api_key": "two"}"#)),
+        ];
+
+        let actual = assert_ok!(fetch_secrets(&providers).await);
+        let names: Vec<_> = actual.iter().map(|s| s.provider()).collect();
+        assert_eq!(names, vec!["first", "second"]);
+        assert_some!(actual[0].values().get("database"));
+
+        let bad: Vec<Arc<dyn SecretsProvider>> = vec![Arc::new(StaticProvider("bad", "[1, 2]"))];
+        assert_err!(fetch_secrets(&bad).await);
+    }
+
+    #[test]
+    fn test_nest_under() {
+        let values = assert_ok!(parse_json_secrets("test", r#"{"password": "hunter2"}"#));
+        let nested = nest_under(Some("app.database"), values);
+        let app = assert_ok!(assert_some!(nested.get("app")).clone().into_table());
+        let database = assert_ok!(assert_some!(app.get("database")).clone().into_table());
+        assert_eq!(
+            assert_ok!(assert_some!(database.get("password")).clone().into_string()),
+            "hunter2"
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use config::{Map, Source, Value, ValueKind};
+
+use super::SecretsProvider;
+use crate::layer::{LayerSource, TableSource};
+use crate::SettingsError;
+
+/// Key of the single-entry table that references a secret in place of its value; e.g.,
+/// `password = { secret_ref = "vault:kv/app#db_password" }`.
+pub const SECRET_REF_KEY: &str = "secret_ref";
+
+/// A reference to a secret held by a secrets provider, written as `<scheme>:<path>#<key>`.
+///
+/// The scheme selects the provider (see `SecretsProvider::scheme()`), the path identifies the
+/// secret within it, and the optional key selects a (dotted) setting within the secret.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SecretRef {
+    scheme: String,
+    path: String,
+    key: Option<String>,
+}
+
+impl SecretRef {
+    pub const fn scheme(&self) -> &str {
+        self.scheme.as_str()
+    }
+
+    pub const fn path(&self) -> &str {
+        self.path.as_str()
+    }
+
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+}
+
+impl fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.scheme, self.path)?;
+        if let Some(ref key) = self.key {
+            write!(f, "#{key}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SecretRef {
+    type Err = SettingsError;
+
+    fn from_str(rep: &str) -> Result<Self, Self::Err> {
+        let invalid = || SettingsError::Bootstrap {
+            message: "secret reference must be of the form <scheme>:<path>#<key>".to_string(),
+            setting: rep.to_string(),
+        };
+
+        let (scheme, rest) = rep.split_once(':').ok_or_else(invalid)?;
+        let (path, key) = match rest.split_once('#') {
+            Some((path, key)) => (path, Some(key.to_string())),
+            None => (rest, None),
+        };
+        if scheme.is_empty() || path.is_empty() || key.as_deref() == Some("") {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            scheme: scheme.to_string(),
+            path: path.to_string(),
+            key,
+        })
+    }
+}
+
+/// Resolves the secret references found in the layers using the providers, matched by scheme.
+///
+/// Each resolved value records the reference as its origin, so the secret value itself never
+/// appears in provenance. Layers without references are returned unchanged.
+pub async fn resolve_secret_refs(
+    layers: Vec<LayerSource>, providers: &[Arc<dyn SecretsProvider>],
+) -> Result<Vec<LayerSource>, SettingsError> {
+    let mut fetched: HashMap<(String, String), Map<String, Value>> = HashMap::new();
+    let mut resolved = Vec::with_capacity(layers.len());
+
+    for layer in layers {
+        let mut table = layer.collect()?;
+        let mut refs = Vec::new();
+        find_refs(&table, &mut refs)?;
+        if refs.is_empty() {
+            resolved.push(layer);
+            continue;
+        }
+
+        for secret_ref in &refs {
+            let cache_key = (secret_ref.scheme().to_string(), secret_ref.path().to_string());
+            if !fetched.contains_key(&cache_key) {
+                let provider = providers
+                    .iter()
+                    .find(|p| p.scheme() == Some(secret_ref.scheme()))
+                    .ok_or_else(|| SettingsError::SecretsProvider {
+                        provider: secret_ref.scheme().to_string(),
+                        message: format!("no secrets provider configured to resolve {secret_ref}"),
+                    })?;
+                tracing::info!(%secret_ref, provider=%provider.name(), "resolving secret reference");
+                fetched.insert(cache_key.clone(), provider.fetch_path(secret_ref.path()).await?);
+            }
+        }
+
+        replace_refs(&mut table, &fetched)?;
+        resolved.push(layer.with_source(TableSource(table)));
+    }
+
+    Ok(resolved)
+}
+
+fn as_secret_ref(value: &Value) -> Result<Option<SecretRef>, SettingsError> {
+    match value.kind {
+        ValueKind::Table(ref table) if table.len() == 1 => match table.get(SECRET_REF_KEY).map(|v| &v.kind) {
+            Some(ValueKind::String(rep)) => rep.parse().map(Some),
+            _ => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
+fn find_refs(table: &Map<String, Value>, refs: &mut Vec<SecretRef>) -> Result<(), SettingsError> {
+    for value in table.values() {
+        if let Some(secret_ref) = as_secret_ref(value)? {
+            refs.push(secret_ref);
+        } else if let ValueKind::Table(ref nested) = value.kind {
+            find_refs(nested, refs)?;
+        }
+    }
+    Ok(())
+}
+
+fn replace_refs(
+    table: &mut Map<String, Value>, fetched: &HashMap<(String, String), Map<String, Value>>,
+) -> Result<(), SettingsError> {
+    for value in table.values_mut() {
+        if let Some(secret_ref) = as_secret_ref(value)? {
+            let secret = &fetched[&(secret_ref.scheme().to_string(), secret_ref.path().to_string())];
+            let kind = match secret_ref.key() {
+                Some(key) => lookup(secret, key)
+                    .ok_or_else(|| SettingsError::SecretsProvider {
+                        provider: secret_ref.scheme().to_string(),
+                        message: format!("secret does not hold the key referenced by {secret_ref}"),
+                    })?
+                    .kind
+                    .clone(),
+                None => ValueKind::Table(secret.clone()),
+            };
+            *value = Value::new(Some(&secret_ref.to_string()), kind);
+        } else if let ValueKind::Table(ref mut nested) = value.kind {
+            replace_refs(nested, fetched)?;
+        }
+    }
+    Ok(())
+}
+
+fn lookup<'v>(table: &'v Map<String, Value>, key: &str) -> Option<&'v Value> {
+    let mut segments = key.split('.');
+    let first = table.get(segments.next()?)?;
+    segments.try_fold(first, |value, segment| match value.kind {
+        ValueKind::Table(ref nested) => nested.get(segment),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use claim::*;
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::secrets::parse_json_secrets;
+    use crate::Layer;
+
+    #[derive(Debug)]
+    struct StaticProvider;
+
+    #[async_trait]
+    impl SecretsProvider for StaticProvider {
+        fn name(&self) -> String {
+            "static".to_string()
+        }
+
+        fn scheme(&self) -> Option<&str> {
+            Some("static")
+        }
+
+        async fn fetch(&self) -> Result<Map<String, Value>, SettingsError> {
+            Ok(Map::new())
+        }
+
+        async fn fetch_path(&self, path: &str) -> Result<Map<String, Value>, SettingsError> {
+            assert_eq!(path, "kv/app");
+            parse_json_secrets(path, r#"{"db_password": "hunter2", "api": {"key": "abc"}}"#)
+        }
+    }
+
+    #[test]
+    fn test_parse_secret_ref() {
+        let actual: SecretRef = assert_ok!("vault:kv/app#db_password".parse());
+        assert_eq!(actual.scheme(), "vault");
+        assert_eq!(actual.path(), "kv/app");
+        assert_eq!(actual.key(), Some("db_password"));
+        assert_eq!(actual.to_string(), "vault:kv/app#db_password");
+
+        assert_err!("kv/app".parse::<SecretRef>());
+        assert_err!("vault:kv/app#".parse::<SecretRef>());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_secret_refs() {
+        let yaml = "database:\n  password: { secret_ref: 'static:kv/app#db_password' }\napi_key: { secret_ref: \
+                    'static:kv/app#api.key' }\nport: 5432";
+        let layers = vec![LayerSource::new(
+            Layer::Config,
+            "application.yaml",
+            File::from_str(yaml, FileFormat::Yaml),
+        )];
+        let providers: Vec<Arc<dyn SecretsProvider>> = vec![Arc::new(StaticProvider)];
+
+        let resolved = assert_ok!(resolve_secret_refs(layers, &providers).await);
+        assert_eq!(resolved[0].origin(), "application.yaml");
+        let flattened = assert_ok!(resolved[0].collect_flattened());
+        let password = assert_some!(flattened.get("database.password"));
+        assert_eq!(password.to_string(), "hunter2");
+        assert_eq!(password.origin(), Some("static:kv/app#db_password"));
+        assert_eq!(assert_some!(flattened.get("api_key")).to_string(), "abc");
+        assert_eq!(assert_some!(flattened.get("port")).to_string(), "5432");
+
+        let unknown = vec![LayerSource::new(
+            Layer::Config,
+            "application.yaml",
+            File::from_str("password: { secret_ref: 'aws:prod/app#password' }", FileFormat::Yaml),
+        )];
+        assert_err!(resolve_secret_refs(unknown, &providers).await);
+    }
+}
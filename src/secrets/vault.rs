@@ -0,0 +1,173 @@
+use std::fmt;
+
+use async_trait::async_trait;
+use config::{Map, Value};
+
+use super::{nest_under, parse_json_secrets, SecretsProvider};
+use crate::SettingsError;
+
+const VAULT_ADDR: &str = "VAULT_ADDR";
+const VAULT_TOKEN: &str = "VAULT_TOKEN";
+const VAULT_NAMESPACE: &str = "VAULT_NAMESPACE";
+
+/// Reads a secret from a HashiCorp Vault KV version 2 secrets engine, whose data holds the secret
+/// settings.
+#[derive(Clone)]
+pub struct VaultSecretsProvider {
+    address: String,
+    token: String,
+    mount: String,
+    path: String,
+    namespace: Option<String>,
+    key_prefix: Option<String>,
+    client: reqwest::Client,
+}
+
+impl fmt::Debug for VaultSecretsProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VaultSecretsProvider")
+            .field("address", &self.address)
+            .field("token", &"<redacted>")
+            .field("mount", &self.mount)
+            .field("path", &self.path)
+            .field("namespace", &self.namespace)
+            .field("key_prefix", &self.key_prefix)
+            .finish()
+    }
+}
+
+impl VaultSecretsProvider {
+    pub fn new(
+        address: impl Into<String>, token: impl Into<String>, mount: impl Into<String>, path: impl Into<String>,
+    ) -> Self {
+        Self {
+            address: address.into(),
+            token: token.into(),
+            mount: mount.into(),
+            path: path.into(),
+            namespace: None,
+            key_prefix: None,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Creates a provider for the secret using the Vault address, token and (optional) namespace
+    /// from the standard `VAULT_ADDR`, `VAULT_TOKEN` and `VAULT_NAMESPACE` environment variables.
+    pub fn from_env(mount: impl Into<String>, path: impl Into<String>) -> Result<Self, SettingsError> {
+        let provider = Self::new(std::env::var(VAULT_ADDR)?, std::env::var(VAULT_TOKEN)?, mount, path);
+        Ok(match std::env::var(VAULT_NAMESPACE) {
+            Ok(namespace) => provider.with_namespace(namespace),
+            Err(_) => provider,
+        })
+    }
+
+    pub fn with_namespace(self, namespace: impl Into<String>) -> Self {
+        Self { namespace: Some(namespace.into()), ..self }
+    }
+
+    /// Nests the secret's settings beneath the dotted key prefix; e.g., `database`.
+    pub fn with_key_prefix(self, key_prefix: impl Into<String>) -> Self {
+        Self { key_prefix: Some(key_prefix.into()), ..self }
+    }
+
+    fn secret_url(&self) -> String {
+        format!(
+            "{}/v1/{}/data/{}",
+            self.address.trim_end_matches('/'),
+            self.mount.trim_matches('/'),
+            self.path.trim_start_matches('/')
+        )
+    }
+
+    fn error(&self, message: impl fmt::Display) -> SettingsError {
+        SettingsError::SecretsProvider {
+            provider: self.name(),
+            message: message.to_string(),
+        }
+    }
+
+    fn secret_data(&self, response: &serde_json::Value) -> Result<Map<String, Value>, SettingsError> {
+        let data = response
+            .pointer("/data/data")
+            .filter(|data| data.is_object())
+            .ok_or_else(|| self.error("response does not hold KV v2 secret data"))?;
+        let values = parse_json_secrets(&self.name(), &data.to_string())?;
+        Ok(nest_under(self.key_prefix.as_deref(), values))
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for VaultSecretsProvider {
+    fn name(&self) -> String {
+        format!(
+            "vault {}/{}",
+            self.mount.trim_matches('/'),
+            self.path.trim_start_matches('/')
+        )
+    }
+
+    async fn fetch(&self) -> Result<Map<String, Value>, SettingsError> {
+        let mut request = self.client.get(self.secret_url()).header("X-Vault-Token", &self.token);
+        if let Some(ref namespace) = self.namespace {
+            request = request.header("X-Vault-Namespace", namespace);
+        }
+
+        let response: serde_json::Value = request
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|err| self.error(err))?
+            .json()
+            .await
+            .map_err(|err| self.error(err))?;
+
+        self.secret_data(&response)
+    }
+
+    fn scheme(&self) -> Option<&str> {
+        Some("vault")
+    }
+
+    /// Fetches the secret at `<mount>/<path>`, using this provider's Vault address and token.
+    async fn fetch_path(&self, path: &str) -> Result<Map<String, Value>, SettingsError> {
+        let (mount, path) = path
+            .trim_start_matches('/')
+            .split_once('/')
+            .ok_or_else(|| self.error(format!("secret path {path} must be of the form <mount>/<path>")))?;
+        let referenced = Self {
+            mount: mount.to_string(),
+            path: path.to_string(),
+            key_prefix: None,
+            ..self.clone()
+        };
+        referenced.fetch().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_vault_secret_data() {
+        let provider = VaultSecretsProvider::new("http://vault:8200/", "s.token", "/secret/", "app/db")
+            .with_key_prefix("database");
+        assert_eq!(provider.secret_url(), "http://vault:8200/v1/secret/data/app/db");
+        assert!(!format!("{provider:?}").contains("s.token"));
+
+        let response = serde_json::json!({
+            "data": { "data": { "password": "hunter2" }, "metadata": { "version": 3 } }
+        });
+        let actual = assert_ok!(provider.secret_data(&response));
+        let database = assert_ok!(assert_some!(actual.get("database")).clone().into_table());
+        assert_eq!(
+            assert_ok!(assert_some!(database.get("password")).clone().into_string()),
+            "hunter2"
+        );
+
+        assert_err!(provider.secret_data(&serde_json::json!({ "errors": [] })));
+    }
+}
@@ -1,14 +1,43 @@
 use std::fmt::Debug;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
 
 use config::builder::DefaultState;
-use config::ConfigBuilder;
+use config::{ConfigBuilder, Source};
 use path_absolutize::*;
 use serde::de::DeserializeOwned;
-
-use crate::{Environment, LoadingOptions, SettingsError};
-
-type ConfigFile = config::File<config::FileSourceFile, config::FileFormat>;
+use serde::Serialize;
+
+use crate::coerce::coerce_values;
+use crate::conflict::{find_conflicts, find_type_conflict, Conflict, CONFLICT_MIN_LAYERS};
+use crate::decrypt::{contains_encrypted, decrypt_values, encrypted_keys};
+use crate::deprecation::find_deprecated;
+use crate::describe::{insert_missing, LayerDescriptor};
+use crate::env_alias::AliasedEnvironmentSource;
+use crate::export::SettingsExport;
+use crate::fs::{ConfigFile, RealFs};
+use crate::include::expand_includes;
+use crate::inline_env::InlineEnvironmentSource;
+use crate::instance::InstanceOverlay;
+use crate::interpolate::interpolate;
+use crate::layer::{LayerSource, TableSource};
+use crate::legacy::{find_legacy_files, LegacyFile};
+use crate::lenient::{deserialize_lenient, FieldError};
+use crate::lint::LintReport;
+use crate::merge::merge_layer_arrays;
+use crate::migrations::MigratedSource;
+use crate::policy::SkippedLayer;
+use crate::preset::{insert_preset_layer, Preset};
+use crate::provenance::locate_deserialize_error;
+use crate::redact::redacted_config;
+use crate::report::LoadReport;
+use crate::required::check_required;
+use crate::secrets::{insert_secrets_layers, ProvidedSecrets};
+use crate::strict::{describe_unknown_keys, deserialize_tracking_unknown};
+use crate::{ConfigFs, Environment, Layer, LoadingOptions, SettingsError};
+
+type FileSource = config::File<config::FileSourceFile, config::FileFormat>;
 
 pub trait SettingsLoader: Debug + Sized {
     type Options: LoadingOptions + Debug;
@@ -40,51 +69,514 @@ pub trait SettingsLoader: Debug + Sized {
     where
         Self: DeserializeOwned,
     {
+        warn_unconsulted_providers(options);
+        Self::load_from_layers(options, Self::load_layers(options)?)
+    }
+
+    /// Deserializes the merged configuration, rejecting keys the settings type does not define
+    /// when `LoadingOptions::strict()` is enabled. The layers are only consulted to explain a
+    /// failure.
+    fn deserialize_config(
+        options: &Self::Options, config: config::Config, layers: &dyn Fn() -> Vec<LayerSource>,
+    ) -> Result<Self, SettingsError>
+    where
+        Self: DeserializeOwned,
+    {
+        if !options.strict() {
+            return config
+                .clone()
+                .try_deserialize()
+                .map_err(|err| Self::explain_deserialize_error(&config, &layers(), err));
+        }
+
+        let (settings, ignored) = deserialize_tracking_unknown(&config)
+            .map_err(|err| Self::explain_deserialize_error(&config, &layers(), err))?;
+        if ignored.is_empty() {
+            Ok(settings)
+        } else {
+            let unknown = describe_unknown_keys(&config, &layers(), ignored);
+            tracing::error!(?unknown, "settings include keys not defined by the settings type");
+            Err(SettingsError::UnknownKeys(unknown))
+        }
+    }
+
+    /// Checks the deprecated settings the layers supply under the options'
+    /// `LoadingOptions::deprecation_policy()`, before the settings are loaded from them.
+    fn check_deprecations(options: &Self::Options, layers: &[LayerSource]) -> Result<(), SettingsError> {
+        options.deprecation_policy().check(
+            layers,
+            &options.deprecated_keys(),
+            options.application_version().as_deref(),
+        )
+    }
+
+    /// Explains a failure to deserialize the merged settings by the failing setting's value and the
+    /// layer supplying it, or as a conflict between the types the layers supply for that setting if
+    /// there is one.
+    fn explain_deserialize_error(
+        config: &config::Config, layers: &[LayerSource], err: config::ConfigError,
+    ) -> SettingsError
+    where
+        Self: DeserializeOwned,
+    {
+        let Some(located) = locate_deserialize_error::<Self>(config, layers) else {
+            return err.into();
+        };
+
+        if let SettingsError::Deserialization { ref key, .. } = located {
+            let setting_key = key.split('[').next().unwrap_or_default();
+            if let Ok(Some(conflict)) = find_type_conflict(layers, setting_key) {
+                tracing::error!(error=%err, %conflict, "settings layers supply conflicting types");
+                return conflict;
+            }
+        }
+
+        tracing::error!(error=%located, "setting failed to deserialize");
+        located
+    }
+
+    /// Load a single section of the settings, e.g., `"database"`, from the merged layers without
+    /// deserializing the rest. Binaries can then depend on only the section types they use.
+    #[tracing::instrument(level = "info")]
+    fn load_section<T>(options: &Self::Options, section: &str) -> Result<T, SettingsError>
+    where
+        T: DeserializeOwned + Debug,
+    {
+        let config = Self::load_config(options)?;
+        let settings: T = config.get(section)?;
+        tracing::info!(?settings, %section, "settings section built for application.");
+        Ok(settings)
+    }
+
+    /// Load settings leniently, deserializing what can be and substituting defaults for fields
+    /// that fail. The per-field errors are returned alongside the settings, which supports, e.g.,
+    /// a GUI opening a broken configuration for repair rather than refusing to start.
+    #[tracing::instrument(level = "info")]
+    fn load_lenient(options: &Self::Options) -> Result<(Self, Vec<FieldError>), SettingsError>
+    where
+        Self: DeserializeOwned + Default + Serialize,
+    {
+        let config = Self::load_config(options)?;
+        let (settings, errors) = deserialize_lenient(config)?;
+        tracing::info!(?settings, ?errors, "settings leniently built for application.");
+        Ok((settings, errors))
+    }
+
+    /// Load settings along with a report of how they were loaded: the time taken to parse each
+    /// layer, skipped and missing files, the number of settings from environment variables,
+    /// warnings, such as keys the settings type ignores, and which layer supplied how many of the
+    /// effective settings.
+    #[tracing::instrument(level = "info")]
+    fn load_with_report(options: &Self::Options) -> Result<(Self, LoadReport), SettingsError>
+    where
+        Self: DeserializeOwned,
+    {
+        let started = Instant::now();
+        let (layers, skipped) = Self::resolve_layers(options)?;
+        Self::check_deprecations(options, &layers)?;
+        let config = Self::build_config(options, layers.clone())?;
+        let mut report = LoadReport::of_layers(&layers, &config)?;
+
+        let settings = if options.strict() {
+            Self::deserialize_config(options, config, &|| layers.clone())?
+        } else {
+            let (settings, ignored) = deserialize_tracking_unknown(&config)
+                .map_err(|err| Self::explain_deserialize_error(&config, &layers, err))?;
+            for unknown in describe_unknown_keys(&config, &layers, ignored) {
+                report
+                    .warnings
+                    .push(format!("setting not defined by the settings type: {unknown}"));
+            }
+            settings
+        };
+
+        for legacy in find_legacy_files(&layers, &options.legacy_locations())? {
+            report.warnings.push(format!(
+                "settings file {} is in a deprecated location; move it to {}",
+                legacy.file.display(),
+                legacy.moved_to.display()
+            ));
+        }
+        for warning in find_deprecated(&layers, &options.deprecated_keys()) {
+            report.warnings.push(warning.to_string());
+        }
+        for layer in &layers {
+            for violation in layer.restriction_violations()? {
+                report.warnings.push(violation.to_string());
+            }
+        }
+        report.precedence_profile = options.precedence_profile();
+        report.skipped = skipped;
+        report.missing = Self::missing_environment_files(options)?;
+        report.duration = started.elapsed();
+        tracing::info!(?settings, %report, "settings built for application.");
+        Ok((settings, report))
+    }
+
+    /// Lint the settings for issues that do not stop them loading: settings the settings type
+    /// ignores, settings set to their default values, sensitive settings outside the secrets file,
+    /// a secrets file readable beyond its owner, and deprecated settings.
+    #[tracing::instrument(level = "info")]
+    fn lint(options: &Self::Options) -> Result<LintReport, SettingsError>
+    where
+        Self: DeserializeOwned + Default + Serialize,
+    {
+        let (layers, _) = Self::resolve_layers(options)?;
+        let config = Self::build_config(options, layers.clone())?;
+        let report = LintReport::of::<Self>(&config, &layers, &options.deprecated_keys())?;
+        tracing::info!(%report, "settings linted.");
+        Ok(report)
+    }
+
+    /// Load settings with secrets already fetched from secrets providers merged into the secrets
+    /// layer, after any secrets file. `secrets::load_with_providers()` fetches them from the
+    /// options' providers.
+    #[tracing::instrument(level = "info", skip(secrets))]
+    fn load_with_secrets(options: &Self::Options, secrets: Vec<ProvidedSecrets>) -> Result<Self, SettingsError>
+    where
+        Self: DeserializeOwned,
+    {
+        Self::load_from_layers(options, Self::layers_with_secrets(options, secrets)?)
+    }
+
+    /// Load settings from the given configuration layers, with the CLI option overrides applied.
+    fn load_from_layers(options: &Self::Options, layers: Vec<LayerSource>) -> Result<Self, SettingsError>
+    where
+        Self: DeserializeOwned,
+    {
+        Self::check_deprecations(options, &layers)?;
+        let config = Self::build_config(options, layers.clone())?;
+        let settings = Self::deserialize_config(options, config, &|| layers.clone())?;
+        tracing::info!(?settings, "settings built for application.");
+        Ok(settings)
+    }
+
+    /// Assembles the configuration layers for the options, as `load_layers()` does, with the
+    /// fetched secrets added to the secrets layer after any secrets file.
+    fn layers_with_secrets(
+        options: &Self::Options, secrets: Vec<ProvidedSecrets>,
+    ) -> Result<Vec<LayerSource>, SettingsError> {
+        let mut layers = Self::load_layers(options)?;
+        insert_secrets_layers(options, &mut layers, secrets);
+        Ok(layers)
+    }
+
+    /// Load the merged configuration from the composed sources, prior to deserializing settings.
+    fn load_config(options: &Self::Options) -> Result<config::Config, SettingsError> {
+        warn_unconsulted_providers(options);
+        Self::build_config(options, Self::load_layers(options)?)
+    }
+
+    /// Builds the configuration from the layers, applying the options' CLI overrides on top.
+    fn build_config(options: &Self::Options, layers: Vec<LayerSource>) -> Result<config::Config, SettingsError> {
         let mut builder = config::Config::builder();
+        for layer in merge_layer_arrays(layers, &options.array_merge_rules())? {
+            builder = builder.add_source(layer);
+        }
+
+        builder = options
+            .load_overrides(builder)
+            .map_err(|err| SettingsError::CliOption(err.into()))?;
+
+        let mut config = builder.build()?;
+        if options.interpolate_values() {
+            let table = interpolate(config.collect()?, options.config_fs().as_ref())?;
+            config = config::Config::builder().add_source(TableSource(table)).build()?;
+        }
+        let table = config.collect()?;
+        let mut decrypted = Vec::default();
+        if contains_encrypted(&table) {
+            encrypted_keys(None, &table, &mut decrypted);
+            let table = decrypt_values(table, &options.value_decryptors())?;
+            config = config::Config::builder().add_source(TableSource(table)).build()?;
+        }
+        let coercions = options.value_coercions();
+        if !coercions.is_empty() {
+            let table = coerce_values(config.collect()?, &coercions)?;
+            config = config::Config::builder().add_source(TableSource(table)).build()?;
+        }
+        let required = options.required_keys();
+        if !required.is_empty() {
+            check_required(&config, &required, options.environment().as_ref())?;
+        }
+        if tracing::enabled!(tracing::Level::INFO) {
+            let redacted = redacted_config(config.collect()?, &decrypted);
+            tracing::info!(config=?redacted, "configuration loaded");
+        }
+        Ok(config)
+    }
+
+    /// Assembles the configuration layers for the options, ordered from lowest to highest
+    /// precedence. CLI option overrides are not included since they are applied directly to the
+    /// config builder via `LoadingOptions::load_overrides()`.
+    fn load_layers(options: &Self::Options) -> Result<Vec<LayerSource>, SettingsError> {
+        Ok(Self::resolve_layers(options)?.0)
+    }
+
+    /// Assembles the configuration layers for the options, as `load_layers()` does, along with the
+    /// optional layers skipped under the options' `LoadingOptions::load_policy()`.
+    fn resolve_layers(options: &Self::Options) -> Result<(Vec<LayerSource>, Vec<SkippedLayer>), SettingsError> {
+        let fs = options.config_fs();
+        let fs = fs.as_ref();
+        let clock = options.clock();
+        let restrictions = Arc::new(options.key_restrictions());
+        let layer_cache = options.layer_cache();
+        let environment = options.environment();
+        let per_environment_keys = options.per_environment_keys();
+        let migrations = options.migrations();
+        let file_layer = |layer, file: ConfigFile| {
+            let file = file
+                .with_env_naming(Self::environment_prefix(), Self::environment_path_separator())
+                .with_cache(layer_cache.clone());
+            if file.is_empty() {
+                tracing::info!(path=?file.path(), %layer, "settings file found empty; adding an empty layer");
+            }
+            let origin = file.path().display().to_string();
+            let file = MigratedSource::new(origin.clone(), migrations.clone(), file);
+            let file = InlineEnvironmentSource::new(environment.clone(), per_environment_keys.clone(), file);
+            LayerSource::restricted(layer, origin, &restrictions, file).with_read_at(clock.now())
+        };
+
+        let mut layers = Vec::default();
         match options.config_path() {
             Some(ref path) => {
-                builder = builder.add_source(Self::make_explicit_config_source(path));
+                layers.push(file_layer(
+                    Layer::Config,
+                    Self::make_explicit_config_source_with_fs(fs, path)?,
+                ));
             },
             None => {
                 tracing::info!(?options, "loading settings based on CLI options and environment.");
-                let mut resource_dirs = Vec::default();
-                for dir in options.implicit_search_paths() {
-                    resource_dirs.push(dir.absolutize()?.into_owned());
-                }
-                if resource_dirs.is_empty() {
-                    tracing::info!("no resource directories specified, using default.");
-                    resource_dirs.push(Self::default_resource_path());
+                let resource_dirs = Self::resource_dirs(options)?;
+                let config_source =
+                    Self::make_implicit_config_source_with_fs(fs, Self::app_config_basename(), &resource_dirs)?;
+                layers.push(file_layer(Layer::Config, config_source));
+
+                if let Some(ref env) = environment {
+                    for source in Self::make_environment_sources_with_fs(fs, env.clone(), &resource_dirs)? {
+                        layers.push(file_layer(Layer::EnvironmentConfig, source).optional());
+                    }
                 }
 
-                builder = builder.add_source(Self::make_implicit_config_source(
-                    Self::app_config_basename(),
-                    &resource_dirs,
-                ));
-
-                if let Some(env) = options.environment() {
-                    for source in Self::make_environment_sources(env, &resource_dirs) {
-                        builder = builder.add_source(source);
+                if let Some(identity) = options.instance_identity() {
+                    let overlay = InstanceOverlay::new(Self::app_config_basename(), identity);
+                    for dir in resource_dirs.iter().rev() {
+                        for file in overlay.files(fs, dir)? {
+                            layers.push(file_layer(Layer::EnvironmentConfig, file).optional());
+                        }
                     }
                 }
             },
         }
 
+        for path in options.presets() {
+            let preset = Preset::load(fs, &path)?;
+            let layer = preset.layer(&migrations)?.restrict(&restrictions);
+            insert_preset_layer(&mut layers, layer.with_read_at(clock.now()));
+        }
+
+        for dir in options.config_dirs() {
+            for file in dir.files(fs)? {
+                layers.push(file_layer(Layer::Config, file).optional());
+            }
+        }
+
+        #[cfg(feature = "http")]
+        {
+            let above_config = layers.iter().position(|l| l.layer() == Layer::Config).map_or(0, |p| p + 1);
+            for (position, source) in (above_config..).zip(options.http_sources()) {
+                let fetched = TableSource(source.collect()?);
+                let remote = LayerSource::restricted(Layer::Config, source.url().to_string(), &restrictions, fetched);
+                layers.insert(position, remote.with_read_at(clock.now()));
+            }
+        }
+
+        #[cfg(feature = "kubernetes")]
+        let kubernetes = options.kubernetes_scope();
+        #[cfg(feature = "kubernetes")]
+        if let Some(ref kubernetes) = kubernetes {
+            layers.push(kubernetes.config_layer().restrict(&restrictions).with_read_at(clock.now()));
+        }
+
         if let Some(ref secrets) = options.secrets_path() {
             let abs_secrets = secrets.absolutize()?;
-            builder = builder.add_source(Self::make_secrets_source(&abs_secrets));
+            options.secrets_permissions().check(&abs_secrets)?;
+            layers.push(file_layer(
+                Layer::Secrets,
+                Self::make_secrets_source_with_fs(fs, &abs_secrets)?,
+            ));
         }
 
-        builder = builder.add_source(Self::make_environment_variables_source());
+        #[cfg(feature = "kubernetes")]
+        if let Some(ref kubernetes) = kubernetes {
+            layers.push(
+                kubernetes
+                    .secrets_layer()
+                    .restrict(&restrictions)
+                    .with_read_at(clock.now()),
+            );
+        }
 
-        builder = options
-            .load_overrides(builder)
-            .map_err(|err| SettingsError::CliOption(err.into()))?;
+        let env_origin = format!(
+            "environment variables {}{}*",
+            Self::environment_prefix().to_uppercase(),
+            Self::environment_path_separator()
+        );
+        let env_filter = options.env_var_filter();
+        let env_aliases = options.env_var_aliases();
+        if !env_aliases.is_empty() {
+            let names: Vec<_> = env_aliases
+                .iter()
+                .map(|a| format!("{}{}*", a.alias(), Self::environment_path_separator()))
+                .collect();
+            let aliased = AliasedEnvironmentSource::new(
+                env_aliases,
+                Self::environment_path_separator(),
+                env_filter.filter(std::env::vars()),
+            );
+            layers.push(
+                LayerSource::restricted(
+                    Layer::EnvironmentVariables,
+                    format!("environment variables {}", names.join(", ")),
+                    &restrictions,
+                    aliased,
+                )
+                .with_read_at(clock.now()),
+            );
+        }
 
-        let config = builder.build()?;
-        tracing::info!(?config, "configuration loaded");
-        let settings = config.try_deserialize()?;
-        tracing::info!(?settings, "settings built for application.");
-        Ok(settings)
+        let mut env_source = Self::make_environment_variables_source();
+        if !env_filter.is_unrestricted() {
+            env_source = env_source.source(Some(env_filter.filter(std::env::vars())));
+        }
+        layers.push(
+            LayerSource::restricted(Layer::EnvironmentVariables, env_origin, &restrictions, env_source)
+                .with_read_at(clock.now()),
+        );
+
+        let in_memory = options.in_memory_layers();
+        if !in_memory.is_empty() {
+            let in_memory = in_memory
+                .into_iter()
+                .map(|l| l.restrict(&restrictions).with_read_at(clock.now()));
+            layers.splice(0..0, in_memory);
+        }
+
+        if let Some(context) = options.runtime_context() {
+            let runtime = LayerSource::restricted(Layer::Config, "runtime context", &restrictions, context);
+            layers.insert(0, runtime.with_read_at(clock.now()));
+        }
+
+        let mut layers = expand_includes(fs, layers, &file_layer)?;
+        let profile = options.precedence_profile();
+        tracing::info!(?profile, "ordering settings layers by precedence profile");
+        profile.sort(&mut layers);
+
+        for legacy in find_legacy_files(&layers, &options.legacy_locations())? {
+            tracing::warn!(
+                file=?legacy.file, moved_to=?legacy.moved_to, layer=%legacy.layer,
+                "settings file found in a deprecated location; move it to the new location"
+            );
+        }
+
+        let (layers, skipped) = options.load_policy().apply(layers);
+        for warning in find_deprecated(&layers, &options.deprecated_keys()) {
+            tracing::warn!(key=%warning.key, origin=%warning.origin, "{warning}");
+        }
+        Ok((layers, skipped))
+    }
+
+    /// Describes the configuration layers resolved for the options, ordered from lowest to highest
+    /// precedence, along with the environment configuration files searched for but not found;
+    /// e.g., to debug which settings files a load actually reads.
+    fn describe_layers(options: &Self::Options) -> Result<Vec<LayerDescriptor>, SettingsError> {
+        let fs = options.config_fs();
+        let (layers, skipped) = Self::resolve_layers(options)?;
+        let mut descriptors = Vec::default();
+        for layer in layers {
+            descriptors.push(LayerDescriptor::of(&layer, fs.as_ref())?);
+        }
+        for skipped in skipped {
+            insert_missing(&mut descriptors, LayerDescriptor::skipped(skipped));
+        }
+        for env_path in Self::missing_environment_files(options)?.into_iter().rev() {
+            let missing = LayerDescriptor::missing(Layer::EnvironmentConfig, env_path.display().to_string());
+            insert_missing(&mut descriptors, missing);
+        }
+        Ok(descriptors)
+    }
+
+    /// The environment configuration files searched for in the resource directories but not found,
+    /// without their extensions.
+    fn missing_environment_files(options: &Self::Options) -> Result<Vec<PathBuf>, SettingsError> {
+        let (None, Some(env)) = (options.config_path(), options.environment()) else {
+            return Ok(Vec::default());
+        };
+
+        let fs = options.config_fs();
+        Ok(Self::resource_dirs(options)?
+            .into_iter()
+            .map(|dir| dir.join(env.as_ref()))
+            .filter(|env_path| ConfigFile::locate(fs.as_ref(), env_path).is_none())
+            .collect())
+    }
+
+    /// Finds the settings files loaded from the options' legacy locations, along with where each
+    /// should be moved; e.g., for tooling that migrates them.
+    fn legacy_files(options: &Self::Options) -> Result<Vec<LegacyFile>, SettingsError> {
+        find_legacy_files(&Self::load_layers(options)?, &options.legacy_locations())
+    }
+
+    /// Watches the settings files resolved for the options, reloading the settings whenever they
+    /// change. Updated settings are delivered via `SettingsWatcher::updates()`.
+    #[cfg(feature = "watch")]
+    fn watch(options: Self::Options) -> Result<crate::watch::SettingsWatcher<Self>, SettingsError>
+    where
+        Self: DeserializeOwned + Send + 'static,
+        Self::Options: Send + 'static,
+    {
+        crate::watch::SettingsWatcher::new(options)
+    }
+
+    /// Writes the effective configuration for the options, after environment variables and CLI
+    /// overrides are applied, to a single file; see `export::SettingsExport`.
+    fn export_merged(options: &Self::Options, path: &Path, export: &SettingsExport) -> Result<(), SettingsError> {
+        let layers = Self::load_layers(options)?;
+        let config = Self::build_config(options, layers.clone())?;
+        export.write(path, &config, &layers)
+    }
+
+    /// Describes the configuration inputs producing the effective configuration for the options,
+    /// for signing into an attestation; see `attestation::Attestation`.
+    #[cfg(feature = "attestation")]
+    fn attest(
+        options: &Self::Options, subject: impl Into<String>,
+    ) -> Result<crate::attestation::Attestation, SettingsError> {
+        let layers = Self::load_layers(options)?;
+        let config = Self::build_config(options, layers.clone())?;
+        crate::attestation::Attestation::new(subject, &config, &layers)
+    }
+
+    /// Finds settings assigned differing values by several configuration layers.
+    fn conflicts(options: &Self::Options) -> Result<Vec<Conflict>, SettingsError> {
+        find_conflicts(&Self::load_layers(options)?, CONFLICT_MIN_LAYERS)
+    }
+
+    /// The directories searched for the application and environment configuration files: the
+    /// implicit search paths, or the default resource path, followed by any legacy locations.
+    fn resource_dirs(options: &Self::Options) -> Result<Vec<PathBuf>, SettingsError> {
+        let mut resource_dirs = Vec::default();
+        for dir in options.implicit_search_paths() {
+            resource_dirs.push(dir.absolutize()?.into_owned());
+        }
+        if resource_dirs.is_empty() {
+            tracing::info!("no resource directories specified, using default.");
+            resource_dirs.push(Self::default_resource_path());
+        }
+        for legacy in options.legacy_locations() {
+            resource_dirs.push(legacy.dir().absolutize()?.into_owned());
+        }
+        Ok(resource_dirs)
     }
 
     fn default_resource_path() -> PathBuf {
@@ -92,39 +584,51 @@ pub trait SettingsLoader: Debug + Sized {
         current_dir.join(Self::resources_home())
     }
 
-    fn make_glob_walker(base_dir: impl AsRef<Path>, pattern: impl AsRef<str>) -> Option<globwalk::GlobWalker> {
-        globwalk::GlobWalkerBuilder::new(base_dir.as_ref(), pattern.as_ref())
-            .build()
-            .map_err(|err| {
-                tracing::warn!(
-                    error=?err,
-                    "failed to build glob walker for base-directory:{:?} pattern:{}",
-                    base_dir.as_ref(), pattern.as_ref()
-                );
-                err
-            })
-            .ok()
-    }
-
     /// returns the first settings resource file found in the list of resource directories.
     fn find_resource_dir(resource: &str, dirs: &[PathBuf]) -> Option<PathBuf> {
-        for d in dirs.iter() {
-            let walker = Self::make_glob_walker(d, format!("{}.*", resource));
-            let walker = match walker {
-                Some(w) => w,
-                None => continue,
-            };
+        Self::find_resource_dir_with_fs(&RealFs, resource, dirs)
+    }
 
-            let found = walker
-                .into_iter()
-                .filter_map(|entry| {
-                    tracing::info!("found settings entry: {entry:?}");
-                    Result::ok(entry)
-                })
-                .next()
-                .is_some();
-            if found {
-                tracing::info!("found settings {resource} file in base-directory:{d:?}");
+    fn make_explicit_config_source(path: &Path) -> FileSource {
+        FileSource::from(path).required(true)
+    }
+
+    fn make_implicit_config_source(basename: &str, dir_paths: &[PathBuf]) -> FileSource {
+        let source_dir = Self::find_resource_dir(basename, dir_paths).unwrap_or_else(Self::default_resource_path);
+
+        let path = source_dir.join(basename);
+        FileSource::from(path).required(true)
+    }
+
+    fn make_environment_sources(environment: Environment, dir_paths: &[PathBuf]) -> Vec<FileSource> {
+        dir_paths
+            .iter()
+            .rev()
+            .map(|dir| Self::make_app_environment_source(&environment, dir))
+            .collect()
+    }
+
+    fn make_app_environment_source(environment: &Environment, resources: &Path) -> FileSource {
+        tracing::info!("creating application {environment} settings source at {:?}", resources);
+        let env_path = resources.join(environment.as_ref());
+        FileSource::from(env_path).required(false)
+    }
+
+    fn make_secrets_source(secrets_path: &Path) -> FileSource {
+        if secrets_path.exists() {
+            tracing::info!("adding secrets override configuration source at {:?}", secrets_path);
+        } else {
+            tracing::error!("cannot find secrets override configuration at {:?}", secrets_path);
+        }
+        FileSource::from(secrets_path).required(true)
+    }
+
+    /// returns the first settings resource file found in the list of resource directories of the
+    /// filesystem.
+    fn find_resource_dir_with_fs(fs: &dyn ConfigFs, resource: &str, dirs: &[PathBuf]) -> Option<PathBuf> {
+        for d in dirs.iter() {
+            if let Some((path, _)) = ConfigFile::locate(fs, &d.join(resource)) {
+                tracing::info!("found settings {resource} file in base-directory:{d:?}: {path:?}");
                 return Some(d.clone());
             }
         }
@@ -133,40 +637,47 @@ pub trait SettingsLoader: Debug + Sized {
         None
     }
 
-    fn make_explicit_config_source(path: &Path) -> ConfigFile {
-        ConfigFile::from(path).required(true)
+    fn make_explicit_config_source_with_fs(fs: &dyn ConfigFs, path: &Path) -> Result<ConfigFile, SettingsError> {
+        ConfigFile::load_required(fs, path)
     }
 
-    fn make_implicit_config_source(basename: &str, dir_paths: &[PathBuf]) -> ConfigFile {
-        let source_dir = Self::find_resource_dir(basename, dir_paths)
-            // .cloned()
-            .unwrap_or_else(Self::default_resource_path);
+    fn make_implicit_config_source_with_fs(
+        fs: &dyn ConfigFs, basename: &str, dir_paths: &[PathBuf],
+    ) -> Result<ConfigFile, SettingsError> {
+        let source_dir =
+            Self::find_resource_dir_with_fs(fs, basename, dir_paths).unwrap_or_else(Self::default_resource_path);
 
         let path = source_dir.join(basename);
-        ConfigFile::from(path).required(true)
+        ConfigFile::load_required(fs, &path)
     }
 
-    fn make_environment_sources(environment: Environment, dir_paths: &[PathBuf]) -> Vec<ConfigFile> {
-        dir_paths
-            .iter()
-            .rev()
-            .map(|dir| Self::make_app_environment_source(&environment, dir))
-            .collect()
+    fn make_environment_sources_with_fs(
+        fs: &dyn ConfigFs, environment: Environment, dir_paths: &[PathBuf],
+    ) -> Result<Vec<ConfigFile>, SettingsError> {
+        let mut sources = Vec::with_capacity(dir_paths.len());
+        for dir in dir_paths.iter().rev() {
+            if let Some(source) = Self::make_app_environment_source_with_fs(fs, &environment, dir)? {
+                sources.push(source);
+            }
+        }
+        Ok(sources)
     }
 
-    fn make_app_environment_source(environment: &Environment, resources: &Path) -> ConfigFile {
+    fn make_app_environment_source_with_fs(
+        fs: &dyn ConfigFs, environment: &Environment, resources: &Path,
+    ) -> Result<Option<ConfigFile>, SettingsError> {
         tracing::info!("creating application {environment} settings source at {:?}", resources);
         let env_path = resources.join(environment.as_ref());
-        ConfigFile::from(env_path).required(false)
+        ConfigFile::load(fs, &env_path)
     }
 
-    fn make_secrets_source(secrets_path: &Path) -> ConfigFile {
-        if secrets_path.exists() {
+    fn make_secrets_source_with_fs(fs: &dyn ConfigFs, secrets_path: &Path) -> Result<ConfigFile, SettingsError> {
+        if fs.is_file(secrets_path) {
             tracing::info!("adding secrets override configuration source at {:?}", secrets_path);
         } else {
             tracing::error!("cannot find secrets override configuration at {:?}", secrets_path);
         }
-        ConfigFile::from(secrets_path).required(true)
+        ConfigFile::load_required(fs, secrets_path)
     }
 
     fn make_environment_variables_source() -> config::Environment {
@@ -216,16 +727,32 @@ pub trait SettingsLoader: Debug + Sized {
     }
 }
 
+fn warn_unconsulted_providers(options: &impl LoadingOptions) {
+    if !options.secrets_providers().is_empty() {
+        tracing::warn!("secrets providers are not consulted by a synchronous load; use secrets::load_with_providers()");
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use crate::{environment, NoOptions, APP_ENVIRONMENT};
     use claim::{assert_err, assert_ok};
-    use config::{Config, FileFormat};
+    use config::{Config, FileFormat, Format};
     use pretty_assertions::assert_eq;
     use serde::{Deserialize, Serialize};
     use serde_with::{serde_as, DisplayFromStr};
 
+    use super::*;
+    use crate::coerce::{Coercion, ValueType};
+    use crate::deprecation::{DeprecatedKey, DeprecationPolicy};
+    use crate::describe::LayerStatus;
+    use crate::fs::SettingsFormat;
+    use crate::required::RequiredKey;
+    use crate::strict::UnknownKey;
+    use crate::{
+        environment, Clock, ConfigDir, EnvVarFilter, InstanceIdentity, KeyRestriction, LoadPolicy, NoOptions,
+        PrecedenceProfile, SortOrder, ValueDecryptor, APP_ENVIRONMENT,
+    };
+
     #[derive(Debug, PartialEq, Eq)]
     struct TestOptions(String, Option<Environment>);
 
@@ -490,17 +1017,1126 @@ mod tests {
         Ok(())
     }
 
+    #[derive(Debug)]
+    struct TestFsOptions {
+        fs: Arc<MemoryFs>,
+        restrictions: Vec<KeyRestriction>,
+        env_filter: EnvVarFilter,
+        profile: PrecedenceProfile,
+        strict: bool,
+        in_memory: Vec<LayerSource>,
+        dirs: Vec<ConfigDir>,
+        policy: LoadPolicy,
+        coercions: Vec<Coercion>,
+        identity: Option<InstanceIdentity>,
+        required: Vec<RequiredKey>,
+        deprecated: Vec<DeprecatedKey>,
+        deprecation_policy: DeprecationPolicy,
+        version: Option<String>,
+        decryptors: Vec<Arc<dyn ValueDecryptor>>,
+    }
+
+    impl TestFsOptions {
+        fn new(fs: MemoryFs) -> Self {
+            Self {
+                fs: Arc::new(fs),
+                restrictions: Vec::default(),
+                env_filter: EnvVarFilter::default(),
+                profile: PrecedenceProfile::default(),
+                strict: false,
+                in_memory: Vec::default(),
+                dirs: Vec::default(),
+                policy: LoadPolicy::default(),
+                coercions: Vec::default(),
+                identity: None,
+                required: Vec::default(),
+                deprecated: Vec::default(),
+                deprecation_policy: DeprecationPolicy::default(),
+                version: None,
+                decryptors: Vec::default(),
+            }
+        }
+    }
+
+    impl LoadingOptions for TestFsOptions {
+        type Error = SettingsError;
+
+        fn config_path(&self) -> Option<PathBuf> {
+            None
+        }
+
+        fn secrets_path(&self) -> Option<PathBuf> {
+            Some(PathBuf::from("./secrets/db.yaml"))
+        }
+
+        fn implicit_search_paths(&self) -> Vec<PathBuf> {
+            vec!["./virtual".into()]
+        }
+
+        fn config_fs(&self) -> Arc<dyn ConfigFs> {
+            self.fs.clone()
+        }
+
+        fn key_restrictions(&self) -> Vec<KeyRestriction> {
+            self.restrictions.clone()
+        }
+
+        fn env_var_filter(&self) -> EnvVarFilter {
+            self.env_filter.clone()
+        }
+
+        fn precedence_profile(&self) -> PrecedenceProfile {
+            self.profile.clone()
+        }
+
+        fn strict(&self) -> bool {
+            self.strict
+        }
+
+        fn in_memory_layers(&self) -> Vec<LayerSource> {
+            self.in_memory.clone()
+        }
+
+        fn config_dirs(&self) -> Vec<ConfigDir> {
+            self.dirs.clone()
+        }
+
+        fn load_policy(&self) -> LoadPolicy {
+            self.policy
+        }
+
+        fn value_coercions(&self) -> Vec<Coercion> {
+            self.coercions.clone()
+        }
+
+        fn instance_identity(&self) -> Option<InstanceIdentity> {
+            self.identity.clone()
+        }
+
+        fn required_keys(&self) -> Vec<RequiredKey> {
+            self.required.clone()
+        }
+
+        fn deprecated_keys(&self) -> Vec<DeprecatedKey> {
+            self.deprecated.clone()
+        }
+
+        fn deprecation_policy(&self) -> DeprecationPolicy {
+            self.deprecation_policy
+        }
+
+        fn value_decryptors(&self) -> Vec<Arc<dyn ValueDecryptor>> {
+            self.decryptors.clone()
+        }
+
+        fn application_version(&self) -> Option<String> {
+            self.version.clone()
+        }
+
+        fn clock(&self) -> Arc<dyn Clock> {
+            Arc::new(FixedClock(*TEST_NOW))
+        }
+    }
+
+    static TEST_NOW: Lazy<SystemTime> = Lazy::new(|| SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct TestFsSettings {
+        pub application: TestHttpSettings,
+        pub database: TestDbSettings,
+        pub foo: String,
+    }
+
+    impl SettingsLoader for TestFsSettings {
+        type Options = TestFsOptions;
+    }
+
+    #[test]
+    fn test_settings_load_from_memory_fs() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_from_memory_fs",
+            vec![(APP_ENVIRONMENT, Some("production"))],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_from_memory_fs");
+                let _ = main_span.enter();
+
+                let application = r###"
+                    | application:
+                    |   port: 8000
+                    |   host: 0.0.0.0
+                    | database:
+                    |   host: localhost
+                    |   port: 5432
+                    |   require_ssl: false
+                "###
+                .trim_margin_with("| ")
+                .unwrap();
+
+                let fs = MemoryFs::new()
+                    .with_file("virtual/application.yaml", application.as_str())
+                    .with_file(
+                        "virtual/production.json",
+                        r#"{"database": {"name": "virtual_db"}, "foo": "json"}"#,
+                    )
+                    .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+
+                let actual = assert_ok!(TestFsSettings::load(&TestFsOptions::new(fs)));
+                let expected = TestFsSettings {
+                    application: TestHttpSettings { port: 8000, host: "0.0.0.0".to_string() },
+                    database: TestDbSettings {
+                        username: "vfs".to_string(),
+                        password: "in-memory".to_string(),
+                        port: 5432,
+                        host: "localhost".to_string(),
+                        database_name: "virtual_db".to_string(),
+                        require_ssl: false,
+                    },
+                    foo: "json".to_string(),
+                };
+                assert_eq!(actual, expected);
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_w_key_restrictions() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_w_key_restrictions",
+            vec![
+                (APP_ENVIRONMENT, None),
+                ("APP__DATABASE__PASSWORD", Some("injected")),
+                ("APP__DATABASE__PORT", Some("1111")),
+            ],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_w_key_restrictions");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file(
+                        "virtual/application.yaml",
+                        "application: { port: 8000, host: 0.0.0.0 }\ndatabase: { host: localhost, port: 5432, name: \
+                         db, require_ssl: false, password: checked-in }\nfoo: bar",
+                    )
+                    .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+                let restrictions = vec![KeyRestriction::new("database.password", [Layer::Secrets])];
+
+                let options = TestFsOptions { restrictions, ..TestFsOptions::new(fs) };
+                let actual = assert_ok!(TestFsSettings::load(&options));
+                assert_eq!(actual.database.password, "in-memory".to_string());
+                assert_eq!(actual.database.port, 1111);
+
+                let (_, report) = assert_ok!(TestFsSettings::load_with_report(&options));
+                let violations: Vec<_> = report.warnings.iter().filter(|w| w.contains(" dropped: ")).collect();
+                assert_eq!(violations.len(), 2, "unexpected warnings: {:?}", report.warnings);
+                assert!(violations[0].starts_with("setting database.password from /"));
+                assert!(violations[0].ends_with(
+                    "virtual/application.yaml dropped: database.password may not be supplied by the config layer"
+                ));
+                assert_eq!(
+                    violations[1],
+                    "setting database.password from environment variables APP__* dropped: database.password may not \
+                     be supplied by the environment-variables layer"
+                );
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_w_precedence_profile() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_w_precedence_profile",
+            vec![
+                (APP_ENVIRONMENT, None),
+                ("APP__DATABASE__PORT", Some("1111")),
+                ("APP__DATABASE__NAME", Some("from_env")),
+            ],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_w_precedence_profile");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file(
+                        "virtual/application.yaml",
+                        "application: { port: 8000, host: 0.0.0.0 }\ndatabase: { host: localhost, port: 5432, \
+                         require_ssl: false }\nfoo: bar",
+                    )
+                    .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+                let options = TestFsOptions {
+                    profile: PrecedenceProfile::CliFileEnv,
+                    ..TestFsOptions::new(fs)
+                };
+
+                let layers = assert_ok!(TestFsSettings::load_layers(&options));
+                let order: Vec<_> = layers.iter().map(LayerSource::layer).collect();
+                assert_eq!(order, vec![Layer::EnvironmentVariables, Layer::Config, Layer::Secrets]);
+
+                let actual = assert_ok!(TestFsSettings::load(&options));
+                assert_eq!(actual.database.port, 5432);
+                assert_eq!(actual.database.database_name, "from_env".to_string());
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_section() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_section",
+            vec![(APP_ENVIRONMENT, None), ("APP__DATABASE__PORT", Some("1111"))],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_section");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file(
+                        "virtual/application.yaml",
+                        "database: { host: localhost, port: 5432, name: db, require_ssl: false }",
+                    )
+                    .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+                let options = TestFsOptions::new(fs);
+
+                assert_err!(TestFsSettings::load(&options));
+                let actual: TestDbSettings = assert_ok!(TestFsSettings::load_section(&options, "database"));
+                assert_eq!(
+                    actual,
+                    TestDbSettings {
+                        username: "vfs".to_string(),
+                        password: "in-memory".to_string(),
+                        port: 1111,
+                        host: "localhost".to_string(),
+                        database_name: "db".to_string(),
+                        require_ssl: false,
+                    }
+                );
+                assert_err!(TestFsSettings::load_section::<TestHttpSettings>(
+                    &options,
+                    "application"
+                ));
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_strict() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_strict",
+            vec![(APP_ENVIRONMENT, None), ("APP__DATABASE__HOTS", Some("typo"))],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_strict");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file(
+                        "virtual/application.yaml",
+                        "application: { port: 8000, host: 0.0.0.0 }\ndatabase: { host: localhost, port: 5432, name: \
+                         db, require_ssl: false }\nfoo: bar\nunused: { enabled: true }",
+                    )
+                    .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+                let options = TestFsOptions::new(fs);
+                assert_ok!(TestFsSettings::load(&options));
+
+                let strict = TestFsOptions { strict: true, ..options };
+                let err = assert_err!(TestFsSettings::load(&strict));
+                let SettingsError::UnknownKeys(mut unknown) = err else {
+                    panic!("expected unknown keys error but got: {err:?}");
+                };
+                unknown.sort_by(|a, b| a.key.cmp(&b.key));
+                assert_eq!(
+                    unknown,
+                    vec![
+                        UnknownKey {
+                            key: "database.hots".to_string(),
+                            origin: Some("environment variables APP__*".to_string()),
+                            suggestion: Some("database.host".to_string()),
+                        },
+                        UnknownKey {
+                            key: "unused".to_string(),
+                            origin: Some(
+                                assert_ok!(Path::new("virtual/application.yaml").absolutize())
+                                    .display()
+                                    .to_string()
+                            ),
+                            suggestion: None,
+                        },
+                    ]
+                );
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_in_memory_layers() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_in_memory_layers",
+            vec![(APP_ENVIRONMENT, None)],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_in_memory_layers");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file("virtual/application.yaml", "foo: from file")
+                    .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+                let defaults = LayerSource::from_contents(
+                    Layer::Config,
+                    "embedded defaults",
+                    "application: { port: 8000, host: 0.0.0.0 }\ndatabase: { host: localhost, port: 5432, name: db, \
+                     require_ssl: false }\nfoo: embedded",
+                    FileFormat::Yaml,
+                );
+                let overrides = assert_ok!(LayerSource::from_value(
+                    Layer::EnvironmentConfig,
+                    "test overrides",
+                    &serde_json::json!({ "database": { "port": 6543 } }),
+                ));
+                let options = TestFsOptions {
+                    in_memory: vec![defaults, overrides],
+                    ..TestFsOptions::new(fs)
+                };
+
+                let actual = assert_ok!(TestFsSettings::load(&options));
+                assert_eq!(actual.foo, "from file");
+                assert_eq!(actual.database.host, "localhost");
+                assert_eq!(actual.database.port, 6543);
+                assert_eq!(actual.database.username, "vfs");
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_config_dir() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_config_dir",
+            vec![(APP_ENVIRONMENT, Some("production"))],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_config_dir");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file(
+                        "virtual/application.yaml",
+                        "application: { port: 8000, host: 0.0.0.0 }\ndatabase: { host: localhost, port: 5432, name: \
+                         db, require_ssl: false }\nfoo: bar",
+                    )
+                    .with_file("virtual/production.yaml", "database: { require_ssl: true }")
+                    .with_file(
+                        "conf.d/9-db.yaml",
+                        "database: { host: db.internal, require_ssl: false }",
+                    )
+                    .with_file("conf.d/10-db.toml", "[database]\nhost = \"db.override\"")
+                    .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+                let options = TestFsOptions {
+                    dirs: vec![ConfigDir::new("conf.d", SortOrder::Natural)],
+                    ..TestFsOptions::new(fs)
+                };
+
+                let actual = assert_ok!(TestFsSettings::load(&options));
+                assert_eq!(actual.database.host, "db.override");
+                assert!(actual.database.require_ssl);
+                assert_eq!(actual.foo, "bar");
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_skip_invalid_layers() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_skip_invalid_layers",
+            vec![(APP_ENVIRONMENT, Some("production"))],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_skip_invalid_layers");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file(
+                        "virtual/application.yaml",
+                        "application: { port: 8000, host: 0.0.0.0 }\ndatabase: { host: localhost, port: 5432, name: \
+                         db, require_ssl: false }\nfoo: bar",
+                    )
+                    .with_file("virtual/production.toml", "[database]\nrequire_ssl = ")
+                    .with_file("conf.d/10-db.yaml", "database: { host: db.override }")
+                    .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+                let options = TestFsOptions {
+                    dirs: vec![ConfigDir::new("conf.d", SortOrder::Natural)],
+                    ..TestFsOptions::new(fs)
+                };
+                assert_err!(TestFsSettings::load(&options));
+
+                let options = TestFsOptions { policy: LoadPolicy::SkipInvalidLayers, ..options };
+                let actual = assert_ok!(TestFsSettings::load(&options));
+                assert_eq!(actual.database.host, "db.override");
+                assert!(!actual.database.require_ssl);
+
+                let (_, skipped) = assert_ok!(TestFsSettings::resolve_layers(&options));
+                assert_eq!(skipped.len(), 1);
+                assert_eq!(skipped[0].layer, Layer::EnvironmentConfig);
+                assert!(skipped[0].origin.ends_with("virtual/production.toml"));
+                let described = assert_ok!(TestFsSettings::describe_layers(&options));
+                assert!(described
+                    .iter()
+                    .any(|d| d.layer == Layer::EnvironmentConfig && d.status == LayerStatus::Skipped));
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_with_report() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_with_report",
+            vec![(APP_ENVIRONMENT, Some("production"))],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_with_report");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file(
+                        "virtual/application.yaml",
+                        "application: { port: 8000, host: 0.0.0.0 }\ndatabase: { host: localhost, port: 5432, name: \
+                         db, require_ssl: false }\nfoo: bar\nfooo: baz",
+                    )
+                    .with_file("conf.d/10-broken.toml", "[database]\nhost = ")
+                    .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+                let options = TestFsOptions {
+                    dirs: vec![ConfigDir::new("conf.d", SortOrder::Natural)],
+                    policy: LoadPolicy::SkipInvalidLayers,
+                    profile: PrecedenceProfile::CliFileEnv,
+                    ..TestFsOptions::new(fs)
+                };
+
+                let (actual, report) = assert_ok!(TestFsSettings::load_with_report(&options));
+                assert_eq!(actual.database.host, "localhost");
+                assert_eq!(report.precedence_profile, PrecedenceProfile::CliFileEnv);
+                let layers: Vec<_> = report.layers.iter().map(|l| (l.layer, l.settings)).collect();
+                assert_eq!(
+                    layers,
+                    vec![
+                        (Layer::EnvironmentVariables, 0),
+                        (Layer::Config, 8),
+                        (Layer::Secrets, 2)
+                    ]
+                );
+                assert_eq!(report.skipped.len(), 1);
+                assert_eq!(report.missing.len(), 1);
+                assert!(report.missing[0].ends_with("virtual/production"));
+                assert_eq!(report.env_vars_applied, 0);
+                assert_eq!(report.provenance.values().sum::<usize>(), 10);
+                assert_eq!(report.warnings.len(), 1);
+                assert!(report.warnings[0].starts_with("setting not defined by the settings type: fooo (from "));
+                assert!(report.warnings[0].ends_with("virtual/application.yaml); did you mean foo?"));
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_w_deprecated_keys() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_w_deprecated_keys",
+            vec![(APP_ENVIRONMENT, None)],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_w_deprecated_keys");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file(
+                        "virtual/application.yaml",
+                        "application: { port: 8000, host: 0.0.0.0 }\ndatabase: { host: localhost, port: 5432, name: \
+                         db, require_ssl: false }\nfoo: bar",
+                    )
+                    .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+                let options = TestFsOptions {
+                    deprecated: vec![
+                        DeprecatedKey::new("foo").since("0.14").replaced_by("application.name"),
+                        DeprecatedKey::new("database.pool"),
+                    ],
+                    ..TestFsOptions::new(fs)
+                };
+
+                let (actual, report) = assert_ok!(TestFsSettings::load_with_report(&options));
+                assert_eq!(actual.foo, "bar");
+                assert_eq!(report.warnings.len(), 1);
+                assert!(report.warnings[0].starts_with("setting foo from "));
+                assert!(report.warnings[0]
+                    .ends_with("application.yaml is deprecated since 0.14; use application.name instead"));
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_w_removed_keys() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_w_removed_keys",
+            vec![(APP_ENVIRONMENT, None)],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_w_removed_keys");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file(
+                        "virtual/application.yaml",
+                        "application: { port: 8000, host: 0.0.0.0 }\ndatabase: { host: localhost, port: 5432, name: \
+                         db, require_ssl: false }\nfoo: bar",
+                    )
+                    .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+                let deprecated = vec![DeprecatedKey::new("foo").since("0.14").removed_in("0.16")];
+
+                let options = TestFsOptions {
+                    deprecated: deprecated.clone(),
+                    deprecation_policy: DeprecationPolicy::RejectRemoved,
+                    version: Some("0.15.2".to_string()),
+                    ..TestFsOptions::new(fs.clone())
+                };
+                let (actual, report) = assert_ok!(TestFsSettings::load_with_report(&options));
+                assert_eq!(actual.foo, "bar");
+                assert_eq!(report.warnings.len(), 1);
+                assert!(report.warnings[0].ends_with("is deprecated since 0.14, to be removed in 0.16"));
+
+                let options = TestFsOptions {
+                    deprecated: deprecated.clone(),
+                    version: Some("0.16.0".to_string()),
+                    ..TestFsOptions::new(fs.clone())
+                };
+                assert_eq!(assert_ok!(TestFsSettings::load(&options)).foo, "bar");
+
+                let options = TestFsOptions {
+                    deprecated,
+                    deprecation_policy: DeprecationPolicy::RejectRemoved,
+                    version: Some("0.16.0".to_string()),
+                    ..TestFsOptions::new(fs)
+                };
+                assert_ok!(TestFsSettings::describe_layers(&options));
+                assert_ok!(TestFsSettings::conflicts(&options));
+                let err = assert_err!(TestFsSettings::load_with_report(&options));
+                assert!(matches!(err, SettingsError::RemovedSettings { .. }));
+                let err = assert_err!(TestFsSettings::load(&options));
+                assert!(err
+                    .to_string()
+                    .starts_with("settings removed as of version 0.16.0 are still supplied: foo from "));
+            },
+        );
+        Ok(())
+    }
+
+    #[derive(Debug)]
+    struct Reversed;
+
+    impl ValueDecryptor for Reversed {
+        fn scheme(&self) -> &str {
+            "REV"
+        }
+
+        fn decrypt(&self, ciphertext: &str) -> Result<String, String> {
+            Ok(ciphertext.chars().rev().collect())
+        }
+    }
+
+    #[derive(Debug, Clone, Default)]
+    struct CapturedLog(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLog {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_settings_load_does_not_log_decrypted_values() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_does_not_log_decrypted_values",
+            vec![(APP_ENVIRONMENT, None)],
+            || {
+                let fs = MemoryFs::new()
+                    .with_file(
+                        "virtual/application.yaml",
+                        "application: { port: 8000, host: 0.0.0.0 }\ndatabase: { host: localhost, port: 5432, name: \
+                         db, require_ssl: false }\nfoo: 'enc:REV:terces-txetnialp'",
+                    )
+                    .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+                let options = TestFsOptions {
+                    decryptors: vec![Arc::new(Reversed)],
+                    ..TestFsOptions::new(fs)
+                };
+
+                let log = CapturedLog::default();
+                let writer = log.clone();
+                let subscriber = crate::tracing::get_subscriber("test", "info", move || writer.clone());
+                let config =
+                    tracing::subscriber::with_default(subscriber, || assert_ok!(TestFsSettings::load_config(&options)));
+                assert_eq!(assert_ok!(config.get_string("foo")), "plaintext-secret");
+
+                let log = String::from_utf8(log.0.lock().unwrap().clone()).unwrap();
+                assert!(log.contains("configuration loaded"));
+                assert!(log.contains(r#"\"foo\": \"<redacted>\""#));
+                assert!(!log.contains("plaintext-secret"));
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_w_includes() -> anyhow::Result<()> {
+        with_env_vars("test_settings_load_w_includes", vec![(APP_ENVIRONMENT, None)], || {
+            Lazy::force(&TEST_TRACING);
+            let main_span = tracing::info_span!("test_settings_load_w_includes");
+            let _ = main_span.enter();
+
+            let fs = MemoryFs::new()
+                .with_file(
+                    "virtual/application.yaml",
+                    "include: [db/*.yaml]\napplication: { port: 8000, host: 0.0.0.0 }\ndatabase: { host: localhost \
+                     }\nfoo: bar",
+                )
+                .with_file(
+                    "virtual/db/postgres.yaml",
+                    "database: { host: db.internal, port: 5432, name: db, require_ssl: true }",
+                )
+                .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+            let options = TestFsOptions { strict: true, ..TestFsOptions::new(fs) };
+
+            let actual = assert_ok!(TestFsSettings::load(&options));
+            assert_eq!(actual.database.host, "localhost");
+            assert_eq!(actual.database.database_name, "db");
+            assert!(actual.database.require_ssl);
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_w_instance_overlay() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_w_instance_overlay",
+            vec![(APP_ENVIRONMENT, Some("production"))],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_w_instance_overlay");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file(
+                        "virtual/application.yaml",
+                        "application: { port: 8000, host: 0.0.0.0 }\ndatabase: { host: localhost, port: 5432, name: \
+                         db, require_ssl: false }\nfoo: bar",
+                    )
+                    .with_file("virtual/production.yaml", "application: { port: 80 }\nfoo: production")
+                    .with_file("virtual/application@web-01.yaml", "application: { port: 8080 }")
+                    .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+                let options = TestFsOptions {
+                    identity: Some(InstanceIdentity::new().with_hostname("web-01").with_instance_id("i-0abc")),
+                    ..TestFsOptions::new(fs)
+                };
+
+                let actual = assert_ok!(TestFsSettings::load(&options));
+                assert_eq!(actual.application.port, 8080);
+                assert_eq!(actual.foo, "production");
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_w_required_keys() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_w_required_keys",
+            vec![(APP_ENVIRONMENT, Some("production"))],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_w_required_keys");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file(
+                        "virtual/application.yaml",
+                        "application: { port: 8000, host: 0.0.0.0 }\ndatabase: { host: localhost, port: 5432, name: \
+                         db, require_ssl: false }\nfoo: bar",
+                    )
+                    .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+                let options = TestFsOptions {
+                    required: vec![
+                        RequiredKey::new("database.password"),
+                        RequiredKey::new("tls.cert_path").in_environment("production"),
+                        RequiredKey::new("tls.key_path").in_environment("staging"),
+                    ],
+                    ..TestFsOptions::new(fs)
+                };
+
+                let err = assert_err!(TestFsSettings::load(&options));
+                assert_eq!(
+                    err.to_string(),
+                    "required settings missing in production: tls.cert_path"
+                );
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_explains_failing_setting() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_explains_failing_setting",
+            vec![(APP_ENVIRONMENT, Some("local"))],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_explains_failing_setting");
+                let _ = main_span.enter();
+
+                let application = |port: &str| {
+                    format!(
+                        "application: {{ port: {port}, host: 0.0.0.0 }}\ndatabase: {{ host: localhost, port: 5432, \
+                         name: db, require_ssl: false }}\nfoo: bar"
+                    )
+                };
+                let fs = |port: &str, local: (&str, &str)| {
+                    TestFsOptions::new(
+                        MemoryFs::new()
+                            .with_file("virtual/application.yaml", application(port))
+                            .with_file(local.0, local.1)
+                            .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }"),
+                    )
+                };
+
+                let ini = ("virtual/local.ini", "[database]\nport=5433\nrequire_ssl=true");
+                let actual = assert_ok!(TestFsSettings::load(&fs("8000", ini)));
+                assert_eq!(actual.database.port, 5433);
+                assert!(actual.database.require_ssl);
+                let err = assert_err!(TestFsSettings::load(&fs("eighty", ini)));
+                assert!(
+                    err.to_string()
+                        .starts_with("invalid setting application.port = eighty from "),
+                    "unexpected error: {err}"
+                );
+
+                let unrelated = ("virtual/local.yaml", "foo: 3");
+                let err = assert_err!(TestFsSettings::load(&fs("eighty", unrelated)));
+                assert!(
+                    err.to_string()
+                        .starts_with("invalid setting application.port = eighty from "),
+                    "unexpected error: {err}"
+                );
+
+                let conflicting = ("virtual/local.yaml", "application: { port: [80] }");
+                let err = assert_err!(TestFsSettings::load(&fs("8000", conflicting)));
+                assert!(
+                    matches!(err, SettingsError::LayerTypeConflict { ref key, .. } if key == "application.port"),
+                    "unexpected error: {err}"
+                );
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_describe_layers() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_describe_layers",
+            vec![(APP_ENVIRONMENT, Some("production"))],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_describe_layers");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file("virtual/application.yaml", "foo: bar")
+                    .with_file("secrets/db.yaml", "");
+                let options = TestFsOptions::new(fs);
+
+                let actual: Vec<_> = assert_ok!(TestFsSettings::describe_layers(&options))
+                    .into_iter()
+                    .map(|d| (d.layer, d.status, d.format))
+                    .collect();
+                assert_eq!(
+                    actual,
+                    vec![
+                        (
+                            Layer::Config,
+                            LayerStatus::Loaded,
+                            Some(SettingsFormat::File(FileFormat::Yaml))
+                        ),
+                        (Layer::EnvironmentConfig, LayerStatus::Missing, None),
+                        (
+                            Layer::Secrets,
+                            LayerStatus::Empty,
+                            Some(SettingsFormat::File(FileFormat::Yaml))
+                        ),
+                        (Layer::EnvironmentVariables, LayerStatus::Empty, None),
+                    ]
+                );
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_w_env_var_filter() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_w_env_var_filter",
+            vec![
+                (APP_ENVIRONMENT, None),
+                ("APP__APPLICATION__PORT", Some("80")),
+                ("APP__DATABASE__PASSWORD", Some("injected")),
+                ("APP__FOO", Some("injected")),
+            ],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_w_env_var_filter");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file(
+                        "virtual/application.yaml",
+                        "application: { port: 8000, host: 0.0.0.0 }\ndatabase: { host: localhost, port: 5432, name: \
+                         db, require_ssl: false }\nfoo: bar",
+                    )
+                    .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+                let env_filter =
+                    EnvVarFilter::allow_only(["APP__APPLICATION__*", "APP__DATABASE__*"]).deny("*PASSWORD");
+
+                let actual = assert_ok!(TestFsSettings::load(&TestFsOptions {
+                    env_filter,
+                    ..TestFsOptions::new(fs)
+                }));
+                assert_eq!(actual.application.port, 80);
+                assert_eq!(actual.database.password, "in-memory".to_string());
+                assert_eq!(actual.foo, "bar".to_string());
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_w_value_coercions() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_w_value_coercions",
+            vec![
+                (APP_ENVIRONMENT, None),
+                ("APP__APPLICATION__PORT", Some(" 80 ")),
+                ("APP__DATABASE__REQUIRE_SSL", Some("yes")),
+            ],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_w_value_coercions");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file(
+                        "virtual/application.yaml",
+                        "application: { port: 8000, host: 0.0.0.0 }\ndatabase: { host: localhost, port: 5432, name: \
+                         db, require_ssl: false }\nfoo: bar",
+                    )
+                    .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+                let options = TestFsOptions {
+                    coercions: vec![
+                        Coercion::new("*.port", ValueType::Integer),
+                        Coercion::new("database.require_ssl", ValueType::Boolean),
+                    ],
+                    ..TestFsOptions::new(fs)
+                };
+
+                let actual = assert_ok!(TestFsSettings::load(&options));
+                assert_eq!(actual.application.port, 80);
+                assert!(actual.database.require_ssl);
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_rejects_invalid_coerced_value() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_rejects_invalid_coerced_value",
+            vec![
+                (APP_ENVIRONMENT, None),
+                ("APP__APPLICATION__PORT", Some(" 80 ")),
+                ("APP__DATABASE__REQUIRE_SSL", Some("maybe")),
+            ],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_rejects_invalid_coerced_value");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file(
+                        "virtual/application.yaml",
+                        "application: { port: 8000, host: 0.0.0.0 }\ndatabase: { host: localhost, port: 5432, name: \
+                         db, require_ssl: false }\nfoo: bar",
+                    )
+                    .with_file("secrets/db.yaml", "database: { username: vfs, password: in-memory }");
+                let options = TestFsOptions {
+                    coercions: vec![
+                        Coercion::new("*.port", ValueType::Integer),
+                        Coercion::new("database.require_ssl", ValueType::Boolean),
+                    ],
+                    ..TestFsOptions::new(fs)
+                };
+
+                let err = assert_err!(TestFsSettings::load(&options));
+                assert!(err.to_string().starts_with("invalid setting database.require_ssl = maybe"));
+                assert!(err.to_string().ends_with(": expected a boolean"));
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_conflicts() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_conflicts",
+            vec![
+                (APP_ENVIRONMENT, Some("local")),
+                ("APP__DATABASE__PASSWORD", Some("from-env")),
+            ],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_conflicts");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file("virtual/application.yaml", "database: { password: app, port: 5432 }")
+                    .with_file("virtual/local.yaml", "database: { password: local, port: 5432 }")
+                    .with_file("secrets/db.yaml", "database: { password: secret }");
+
+                let conflicts = assert_ok!(TestFsSettings::conflicts(&TestFsOptions::new(fs)));
+                assert_eq!(conflicts.len(), 1);
+                assert_eq!(conflicts[0].key, "database.password");
+                let layers: Vec<_> = conflicts[0].candidates.iter().map(|c| c.layer).collect();
+                assert_eq!(
+                    layers,
+                    vec![
+                        Layer::Config,
+                        Layer::EnvironmentConfig,
+                        Layer::Secrets,
+                        Layer::EnvironmentVariables
+                    ]
+                );
+                assert_eq!(
+                    assert_ok!(conflicts[0].winner().value.clone().into_string()),
+                    "from-env"
+                );
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_load_w_provided_secrets() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_settings_load_w_provided_secrets",
+            vec![(APP_ENVIRONMENT, None), ("APP__DATABASE__USERNAME", Some("from-env"))],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_settings_load_w_provided_secrets");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file(
+                        "virtual/application.yaml",
+                        "application: { port: 8000, host: 0.0.0.0 }\ndatabase: { host: localhost, port: 5432, name: \
+                         db, require_ssl: false }\nfoo: bar",
+                    )
+                    .with_file("secrets/db.yaml", "database: { username: file, password: file }");
+                let provided = ProvidedSecrets::new(
+                    "vault secret/app",
+                    assert_ok!(
+                        FileFormat::Json.parse(None, r#"{"database": {"username": "vault", "password": "vault"}}"#)
+                    ),
+                );
+
+                let options = TestFsOptions::new(fs);
+                let actual = assert_ok!(TestFsSettings::load_with_secrets(&options, vec![provided]));
+                assert_eq!(actual.database.password, "vault".to_string());
+                assert_eq!(actual.database.username, "from-env".to_string());
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_layers_timestamps() -> anyhow::Result<()> {
+        with_env_vars(
+            "test_load_layers_timestamps",
+            vec![(APP_ENVIRONMENT, Some("local"))],
+            || {
+                Lazy::force(&TEST_TRACING);
+                let main_span = tracing::info_span!("test_load_layers_timestamps");
+                let _ = main_span.enter();
+
+                let fs = MemoryFs::new()
+                    .with_file("virtual/application.yaml", "foo: bar")
+                    .with_file("virtual/local.toml", "foo = \"zed\"")
+                    .with_file("secrets/db.yaml", "database: { password: secret }");
+
+                let layers = assert_ok!(TestFsSettings::load_layers(&TestFsOptions::new(fs)));
+                let actual: Vec<_> = layers.iter().map(|l| (l.layer(), l.read_at())).collect();
+                assert_eq!(
+                    actual,
+                    vec![
+                        (Layer::Config, Some(*TEST_NOW)),
+                        (Layer::EnvironmentConfig, Some(*TEST_NOW)),
+                        (Layer::Secrets, Some(*TEST_NOW)),
+                        (Layer::EnvironmentVariables, Some(*TEST_NOW)),
+                    ]
+                );
+                assert!(layers[1].origin().ends_with("local.toml"));
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_resource_dir() {
+        let resources = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources");
+        let dirs = vec![PathBuf::from("missing"), resources.clone()];
+        assert_eq!(
+            TestFsSettings::find_resource_dir("application", &dirs),
+            Some(resources.clone())
+        );
+        assert_eq!(TestFsSettings::find_resource_dir("unknown", &dirs), None);
+        assert_eq!(TestFsSettings::make_environment_sources("local".into(), &dirs).len(), 2);
+
+        let fs = MemoryFs::new().with_file("virtual/application.yaml", "foo: bar");
+        let dirs = vec![resources, PathBuf::from("virtual")];
+        assert_eq!(
+            TestFsSettings::find_resource_dir_with_fs(&fs, "application", &dirs),
+            Some(PathBuf::from("virtual"))
+        );
+    }
+
     use std::env::VarError;
     use std::panic::{RefUnwindSafe, UnwindSafe};
-    use std::sync::Mutex;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, SystemTime};
     use std::{env, panic};
 
     use once_cell::sync::Lazy;
     use trim_margin::MarginTrimmable;
 
+    use crate::clock::FixedClock;
+    use crate::fs::MemoryFs;
     use crate::tracing::TEST_TRACING;
 
-    static SERIAL_TEST: Lazy<Mutex<()>> = Lazy::new(|| Default::default());
+    static SERIAL_TEST: Lazy<Mutex<()>> = Lazy::new(Default::default);
 
     /// Sets environment variables to the given value for the duration of the closure.
     /// Restores the previous values when the closure completes or panics, before unwinding the
@@ -0,0 +1,364 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+
+use config::{Config, ConfigError, Source, Value, ValueKind};
+use serde::de::value::StringDeserializer;
+use serde::de::{DeserializeOwned, DeserializeSeed, Deserializer, MapAccess, Visitor};
+
+use crate::layer::{flatten_into, LayerSource};
+use crate::migrations::VERSION_KEY;
+
+/// Maximum edit distance of a known key suggested for an unknown key.
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// A setting supplied by the configuration that the settings type does not define, reported by
+/// `SettingsError::UnknownKeys` when loading with `LoadingOptions::strict()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownKey {
+    pub key: String,
+
+    /// Description of the highest precedence layer supplying the key; e.g., its file path.
+    pub origin: Option<String>,
+
+    /// A similarly named field the settings type defines, whether or not the settings supply it.
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.key)?;
+        if let Some(ref origin) = self.origin {
+            write!(f, " (from {origin})")?;
+        }
+        if let Some(ref suggestion) = self.suggestion {
+            write!(f, "; did you mean {suggestion}?")?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn display_unknown_keys(unknown: &[UnknownKey]) -> String {
+    unknown.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+}
+
+/// The keys the settings type ignored while deserializing, with the fields it defines to suggest
+/// in their place.
+#[derive(Debug, Default)]
+pub(crate) struct IgnoredKeys {
+    keys: Vec<String>,
+    fields: BTreeSet<String>,
+}
+
+impl IgnoredKeys {
+    pub(crate) const fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+/// Deserializes the settings, returning the keys the settings type ignored alongside them.
+pub(crate) fn deserialize_tracking_unknown<T: DeserializeOwned>(
+    config: &Config,
+) -> Result<(T, IgnoredKeys), ConfigError> {
+    let value: Value = config.clone().try_deserialize()?;
+    let mut keys = Vec::new();
+    let settings = serde_ignored::deserialize(value.clone(), |path| {
+        let key: Vec<_> = path
+            .to_string()
+            .split('.')
+            .filter(|s| *s != "?")
+            .map(String::from)
+            .collect();
+        keys.push(key.join("."));
+    })?;
+    keys.retain(|key| key != VERSION_KEY);
+
+    let fields = if keys.is_empty() { BTreeSet::new() } else { settings_fields::<T>(value) };
+    Ok((settings, IgnoredKeys { keys, fields }))
+}
+
+/// Describes the unknown keys with the layers supplying them and suggestions drawn from the fields
+/// the settings type defines and the keys it accepted.
+pub(crate) fn describe_unknown_keys(config: &Config, layers: &[LayerSource], ignored: IgnoredKeys) -> Vec<UnknownKey> {
+    let IgnoredKeys { keys: ignored, fields } = ignored;
+    let mut known = BTreeMap::new();
+    if let Ok(table) = config.collect() {
+        flatten_into(None, table, &mut known);
+    }
+    let known: BTreeSet<_> = known
+        .into_keys()
+        .filter(|key| !ignored.iter().any(|i| is_within(key, i)))
+        .chain(fields)
+        .collect();
+
+    ignored
+        .into_iter()
+        .map(|key| {
+            let origin = layers
+                .iter()
+                .rev()
+                .find(|l| {
+                    l.collect_flattened()
+                        .map(|settings| settings.keys().any(|k| is_within(k, &key)))
+                        .unwrap_or(false)
+                })
+                .map(|l| l.origin().to_string());
+            let suggestion = suggest(&key, &known);
+            UnknownKey { key, origin, suggestion }
+        })
+        .collect()
+}
+
+/// The dotted paths of the struct fields the settings type defines, including those the settings
+/// do not supply, recorded by deserializing the settings again. Fields of sections the settings
+/// omit entirely, or within arrays, are not reached.
+fn settings_fields<T: DeserializeOwned>(value: Value) -> BTreeSet<String> {
+    let fields = RefCell::default();
+    let _ignored = T::deserialize(FieldRecorder { value, path: String::new(), fields: &fields });
+    fields.into_inner()
+}
+
+fn join_key(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// Deserializes a settings value as `config::Value` does, recording the fields of each struct
+/// deserialized from it.
+struct FieldRecorder<'a> {
+    value: Value,
+    path: String,
+    fields: &'a RefCell<BTreeSet<String>>,
+}
+
+impl FieldRecorder<'_> {
+    fn visit_table<'de, V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+        let Self { value, path, fields } = self;
+        if matches!(value.kind, ValueKind::Table(_)) {
+            let entries = value.into_table()?.into_iter().collect();
+            visitor.visit_map(RecordingMap { entries, path, fields })
+        } else {
+            value.deserialize_any(visitor)
+        }
+    }
+}
+
+macro_rules! forward_to_value {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+                self.value.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for FieldRecorder<'_> {
+    type Error = ConfigError;
+
+    forward_to_value! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128 deserialize_f32
+        deserialize_f64 deserialize_char deserialize_str deserialize_string deserialize_bytes
+        deserialize_byte_buf deserialize_unit deserialize_seq deserialize_identifier deserialize_ignored_any
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+        self.visit_table(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+        self.visit_table(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self, _name: &'static str, fields: &'static [&'static str], visitor: V,
+    ) -> Result<V::Value, ConfigError> {
+        self.fields
+            .borrow_mut()
+            .extend(fields.iter().map(|field| join_key(&self.path, field)));
+        self.visit_table(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+        if matches!(self.value.kind, ValueKind::Nil) {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self, _name: &'static str, visitor: V,
+    ) -> Result<V::Value, ConfigError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, name: &'static str, visitor: V) -> Result<V::Value, ConfigError> {
+        self.value.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, ConfigError> {
+        self.value.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self, name: &'static str, len: usize, visitor: V,
+    ) -> Result<V::Value, ConfigError> {
+        self.value.deserialize_tuple_struct(name, len, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self, name: &'static str, variants: &'static [&'static str], visitor: V,
+    ) -> Result<V::Value, ConfigError> {
+        self.value.deserialize_enum(name, variants, visitor)
+    }
+}
+
+struct RecordingMap<'a> {
+    entries: VecDeque<(String, Value)>,
+    path: String,
+    fields: &'a RefCell<BTreeSet<String>>,
+}
+
+impl<'de> MapAccess<'de> for RecordingMap<'_> {
+    type Error = ConfigError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, ConfigError> {
+        self.entries
+            .front()
+            .map(|(key, _)| seed.deserialize(StringDeserializer::new(key.clone())))
+            .transpose()
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, ConfigError> {
+        let (key, value) = self
+            .entries
+            .pop_front()
+            .ok_or_else(|| ConfigError::Message("settings value requested without a key".to_string()))?;
+        let path = join_key(&self.path, &key);
+        seed.deserialize(FieldRecorder { value, path, fields: self.fields })
+    }
+}
+
+fn is_within(key: &str, section: &str) -> bool {
+    key == section || key.strip_prefix(section).is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// Suggests the known key, or section, whose name at the unknown key's level is closest to it.
+fn suggest(unknown: &str, known: &BTreeSet<String>) -> Option<String> {
+    let (parent, name) = unknown.rsplit_once('.').map_or(("", unknown), |(p, n)| (p, n));
+    let depth = if parent.is_empty() { 0 } else { parent.split('.').count() };
+
+    let siblings: BTreeSet<String> = known
+        .iter()
+        .filter(|key| parent.is_empty() || key.starts_with(&format!("{parent}.")))
+        .filter_map(|key| key.split('.').nth(depth).map(String::from))
+        .collect();
+
+    siblings
+        .into_iter()
+        .map(|sibling| (strsim::levenshtein(name, &sibling), sibling))
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+        .min()
+        .map(|(_, sibling)| if parent.is_empty() { sibling } else { format!("{parent}.{sibling}") })
+}
+
+#[cfg(test)]
+mod tests {
+    use claim::*;
+    use config::{File, FileFormat};
+    use pretty_assertions::assert_eq;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::Layer;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Database {
+        host: String,
+        port: Option<u16>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct TestSettings {
+        database: Option<Database>,
+        workers: u32,
+    }
+
+    #[test]
+    fn test_describe_unknown_keys() {
+        let layers = vec![
+            LayerSource::new(
+                Layer::Config,
+                "application.yaml",
+                File::from_str(
+                    "database: { host: localhost, prot: 5432 }\nworkers: 4",
+                    FileFormat::Yaml,
+                ),
+            ),
+            LayerSource::new(
+                Layer::Secrets,
+                "secrets.yaml",
+                File::from_str("wrokers: 8\nsettings_version: 2\nextra: { a: 1 }", FileFormat::Yaml),
+            ),
+        ];
+        let mut builder = Config::builder();
+        for layer in &layers {
+            builder = builder.add_source(layer.clone());
+        }
+        let config = assert_ok!(builder.build());
+
+        let (settings, ignored) = assert_ok!(deserialize_tracking_unknown::<TestSettings>(&config));
+        assert_eq!(settings.workers, 4);
+        let mut actual = describe_unknown_keys(&config, &layers, ignored);
+        actual.sort_by(|a, b| a.key.cmp(&b.key));
+        assert_eq!(
+            actual,
+            vec![
+                UnknownKey {
+                    key: "database.prot".to_string(),
+                    origin: Some("application.yaml".to_string()),
+                    suggestion: Some("database.port".to_string()),
+                },
+                UnknownKey {
+                    key: "extra".to_string(),
+                    origin: Some("secrets.yaml".to_string()),
+                    suggestion: None
+                },
+                UnknownKey {
+                    key: "wrokers".to_string(),
+                    origin: Some("secrets.yaml".to_string()),
+                    suggestion: Some("workers".to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            actual[2].to_string(),
+            "wrokers (from secrets.yaml); did you mean workers?"
+        );
+    }
+
+    #[test]
+    fn test_suggest_fields_absent_from_settings() {
+        let config = assert_ok!(Config::builder()
+            .add_source(File::from_str(
+                "databse: { host: localhost }\nworkers: 4",
+                FileFormat::Yaml
+            ))
+            .build());
+        let layers = vec![];
+
+        let (settings, ignored) = assert_ok!(deserialize_tracking_unknown::<TestSettings>(&config));
+        assert_none!(settings.database);
+        let actual = describe_unknown_keys(&config, &layers, ignored);
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0].key, "databse");
+        assert_eq!(actual[0].suggestion.as_deref(), Some("database"));
+    }
+}
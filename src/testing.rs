@@ -0,0 +1,237 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use config::{Config, Map, Source, Value, ValueKind};
+use quickcheck::{Arbitrary, Gen, TestResult};
+
+use crate::layer::{flatten_into, insert_nested, LayerSource, TableSource};
+use crate::merge::{merge, MergeStrategy};
+use crate::{Layer, SettingsError};
+
+/// Dotted keys the generated layers draw their settings from. The keys share sections, so layers
+/// overlap and nest, but no key is nested beneath another.
+pub const KEYS: &[&str] = &[
+    "name",
+    "server.host",
+    "server.port",
+    "database.pool.max",
+    "database.pool.min",
+    "features.beta",
+];
+
+const WORDS: &[&str] = &["alpha", "beta", "gamma", "42", "", "true"];
+
+/// A generated setting value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scalar {
+    Bool(bool),
+    Int(i64),
+    Str(String),
+}
+
+impl Scalar {
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            Self::Bool(b) => ValueKind::Boolean(*b),
+            Self::Int(i) => ValueKind::I64(*i),
+            Self::Str(s) => ValueKind::String(s.clone()),
+        }
+    }
+}
+
+impl Arbitrary for Scalar {
+    fn arbitrary(g: &mut Gen) -> Self {
+        match g.choose(&[0, 1, 2]) {
+            Some(0) => Self::Bool(bool::arbitrary(g)),
+            Some(1) => Self::Int(i64::arbitrary(g)),
+            _ => Self::Str(g.choose(WORDS).copied().unwrap_or_default().to_string()),
+        }
+    }
+}
+
+/// A generated configuration layer, setting a random subset of `KEYS`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArbitraryLayer {
+    pub origin: String,
+    pub settings: BTreeMap<String, Scalar>,
+}
+
+impl ArbitraryLayer {
+    /// The layer's settings as a nested table, each value recording the layer's origin.
+    pub fn table(&self) -> Map<String, Value> {
+        let mut table = Map::new();
+        for (key, value) in &self.settings {
+            insert_nested(&mut table, &self.origin, key, value.kind());
+        }
+        table
+    }
+
+    pub fn value(&self) -> Value {
+        Value::new(Some(&self.origin), ValueKind::Table(self.table()))
+    }
+
+    pub fn source(&self, layer: Layer) -> LayerSource {
+        LayerSource::new(layer, self.origin.clone(), TableSource(self.table()))
+    }
+
+    fn keys(&self) -> BTreeSet<&str> {
+        self.settings.keys().map(String::as_str).collect()
+    }
+}
+
+impl Arbitrary for ArbitraryLayer {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let mut settings = BTreeMap::new();
+        for key in KEYS {
+            if bool::arbitrary(g) {
+                settings.insert(key.to_string(), Scalar::arbitrary(g));
+            }
+        }
+        Self {
+            origin: format!("layer-{}.yaml", u16::arbitrary(g)),
+            settings,
+        }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let origin = self.origin.clone();
+        Box::new(
+            self.settings
+                .clone()
+                .into_iter()
+                .collect::<Vec<_>>()
+                .shrink()
+                .map(move |settings| Self {
+                    origin: origin.clone(),
+                    settings: settings.into_iter().collect(),
+                }),
+        )
+    }
+}
+
+/// A generated stack of configuration layers, listed from lowest to highest precedence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerStack(pub Vec<ArbitraryLayer>);
+
+impl LayerStack {
+    /// The layers as configuration layer sources, lowest precedence first.
+    pub fn layer_sources(&self) -> Vec<LayerSource> {
+        self.0.iter().map(|l| l.source(Layer::Config)).collect()
+    }
+
+    /// The configuration merged from the layers, as `SettingsLoader::load()` merges them.
+    pub fn config(&self) -> Result<Config, SettingsError> {
+        let mut builder = Config::builder();
+        for layer in self.layer_sources() {
+            builder = builder.add_source(layer);
+        }
+        Ok(builder.build()?)
+    }
+
+    /// The highest precedence layer setting the key.
+    pub fn winner(&self, key: &str) -> Option<&ArbitraryLayer> {
+        self.0.iter().rev().find(|l| l.settings.contains_key(key))
+    }
+
+    fn merged_settings(&self) -> Option<BTreeMap<String, Value>> {
+        let config = self.config().ok()?;
+        let mut merged = BTreeMap::new();
+        flatten_into(None, config.collect().ok()?, &mut merged);
+        Some(merged)
+    }
+}
+
+impl Arbitrary for LayerStack {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let len = usize::arbitrary(g) % 6;
+        let layers = (0..len)
+            .map(|i| ArbitraryLayer {
+                origin: format!("layer-{i}.yaml"),
+                ..ArbitraryLayer::arbitrary(g)
+            })
+            .collect();
+        Self(layers)
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(self.0.shrink().map(Self))
+    }
+}
+
+/// Property: each merged setting takes the value of the highest precedence layer setting it, and
+/// no other settings appear.
+pub fn later_layer_wins(stack: LayerStack) -> bool {
+    let Some(merged) = stack.merged_settings() else {
+        return false;
+    };
+
+    let expected: BTreeMap<_, _> = KEYS
+        .iter()
+        .filter_map(|key| stack.winner(key).map(|l| (key.to_string(), l.settings[*key].kind())))
+        .collect();
+    let actual: BTreeMap<_, _> = merged.into_iter().map(|(key, value)| (key, value.kind)).collect();
+    actual == expected
+}
+
+/// Property: each merged setting records the origin of the layer that supplied its value.
+pub fn provenance_matches_winner(stack: LayerStack) -> bool {
+    let Some(merged) = stack.merged_settings() else {
+        return false;
+    };
+
+    merged.iter().all(|(key, value)| {
+        let winner = stack.winner(key).map(|l| l.origin.as_str());
+        winner.is_some() && value.origin() == winner
+    })
+}
+
+/// Property: merging layers is associative, so layers may be grouped, e.g., by a layer cache,
+/// without changing the result.
+pub fn merge_is_associative(a: ArbitraryLayer, b: ArbitraryLayer, c: ArbitraryLayer) -> bool {
+    let deep = MergeStrategy::Deep;
+    let left = merge(merge(a.value(), b.value(), deep), c.value(), deep);
+    let right = merge(a.value(), merge(b.value(), c.value(), deep), deep);
+    as_json(left) == as_json(right)
+}
+
+/// Property: the order of layers setting disjoint keys does not matter.
+pub fn disjoint_merge_is_commutative(a: ArbitraryLayer, b: ArbitraryLayer) -> TestResult {
+    if !a.keys().is_disjoint(&b.keys()) {
+        return TestResult::discard();
+    }
+
+    let deep = MergeStrategy::Deep;
+    let ab = merge(a.value(), b.value(), deep);
+    let ba = merge(b.value(), a.value(), deep);
+    TestResult::from_bool(as_json(ab) == as_json(ba))
+}
+
+fn as_json(value: Value) -> Option<serde_json::Value> {
+    value.try_deserialize().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use super::*;
+
+    #[test]
+    fn test_later_layer_wins() {
+        quickcheck(later_layer_wins as fn(LayerStack) -> bool);
+    }
+
+    #[test]
+    fn test_provenance_matches_winner() {
+        quickcheck(provenance_matches_winner as fn(LayerStack) -> bool);
+    }
+
+    #[test]
+    fn test_merge_is_associative() {
+        quickcheck(merge_is_associative as fn(ArbitraryLayer, ArbitraryLayer, ArbitraryLayer) -> bool);
+    }
+
+    #[test]
+    fn test_disjoint_merge_is_commutative() {
+        quickcheck(disjoint_merge_is_commutative as fn(ArbitraryLayer, ArbitraryLayer) -> TestResult);
+    }
+}
@@ -0,0 +1,488 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use config::Config;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use path_absolutize::*;
+use serde::de::DeserializeOwned;
+
+use crate::diff::{KeyChange, SettingsDiff};
+use crate::layer::{key_pattern_matches, LayerSource};
+use crate::{Layer, LoadingOptions, SettingsError, SettingsLoader};
+
+/// Period over which bursts of file events, such as an editor's save, are collected into a single
+/// reload.
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Name of the symlink Kubernetes atomically swaps to update the files of a mounted `ConfigMap`
+/// or `Secret` volume; the new link is created as `..data_tmp` and renamed over `..data`.
+const KUBERNETES_DATA_LINK: &str = "..data";
+
+/// Coordinates a safe restart when watched settings files change restart-required settings.
+///
+/// Restart-required settings are declared by `LoadingOptions::restart_required_keys()`. The
+/// application may, e.g., drain connections, notify systemd, or signal its supervisor.
+pub trait RestartCoordinator: Send + Sync {
+    /// Invoked with the restart-required settings that changed. Reloaded settings are still
+    /// delivered via `SettingsWatcher::updates()`.
+    fn restart_required(&self, changes: &[KeyChange]);
+}
+
+/// Notified of the settings changed by each reload of watched settings files; e.g.,
+/// `log_filter::LogFilterReloader` updates the logging filter. Registered by
+/// `LoadingOptions::change_listeners()`.
+pub trait SettingsChangeListener: Send + Sync {
+    /// Invoked with the differences from the previously loaded settings and the reloaded
+    /// configuration.
+    fn settings_changed(&self, diff: &SettingsDiff, config: &Config);
+}
+
+/// Watches the settings files resolved for a load, delivering freshly loaded settings each time
+/// they change. Created by `SettingsLoader::watch()`.
+///
+/// The directories holding the resolved files are watched, so files replaced by editors, or by the
+/// `..data` symlink swap of a Kubernetes `ConfigMap` volume, are picked up. Environment
+/// configuration files searched for but not yet created are watched for as well, and the watched
+/// files are resolved again after each reload. Watching stops when the watcher is dropped.
+pub struct SettingsWatcher<S> {
+    dirs: Arc<Mutex<WatchedDirs>>,
+    updates: Receiver<Result<S, SettingsError>>,
+}
+
+impl<S> fmt::Debug for SettingsWatcher<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SettingsWatcher")
+            .field("watched", &self.watched())
+            .finish()
+    }
+}
+
+impl<S> SettingsWatcher<S>
+where
+    S: SettingsLoader + DeserializeOwned + Send + 'static,
+    S::Options: Send + 'static,
+{
+    pub(crate) fn new(options: S::Options) -> Result<Self, SettingsError> {
+        let layers = S::load_layers(&options)?;
+        let files = WatchedFiles::resolve::<S>(&options, &layers)?;
+
+        let (events_tx, events_rx) = mpsc::channel();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let _ignored = events_tx.send(event);
+        })?;
+        let mut dirs = WatchedDirs { watcher, watched: BTreeSet::new() };
+        dirs.watch(files.dirs());
+        tracing::info!(watched=?dirs.watched, ?files, "watching settings files for changes");
+
+        // The reloading thread only holds a weak reference, so dropping the `SettingsWatcher` drops
+        // the notify watcher, which disconnects the events channel and ends the thread.
+        let dirs = Arc::new(Mutex::new(dirs));
+        let reload_dirs = Arc::downgrade(&dirs);
+
+        let config = S::build_config(&options, layers)?;
+        let (updates_tx, updates) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("settings-watcher".to_string())
+            .spawn(move || Self::reload_on_change(&options, config, files, &reload_dirs, &events_rx, &updates_tx))?;
+
+        Ok(Self { dirs, updates })
+    }
+
+    fn reload_on_change(
+        options: &S::Options, mut config: Config, mut files: WatchedFiles, dirs: &Weak<Mutex<WatchedDirs>>,
+        events: &Receiver<notify::Result<Event>>, updates: &Sender<Result<S, SettingsError>>,
+    ) {
+        while let Ok(event) = events.recv() {
+            let is_relevant = match event {
+                Ok(event) => !event.kind.is_access() && event.paths.iter().any(|p| files.is_relevant(p)),
+                Err(err) => {
+                    tracing::warn!(error=%err, "settings watch error");
+                    false
+                },
+            };
+            if !is_relevant {
+                continue;
+            }
+
+            loop {
+                match events.recv_timeout(RELOAD_DEBOUNCE) {
+                    Ok(_) => continue,
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+
+            tracing::info!("settings files changed; reloading settings");
+            let reloaded = S::load_layers(options).and_then(|layers| {
+                Self::rewatch(options, &mut files, dirs, &layers);
+                let reloaded = S::build_config(options, layers.clone())?;
+                let diff = SettingsDiff::between_configs(&config, &reloaded)?;
+                let settings = S::deserialize_config(options, reloaded.clone(), &|| layers.clone())?;
+                Self::coordinate_restart(options, &diff);
+                Self::audit(options, &diff, &reloaded, &layers);
+                if !diff.changes().is_empty() {
+                    for listener in options.change_listeners() {
+                        listener.settings_changed(&diff, &reloaded);
+                    }
+                }
+                config = reloaded;
+                Ok(settings)
+            });
+            if updates.send(reloaded).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Resolves the watched files again from the reloaded layers, watching any new directories.
+    fn rewatch(
+        options: &S::Options, files: &mut WatchedFiles, dirs: &Weak<Mutex<WatchedDirs>>, layers: &[LayerSource],
+    ) {
+        match WatchedFiles::resolve::<S>(options, layers) {
+            Ok(resolved) => *files = resolved,
+            Err(err) => tracing::warn!(error=%err, "failed to resolve settings files to watch"),
+        }
+
+        if let Some(dirs) = dirs.upgrade() {
+            if let Ok(mut dirs) = dirs.lock() {
+                dirs.watch(files.dirs());
+            }
+        }
+    }
+
+    fn coordinate_restart(options: &S::Options, diff: &SettingsDiff) {
+        let Some(coordinator) = options.restart_coordinator() else {
+            return;
+        };
+
+        let patterns = options.restart_required_keys();
+        let changes: Vec<_> = diff
+            .changes()
+            .iter()
+            .filter(|c| patterns.iter().any(|p| key_pattern_matches(p, &c.key)))
+            .cloned()
+            .collect();
+        if !changes.is_empty() {
+            let keys: Vec<_> = changes.iter().map(|c| c.key.as_str()).collect();
+            tracing::warn!(?keys, "changed settings require a restart");
+            coordinator.restart_required(&changes);
+        }
+    }
+
+    fn audit(options: &S::Options, diff: &SettingsDiff, reloaded: &Config, layers: &[LayerSource]) {
+        if let Some(audit_log) = options.audit_log() {
+            if let Err(err) = audit_log.record(options.clock().now(), diff, reloaded, layers) {
+                tracing::warn!(error=%err, path=?audit_log.path(), "failed to record settings changes in audit log");
+            }
+        }
+    }
+}
+
+impl<S> SettingsWatcher<S> {
+    /// Directories watched for changes to the settings files.
+    pub fn watched(&self) -> Vec<PathBuf> {
+        self.dirs
+            .lock()
+            .map(|dirs| dirs.watched.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Settings reloaded after each change, or the error if the changed files failed to load.
+    pub const fn updates(&self) -> &Receiver<Result<S, SettingsError>> {
+        &self.updates
+    }
+}
+
+/// The settings files whose changes trigger a reload.
+#[derive(Debug, Default)]
+struct WatchedFiles {
+    /// Settings files resolved for the load.
+    files: BTreeSet<PathBuf>,
+
+    /// Environment configuration files searched for but not found, without their extensions.
+    pending: BTreeSet<PathBuf>,
+}
+
+impl WatchedFiles {
+    fn resolve<S: SettingsLoader>(options: &S::Options, layers: &[LayerSource]) -> Result<Self, SettingsError> {
+        let fs = options.config_fs();
+        let mut files = BTreeSet::new();
+        for layer in layers {
+            let path = Path::new(layer.origin());
+            if layer.layer() != Layer::EnvironmentVariables && fs.is_file(path) {
+                files.insert(path.absolutize()?.into_owned());
+            }
+        }
+
+        let mut pending = BTreeSet::new();
+        for path in S::missing_environment_files(options)? {
+            pending.insert(path.absolutize()?.into_owned());
+        }
+
+        Ok(Self { files, pending })
+    }
+
+    fn dirs(&self) -> BTreeSet<PathBuf> {
+        self.files
+            .iter()
+            .chain(&self.pending)
+            .filter_map(|f| f.parent().map(PathBuf::from))
+            .collect()
+    }
+
+    fn is_relevant(&self, path: &Path) -> bool {
+        let is_data_link = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with(KUBERNETES_DATA_LINK));
+
+        self.files.contains(path)
+            || self.pending.contains(&path.with_extension(""))
+            || (is_data_link && path.parent().is_some_and(|dir| self.dirs().contains(dir)))
+    }
+}
+
+/// The directories watched for changes to the settings files.
+struct WatchedDirs {
+    watcher: RecommendedWatcher,
+    watched: BTreeSet<PathBuf>,
+}
+
+impl WatchedDirs {
+    fn watch(&mut self, dirs: BTreeSet<PathBuf>) {
+        for dir in dirs {
+            if self.watched.contains(&dir) {
+                continue;
+            }
+
+            match self.watcher.watch(&dir, RecursiveMode::NonRecursive) {
+                Ok(()) => {
+                    tracing::debug!(?dir, "watching settings directory for changes");
+                    self.watched.insert(dir);
+                },
+                Err(err) => tracing::warn!(?dir, error=%err, "cannot watch settings directory"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+
+    use claim::*;
+    use pretty_assertions::assert_eq;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::{Environment, LoadingOptions};
+
+    #[derive(Debug, Default)]
+    struct RecordingCoordinator(Mutex<Vec<Vec<String>>>);
+
+    impl RestartCoordinator for RecordingCoordinator {
+        fn restart_required(&self, changes: &[KeyChange]) {
+            let keys = changes.iter().map(|c| c.key.clone()).collect();
+            self.0.lock().unwrap().push(keys);
+        }
+    }
+
+    #[derive(Debug)]
+    struct TestOptions {
+        dir: PathBuf,
+        coordinator: Option<Arc<RecordingCoordinator>>,
+        strict: bool,
+        environment: Option<Environment>,
+    }
+
+    impl TestOptions {
+        const fn new(dir: PathBuf) -> Self {
+            Self {
+                dir,
+                coordinator: None,
+                strict: false,
+                environment: None,
+            }
+        }
+    }
+
+    impl LoadingOptions for TestOptions {
+        type Error = SettingsError;
+
+        fn config_path(&self) -> Option<PathBuf> {
+            self.environment.is_none().then(|| self.dir.join("application.yaml"))
+        }
+
+        fn secrets_path(&self) -> Option<PathBuf> {
+            None
+        }
+
+        fn implicit_search_paths(&self) -> Vec<PathBuf> {
+            self.environment.iter().map(|_| self.dir.clone()).collect()
+        }
+
+        fn environment_override(&self) -> Option<Environment> {
+            self.environment.clone()
+        }
+
+        fn restart_required_keys(&self) -> Vec<String> {
+            vec!["server".to_string()]
+        }
+
+        fn restart_coordinator(&self) -> Option<Arc<dyn RestartCoordinator>> {
+            self.coordinator.clone().map(|c| c as Arc<dyn RestartCoordinator>)
+        }
+
+        fn strict(&self) -> bool {
+            self.strict
+        }
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct TestSettings {
+        foo: String,
+        #[serde(default)]
+        server: Option<u16>,
+    }
+
+    impl SettingsLoader for TestSettings {
+        type Options = TestOptions;
+
+        fn environment_prefix() -> &'static str {
+            "watch_test"
+        }
+    }
+
+    #[test]
+    fn test_watch_reloads_changed_settings() {
+        let dir = std::env::temp_dir().join(format!("settings-watch-{}", std::process::id()));
+        assert_ok!(fs::create_dir_all(&dir));
+        let dir = assert_ok!(dir.canonicalize());
+        assert_ok!(fs::write(dir.join("application.yaml"), "foo: before"));
+
+        let watcher = assert_ok!(TestSettings::watch(TestOptions::new(dir.clone())));
+        assert_eq!(watcher.watched(), std::slice::from_ref(&dir));
+
+        assert_ok!(fs::write(dir.join("unrelated.txt"), "ignored"));
+        assert_ok!(fs::write(dir.join("application.yaml"), "foo: after"));
+        let actual = assert_ok!(assert_ok!(watcher.updates().recv_timeout(Duration::from_secs(10))));
+        assert_eq!(actual, TestSettings { foo: "after".to_string(), server: None });
+
+        drop(watcher);
+        let _ignored = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_watch_coordinates_restart() {
+        let dir = std::env::temp_dir().join(format!("settings-restart-{}", std::process::id()));
+        assert_ok!(fs::create_dir_all(&dir));
+        let dir = assert_ok!(dir.canonicalize());
+        assert_ok!(fs::write(dir.join("application.yaml"), "foo: before\nserver: 8000"));
+
+        let coordinator = Arc::new(RecordingCoordinator::default());
+        let options = TestOptions {
+            dir: dir.clone(),
+            coordinator: Some(coordinator.clone()),
+            ..TestOptions::new(dir.clone())
+        };
+        let watcher = assert_ok!(TestSettings::watch(options));
+
+        assert_ok!(fs::write(dir.join("application.yaml"), "foo: after\nserver: 8000"));
+        assert_ok!(assert_ok!(watcher.updates().recv_timeout(Duration::from_secs(10))));
+        assert!(coordinator.0.lock().unwrap().is_empty());
+
+        assert_ok!(fs::write(dir.join("application.yaml"), "foo: after\nserver: 9000"));
+        let actual = assert_ok!(assert_ok!(watcher.updates().recv_timeout(Duration::from_secs(10))));
+        assert_eq!(actual.server, Some(9000));
+        assert_eq!(*coordinator.0.lock().unwrap(), vec![vec!["server".to_string()]]);
+
+        drop(watcher);
+        let _ignored = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_watch_reload_rejects_unknown_keys_when_strict() {
+        let dir = std::env::temp_dir().join(format!("settings-watch-strict-{}", std::process::id()));
+        assert_ok!(fs::create_dir_all(&dir));
+        let dir = assert_ok!(dir.canonicalize());
+        assert_ok!(fs::write(dir.join("application.yaml"), "foo: before"));
+
+        let options = TestOptions { strict: true, ..TestOptions::new(dir.clone()) };
+        let watcher = assert_ok!(TestSettings::watch(options));
+
+        assert_ok!(fs::write(dir.join("application.yaml"), "foo: after\nservr: 9000"));
+        let err = assert_err!(assert_ok!(watcher.updates().recv_timeout(Duration::from_secs(10))));
+        let SettingsError::UnknownKeys(ref unknown) = err else {
+            panic!("expected unknown keys but got: {err:?}");
+        };
+        assert_eq!(unknown.len(), 1);
+        assert_eq!(unknown[0].key, "servr");
+        assert_eq!(unknown[0].suggestion.as_deref(), Some("server"));
+
+        assert_ok!(fs::write(dir.join("application.yaml"), "foo: after\nserver: 9000"));
+        let actual = assert_ok!(assert_ok!(watcher.updates().recv_timeout(Duration::from_secs(10))));
+        assert_eq!(actual, TestSettings { foo: "after".to_string(), server: Some(9000) });
+
+        drop(watcher);
+        let _ignored = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_watch_reloads_environment_file_created_after_watching() {
+        let dir = std::env::temp_dir().join(format!("settings-watch-env-{}", std::process::id()));
+        assert_ok!(fs::create_dir_all(&dir));
+        let dir = assert_ok!(dir.canonicalize());
+        assert_ok!(fs::write(dir.join("application.yaml"), "foo: before"));
+
+        let options = TestOptions {
+            environment: Some(Environment::from("production")),
+            ..TestOptions::new(dir.clone())
+        };
+        let watcher = assert_ok!(TestSettings::watch(options));
+
+        assert_ok!(fs::write(dir.join("production.yaml"), "foo: production"));
+        let actual = assert_ok!(assert_ok!(watcher.updates().recv_timeout(Duration::from_secs(10))));
+        assert_eq!(actual, TestSettings { foo: "production".to_string(), server: None });
+
+        assert_ok!(fs::write(dir.join("production.yaml"), "foo: production\nserver: 9000"));
+        let actual = assert_ok!(assert_ok!(watcher.updates().recv_timeout(Duration::from_secs(10))));
+        assert_eq!(
+            actual,
+            TestSettings { foo: "production".to_string(), server: Some(9000) }
+        );
+
+        drop(watcher);
+        let _ignored = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_watch_reloads_configmap_data_swap() {
+        use std::os::unix::fs::symlink;
+
+        let dir = std::env::temp_dir().join(format!("settings-watch-configmap-{}", std::process::id()));
+        assert_ok!(fs::create_dir_all(dir.join("..v1")));
+        let dir = assert_ok!(dir.canonicalize());
+        assert_ok!(fs::write(dir.join("..v1/application.yaml"), "foo: before"));
+        assert_ok!(symlink("..v1", dir.join("..data")));
+        assert_ok!(symlink("..data/application.yaml", dir.join("application.yaml")));
+
+        let watcher = assert_ok!(TestSettings::watch(TestOptions::new(dir.clone())));
+        assert_eq!(watcher.watched(), vec![dir.clone()]);
+
+        assert_ok!(fs::create_dir_all(dir.join("..v2")));
+        assert_ok!(fs::write(dir.join("..v2/application.yaml"), "foo: after"));
+        assert_ok!(symlink("..v2", dir.join("..data_tmp")));
+        assert_ok!(fs::rename(dir.join("..data_tmp"), dir.join("..data")));
+        let actual = assert_ok!(assert_ok!(watcher.updates().recv_timeout(Duration::from_secs(10))));
+        assert_eq!(actual, TestSettings { foo: "after".to_string(), server: None });
+
+        drop(watcher);
+        let _ignored = fs::remove_dir_all(&dir);
+    }
+}